@@ -3,11 +3,15 @@
 //! Demonstrates melior-build's multi-file support with auto-detection:
 //! - `BrilDialect.td` - Dialect definition
 //! - `BrilTypes.td` - Custom type definitions (PtrType)
+//! - `BrilAttrs.td` - Custom attribute definitions (LabelAttr)
 //! - `BrilOps.td` - Operation definitions (including FuncOp with
 //!   FunctionOpInterface)
 //!
 //! Also demonstrates custom C++ implementation files for verifiers:
 //! - `BrilOpsImpl.cpp` - Custom verifiers for LoadOp and StoreOp
+//!
+//! Typed operation builders such as `build_add` are generated as well, along
+//! with the `context_with_dialect` helper.
 
 use melior_build::DialectBuilder;
 
@@ -15,10 +19,13 @@ fn main() {
     DialectBuilder::new("bril")
         .td_file("src/dialect/bril/BrilDialect.td")
         .td_file("src/dialect/bril/BrilTypes.td")
+        .td_file("src/dialect/bril/BrilAttrs.td")
         .td_file("src/dialect/bril/BrilOps.td")
         .include_dir("src/dialect")
         .cpp_namespace("mlir::bril")
         .cpp_file("src/dialect/BrilOpsImpl.cpp")
+        .generate_op_builders(true)
+        .emit_context_helper(true)
         .build()
         .expect("Failed to build bril dialect");
 }
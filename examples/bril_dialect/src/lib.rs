@@ -17,6 +17,7 @@
 //! └── bril/
 //!     ├── BrilDialect.td       # Split: dialect definition only
 //!     ├── BrilTypes.td         # Split: type definitions
+//!     ├── BrilAttrs.td         # Split: attribute definitions
 //!     └── BrilOps.td           # Split: operation definitions
 //! ```
 //!
@@ -28,13 +29,15 @@
 //!
 //! - **`melior::dialect!`** macro uses a Rust-based TableGen parser that runs
 //!   at compile time. This parser has limitations with include path resolution,
-//!   so it works most reliably with a single combined TD file.
+//!   so it works most reliably with a single combined TD file. Such a file can
+//!   also be produced from the split files with
+//!   `melior_build::tblgen::flatten_includes`.
 //!
 //! Both tools serve different purposes:
 //! - `melior-build` generates C++ code for dialect registration with MLIR
 //! - `melior::dialect!` generates Rust wrapper types for operations
 
-use melior::{Context, dialect::DialectRegistry, utility::register_all_dialects};
+use melior::Context;
 
 // Generate Rust operation wrappers from TableGen using the dialect! macro.
 //
@@ -48,34 +51,33 @@ melior::dialect! {
 }
 
 // Include the generated registration code from melior-build.
-// This provides: dialect_handle(), register(), load(), insert_into_registry()
+// This provides: dialect_handle(), register(), load(), insert_into_registry(),
+// the OPERATIONS list, verify(), context_with_dialect(), typed constructors
+// for custom types and attributes such as ptr_type() and label_attr(),
+// typed operation builders such as build_add(), and operation types such as
+// AddOperation, with region helpers such as FuncOperation::add_entry_block()
 include!(concat!(env!("OUT_DIR"), "/bril_register.rs"));
 
 /// Create a context with the Bril dialect loaded.
+///
+/// This is the generated `context_with_dialect`, which also registers all
+/// built-in dialects.
 pub fn create_context_with_bril() -> Context {
-    let context = Context::new();
-
-    // Register all built-in dialects
-    let registry = DialectRegistry::new();
-    register_all_dialects(&registry);
-    context.append_dialect_registry(&registry);
-    context.load_all_available_dialects();
-
-    // Load the Bril dialect
-    load(&context);
-
-    context
+    context_with_dialect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use melior::ir::{
-        Attribute, Block, BlockLike, Identifier, Location, Module, Region, RegionLike, Type,
-        TypeLike,
-        attribute::{IntegerAttribute, TypeAttribute},
-        operation::{OperationBuilder, OperationLike},
-        r#type::{FunctionType, IntegerType},
+    use melior::{
+        dialect::DialectRegistry,
+        ir::{
+            Attribute, Block, BlockLike, Identifier, Location, Module, Operation, Region,
+            RegionLike, Type, TypeLike,
+            attribute::{IntegerAttribute, TypeAttribute},
+            operation::{OperationBuilder, OperationLike},
+            r#type::{FunctionType, IntegerType},
+        },
     };
 
     // ==========================================================================
@@ -136,6 +138,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bril_generated_operations_registered() {
+        let context = create_context_with_bril();
+
+        assert_eq!(OPERATIONS.len(), 27);
+        for operation in OPERATIONS {
+            assert!(
+                context.is_registered_operation(operation),
+                "{operation} should be registered"
+            );
+        }
+    }
+
     #[test]
     fn test_unregistered_operation_not_found() {
         let context = create_context_with_bril();
@@ -204,6 +219,186 @@ mod tests {
         assert_eq!(add_op.result_count(), 1);
     }
 
+    #[test]
+    fn test_verify_operation() {
+        let context = create_context_with_bril();
+        let location = Location::unknown(&context);
+        let i64_type = IntegerType::new(&context, 64).into();
+
+        let block = Block::new(&[(i64_type, location), (i64_type, location)]);
+        let lhs = block.argument(0).unwrap().into();
+        let rhs = block.argument(1).unwrap().into();
+
+        let add_op = OperationBuilder::new("bril.add", location)
+            .add_operands(&[lhs, rhs])
+            .add_results(&[i64_type])
+            .build()
+            .unwrap();
+        assert!(verify(&add_op));
+
+        // bril.add takes exactly two operands
+        let invalid_add_op = OperationBuilder::new("bril.add", location)
+            .add_operands(&[lhs])
+            .add_results(&[i64_type])
+            .build()
+            .unwrap();
+        assert!(!verify(&invalid_add_op));
+    }
+
+    #[test]
+    fn test_context_with_dialect() {
+        let context = context_with_dialect();
+        assert!(is_loaded(&context));
+        assert!(context.is_registered_operation("bril.add"));
+        assert!(context.is_registered_operation("func.func"));
+    }
+
+    #[test]
+    fn test_is_loaded() {
+        let context = Context::new();
+        assert!(!is_loaded(&context));
+        assert!(loaded_dialect(&context).is_none());
+
+        load(&context).unwrap();
+        assert!(is_loaded(&context));
+        assert_eq!(
+            loaded_dialect(&context).unwrap().namespace().unwrap(),
+            "bril"
+        );
+    }
+
+    #[test]
+    fn test_load_checked() {
+        let context = Context::new();
+        let dialect = load(&context).unwrap();
+        assert_eq!(dialect.namespace().unwrap(), "bril");
+        assert!(context.is_registered_operation(OPERATIONS[0]));
+
+        let context = Context::new();
+        load_unchecked(&context);
+        assert!(is_loaded(&context));
+    }
+
+    #[test]
+    fn test_load_all() {
+        let context = Context::new();
+        load_all(&context);
+        assert!(is_loaded(&context));
+
+        let registry = DialectRegistry::new();
+        insert_all_into_registry(&registry);
+        let context = Context::new();
+        context.append_dialect_registry(&registry);
+        assert_eq!(context.registered_dialect_count(), 2);
+    }
+
+    #[test]
+    fn test_generated_op_builders() {
+        let context = create_context_with_bril();
+        let location = Location::unknown(&context);
+        let i64_type = IntegerType::new(&context, 64).into();
+
+        let const_op = build_const(
+            &context,
+            location,
+            IntegerAttribute::new(i64_type, 42).into(),
+            i64_type,
+        );
+        assert!(const_op.verify());
+
+        let block = Block::new(&[(i64_type, location), (i64_type, location)]);
+        let add_op = build_add(
+            &context,
+            location,
+            block.argument(0).unwrap().into(),
+            block.argument(1).unwrap().into(),
+            i64_type,
+        );
+        assert!(add_op.verify());
+        assert_eq!(add_op.name().as_string_ref().as_str().unwrap(), "bril.add");
+        assert_eq!(add_op.operand_count(), 2);
+        assert_eq!(add_op.result_count(), 1);
+    }
+
+    #[test]
+    fn test_generated_op_types() {
+        let context = create_context_with_bril();
+        let location = Location::unknown(&context);
+        let i64_type = IntegerType::new(&context, 64).into();
+
+        let block = Block::new(&[(i64_type, location), (i64_type, location)]);
+        let add_op = AddOperation::try_from(build_add(
+            &context,
+            location,
+            block.argument(0).unwrap().into(),
+            block.argument(1).unwrap().into(),
+            i64_type,
+        ))
+        .unwrap();
+        assert_eq!(AddOperation::NAME, "bril.add");
+        assert_eq!(add_op.operand_count(), 2);
+        assert!(Operation::from(add_op).verify());
+
+        let const_op = build_const(
+            &context,
+            location,
+            IntegerAttribute::new(i64_type, 42).into(),
+            i64_type,
+        );
+        let error = AddOperation::try_from(const_op).unwrap_err();
+        assert_eq!(error.expected, "bril.add");
+        assert_eq!(
+            error.to_string(),
+            "expected a 'bril.add' operation, found 'bril.const'"
+        );
+        assert!(ConstOperation::try_from(error.operation).is_ok());
+    }
+
+    #[test]
+    fn test_generated_region_accessors() {
+        let context = create_context_with_bril();
+        let location = Location::unknown(&context);
+        let i64_type: Type = IntegerType::new(&context, 64).into();
+        let func_type = FunctionType::new(&context, &[i64_type, i64_type], &[i64_type]);
+
+        let func_op = FuncOperation::try_from(
+            OperationBuilder::new("bril.func", location)
+                .add_attributes(&[
+                    (
+                        Identifier::new(&context, "sym_name"),
+                        Attribute::parse(&context, "\"add_two\"").unwrap(),
+                    ),
+                    (
+                        Identifier::new(&context, "function_type"),
+                        TypeAttribute::new(func_type.into()).into(),
+                    ),
+                ])
+                .add_regions([Region::new()])
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let block = func_op.add_entry_block(&[i64_type, i64_type]);
+        let add_op = block.append_operation(build_add(
+            &context,
+            location,
+            block.argument(0).unwrap().into(),
+            block.argument(1).unwrap().into(),
+            i64_type,
+        ));
+        block.append_operation(
+            OperationBuilder::new("bril.ret", location)
+                .add_operands(&[add_op.result(0).unwrap().into()])
+                .build()
+                .unwrap(),
+        );
+
+        assert_eq!(func_op.body_region().first_block(), Some(block));
+        assert_eq!(block.argument_count(), 2);
+        assert!(func_op.as_operation().verify());
+    }
+
     #[test]
     fn test_create_nop_operation() {
         let context = create_context_with_bril();
@@ -232,6 +427,33 @@ mod tests {
         assert!(!ptr_type.is_index());
     }
 
+    #[test]
+    fn test_ptr_type_constructor() {
+        let context = create_context_with_bril();
+
+        let ptr = ptr_type(&context, IntegerType::new(&context, 64).into());
+
+        assert_eq!(Some(ptr), Type::parse(&context, "!bril.ptr<i64>"));
+        assert_eq!(ptr.to_string(), "!bril.ptr<i64>");
+    }
+
+    // ==========================================================================
+    // Custom Attribute Tests (#bril.label<"name">)
+    // ==========================================================================
+
+    #[test]
+    fn test_label_attr_constructor() {
+        let context = create_context_with_bril();
+
+        let label = label_attr(&context, "entry");
+
+        assert_eq!(
+            Some(label),
+            Attribute::parse(&context, "#bril.label<\"entry\">")
+        );
+        assert_eq!(label.to_string(), "#bril.label<\"entry\">");
+    }
+
     // ==========================================================================
     // dialect! Macro Output Tests
     // ==========================================================================
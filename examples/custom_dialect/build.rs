@@ -1,17 +1,16 @@
 //! Build script for the custom dialect example.
 //!
 //! This demonstrates how to use melior-build to compile a custom MLIR dialect
-//! from TableGen definitions.
+//! from TableGen definitions described in a dialect manifest.
 
 use melior_build::DialectBuilder;
 
 fn main() {
-    // Build the math_ext dialect from TableGen
-    DialectBuilder::new("math_ext")
-        .td_file("src/dialect/MathDialect.td")
-        .cpp_namespace("mlir::math_ext")
-        .build()
-        .expect("Failed to build math_ext dialect");
-
-    println!("cargo:rerun-if-changed=src/dialect/MathDialect.td");
+    // Build the math_ext dialect described in dialect.toml, and flatten its
+    // TD files into the single file the dialect! macro in src/lib.rs reads
+    let builder = DialectBuilder::from_manifest("dialect.toml").expect("Invalid dialect manifest");
+    builder
+        .write_flattened_td("src/dialect/math_ext.flat.td")
+        .expect("Failed to flatten math_ext TD files");
+    builder.build().expect("Failed to build math_ext dialect");
 }
@@ -6,10 +6,11 @@
 use melior::{Context, dialect::DialectRegistry, utility::register_all_dialects};
 
 // Generate Rust operation wrappers from TableGen using the dialect! macro
-// Note: paths are relative to the workspace root
+// Note: the path is relative to the workspace root, and build.rs writes the
+// file from the `td_files` of dialect.toml
 melior::dialect! {
     name: "math_ext",
-    files: ["examples/custom_dialect/src/dialect/MathDialect.td"],
+    td_file: "examples/custom_dialect/src/dialect/math_ext.flat.td",
 }
 
 // Include the generated registration code from melior-build.
@@ -28,7 +29,7 @@ pub fn create_context_with_math_ext() -> Context {
 
     // Load our custom dialect (function from included file)
     // Using load() instead of register() ensures the dialect is fully initialized
-    load(&context);
+    load(&context).expect("math_ext operations should be registered");
 
     context
 }
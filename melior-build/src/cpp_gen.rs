@@ -1,6 +1,10 @@
 //! C++ code generation for dialect registration.
 
-use crate::{Error, tblgen::GeneratedFiles, to_class_name};
+use crate::{
+    CodegenOptions, Error,
+    tblgen::{GeneratedFiles, ParameterKind, TypeParameter},
+    to_class_name,
+};
 use std::{fs, path::Path};
 
 /// Generate a simple include line.
@@ -13,17 +17,165 @@ fn guarded_include(prefix: &str, stem: &str, suffix: &str, ext: &str, define: &s
     format!("#define {define}\n#include \"{prefix}{stem}{suffix}.{ext}.inc\"")
 }
 
-/// Generate registration code (addTypes/addAttributes).
-fn registration(prefix: &str, stem: &str, suffix: &str, method: &str, list_define: &str) -> String {
+/// Generate a registration hook (addTypes/addAttributes) for use inside the
+/// dialect's `initialize()`, and invoke it.
+///
+/// The hook is a lambda capturing the dialect, since the registration methods
+/// are protected members of `mlir::Dialect`.
+fn registration(
+    prefix: &str,
+    stem: &str,
+    suffix: &str,
+    method: &str,
+    list_define: &str,
+    hook: &str,
+) -> String {
     format!(
         r#"
-    {method}<
+
+    // Register the {suffix} generated from {stem}.td
+    auto {hook} = [this] {{
+        {method}<
 #define {list_define}
 #include "{prefix}{stem}{suffix}.cpp.inc"
-    >();"#
+        >();
+    }};
+    {hook}();"#
+    )
+}
+
+/// The C signature of a custom type's or attribute's constructor, e.g.,
+/// `MlirType mlirBrilPtrTypeGet(MlirContext context, MlirType pointeeType)`.
+///
+/// `value` is the kind of the constructed value, "Type" or "Attribute".
+fn constructor_signature(
+    class_name: &str,
+    value: &str,
+    def_class_name: &str,
+    parameters: &[TypeParameter],
+) -> String {
+    let parameters = std::iter::once("MlirContext context".to_string())
+        .chain(parameters.iter().map(|parameter| {
+            let c_type = match parameter.kind {
+                ParameterKind::Type => "MlirType",
+                ParameterKind::Attribute => "MlirAttribute",
+                ParameterKind::String => "MlirStringRef",
+                ParameterKind::Scalar { c_type, .. } => c_type,
+            };
+            format!("{c_type} {}", parameter.name)
+        }))
+        .collect::<Vec<_>>();
+
+    format!(
+        "Mlir{value} mlir{class_name}{def_class_name}Get({})",
+        parameters.join(", ")
+    )
+}
+
+/// The C signature of the function populating a pattern set with the
+/// dialect's rewrite patterns.
+fn canonicalization_signature(class_name: &str) -> String {
+    format!("void mlir{class_name}PopulateCanonicalizationPatterns(MlirRewritePatternSet patterns)")
+}
+
+/// The C signature of the function verifying an operation, including the
+/// custom verifiers of the dialect's operations.
+fn verify_signature(class_name: &str) -> String {
+    format!("bool mlir{class_name}VerifyOperation(MlirOperation operation)")
+}
+
+/// The C signature of the function adding the bytecode interface to the
+/// dialect.
+fn bytecode_signature(class_name: &str) -> String {
+    format!("void mlir{class_name}RegisterBytecodeInterface(MlirContext context)")
+}
+
+/// The name of the symbol keeping the C API library linked in.
+fn link_anchor_symbol(class_name: &str) -> String {
+    format!("mlir{class_name}LinkAnchor")
+}
+
+/// The C signature of the function registering the dialect's passes.
+fn passes_signature(class_name: &str) -> String {
+    format!("void mlirRegister{class_name}Passes(void)")
+}
+
+/// The C signature of the function registering the dialect's translation to
+/// LLVM IR.
+fn llvm_translation_signature(class_name: &str) -> String {
+    format!("void mlir{class_name}RegisterLLVMTranslations(MlirContext context)")
+}
+
+/// Generate an `extern "C"` constructor wrapping `{class}::get` for a custom
+/// type or attribute, e.g., `mlirBrilPtrTypeGet(MlirContext, MlirType)`.
+fn constructor(
+    class_name: &str,
+    cpp_namespace: &str,
+    value: &str,
+    def_class_name: &str,
+    parameters: &[TypeParameter],
+) -> String {
+    let arguments = std::iter::once("unwrap(context)".to_string())
+        .chain(parameters.iter().map(|parameter| {
+            let name = &parameter.name;
+            match parameter.kind {
+                ParameterKind::Type | ParameterKind::Attribute | ParameterKind::String => {
+                    format!("unwrap({name})")
+                }
+                ParameterKind::Scalar { .. } => name.clone(),
+            }
+        }))
+        .collect::<Vec<_>>();
+
+    format!(
+        r#"
+{signature} {{
+    return wrap({cpp_namespace}::{def_class_name}::get({arguments}));
+}}
+"#,
+        signature = constructor_signature(class_name, value, def_class_name, parameters),
+        arguments = arguments.join(", "),
     )
 }
 
+/// Headers shared by every generated registration file.
+const PREAMBLE: &str = r#"// Auto-generated by melior-build. Do not edit.
+
+// MLIR C API headers
+#include "mlir-c/IR.h"
+#include "mlir/CAPI/Registration.h"
+#include "mlir/CAPI/Wrap.h"
+
+// Core MLIR headers required for dialect/operation definitions
+#include "mlir/IR/Dialect.h"
+#include "mlir/IR/DialectImplementation.h"
+#include "mlir/IR/OpImplementation.h"
+#include "mlir/IR/Builders.h"
+#include "mlir/IR/BuiltinTypes.h"
+#include "mlir/IR/BuiltinAttributes.h"
+
+// Additional headers for custom types
+#include "llvm/ADT/TypeSwitch.h"
+
+// Common interface headers
+#include "mlir/Interfaces/InferTypeOpInterface.h"
+"#;
+
+/// A single dialect to register in a combined C++ registration file.
+#[derive(Debug, Clone, Copy)]
+pub struct DialectRegistration<'a> {
+    /// The dialect name (e.g., "bril")
+    pub dialect_name: &'a str,
+    /// The C++ namespace of the dialect (e.g., "mlir::bril")
+    pub cpp_namespace: &'a str,
+    /// Which TD file stems generated which content types
+    pub generated: &'a GeneratedFiles,
+    /// The builder options affecting the generated code
+    pub options: &'a CodegenOptions,
+    /// Subdirectory prefix for the dialect's .inc includes
+    pub inc_subdir: Option<&'a Path>,
+}
+
 /// Generate the C++ registration wrapper file.
 ///
 /// This generates a C++ file that:
@@ -32,18 +184,156 @@ fn registration(prefix: &str, stem: &str, suffix: &str, method: &str, list_defin
 /// 3. Uses MLIR_DEFINE_CAPI_DIALECT_REGISTRATION to expose the C API
 ///
 /// The `inc_subdir` parameter specifies the subdirectory prefix for .inc includes.
-/// For example, if `inc_subdir` is `Some("bril")`, includes become `"bril/BrilOps.h.inc"`.
+/// For example, if `inc_subdir` is `Some("bril")`, includes become `"bril/BrilOps.h.inc"`,
+/// and `Some("mycompany/bril")` gives `"mycompany/bril/BrilOps.h.inc"`.
 ///
 /// File names are based on the TD file stems stored in `generated`, matching MLIR convention.
 pub fn generate_cpp_registration(
     dialect_name: &str,
     cpp_namespace: &str,
     generated: &GeneratedFiles,
-    inc_subdir: Option<&str>,
+    options: &CodegenOptions,
+    inc_subdir: Option<&Path>,
+    output_path: &Path,
+) -> Result<(), Error> {
+    generate_combined_cpp_registration(
+        &[DialectRegistration {
+            dialect_name,
+            cpp_namespace,
+            generated,
+            options,
+            inc_subdir,
+        }],
+        output_path,
+    )
+}
+
+/// Generate a C++ registration wrapper file for several dialects.
+///
+/// The file contains one registration section per dialect, each exposing its
+/// own `mlirGetDialectHandle__{name}__()` C API entry point, so all dialects
+/// can be compiled into a single library.
+pub fn generate_combined_cpp_registration(
+    dialects: &[DialectRegistration<'_>],
     output_path: &Path,
 ) -> Result<(), Error> {
-    let class_name = to_class_name(dialect_name);
-    let p = inc_subdir.map(|s| format!("{}/", s)).unwrap_or_default();
+    let code = std::iter::once(PREAMBLE.to_string())
+        .chain(dialects.iter().map(dialect_section))
+        .collect::<String>();
+
+    fs::write(output_path, code)?;
+
+    Ok(())
+}
+
+/// Generate a C header declaring the dialect's C API.
+///
+/// The header declares `mlirGetDialectHandle__{name}__()` along with the typed
+/// constructors of custom types, the operation verifier, the canonicalization
+/// pattern hook, and the pass registration function, so that C and C++ code
+/// outside of Rust can link against the compiled library.
+pub fn generate_c_header(
+    dialect_name: &str,
+    generated: &GeneratedFiles,
+    options: &CodegenOptions,
+    output_path: &Path,
+) -> Result<(), Error> {
+    let class_name = to_class_name(dialect_name, &options.class_name_acronyms);
+    let guard = format!("MELIOR_BUILD_{}_H", dialect_name.to_ascii_uppercase());
+
+    let mut declarations = vec![format!(
+        "MlirDialectHandle mlirGetDialectHandle__{dialect_name}__(void);"
+    )];
+    declarations.extend(
+        generated
+            .type_defs
+            .iter()
+            .map(|type_def| {
+                constructor_signature(
+                    &class_name,
+                    "Type",
+                    &type_def.class_name,
+                    &type_def.parameters,
+                )
+            })
+            .chain(generated.attr_defs.iter().map(|attr_def| {
+                constructor_signature(
+                    &class_name,
+                    "Attribute",
+                    &attr_def.class_name,
+                    &attr_def.parameters,
+                )
+            }))
+            .map(|signature| format!("{};", signature)),
+    );
+    if generated.ops_stem.is_some() {
+        declarations.push(format!("{};", verify_signature(&class_name)));
+    }
+    if generated.rewriters_stem.is_some() {
+        declarations.push(format!("{};", canonicalization_signature(&class_name)));
+    }
+    if generated.passes_stem.is_some() {
+        declarations.push(format!("{};", passes_signature(&class_name)));
+    }
+    if generated.bytecode_stem.is_some() {
+        declarations.push(format!("{};", bytecode_signature(&class_name)));
+    }
+    if options.llvm_translation {
+        declarations.push(format!("{};", llvm_translation_signature(&class_name)));
+    }
+
+    let rewrite_include = if generated.rewriters_stem.is_some() {
+        "#include \"mlir-c/Rewrite.h\"\n"
+    } else {
+        ""
+    };
+
+    fs::write(
+        output_path,
+        format!(
+            r#"// Auto-generated by melior-build. Do not edit.
+
+#ifndef {guard}
+#define {guard}
+
+#include "mlir-c/IR.h"
+{rewrite_include}
+#ifdef __cplusplus
+extern "C" {{
+#endif
+
+{declarations}
+
+#ifdef __cplusplus
+}}
+#endif
+
+#endif // {guard}
+"#,
+            declarations = declarations.join("\n"),
+        ),
+    )?;
+
+    Ok(())
+}
+
+/// Generate the includes, `initialize()` implementation, and C API
+/// registration for a single dialect.
+fn dialect_section(dialect: &DialectRegistration<'_>) -> String {
+    let DialectRegistration {
+        dialect_name,
+        cpp_namespace,
+        generated,
+        options,
+        inc_subdir,
+    } = *dialect;
+    let class_name = to_class_name(dialect_name, &options.class_name_acronyms);
+    // Include paths always use `/`, regardless of the host separator
+    let p = inc_subdir
+        .into_iter()
+        .flat_map(Path::components)
+        .map(|c| format!("{}/", c.as_os_str().to_string_lossy()))
+        .collect::<String>();
 
     // Dialect includes (simple: {stem}Dialect.h.inc)
     let (dialect_decl_include, dialect_def_include) = generated
@@ -69,6 +359,15 @@ pub fn generate_cpp_registration(
             )
         })
         .unwrap_or_default();
+    // Register only the chosen operations instead of the whole op list
+    let ops_list_include = match &options.registered_ops {
+        Some(classes) => classes
+            .iter()
+            .map(|class| format!("        {}", class))
+            .collect::<Vec<_>>()
+            .join(",\n"),
+        None => ops_list_include,
+    };
 
     // Types includes (guarded: GET_TYPEDEF_CLASSES, {stem}Types.h.inc)
     let (type_decl_include, type_def_include, type_registration) = generated
@@ -84,7 +383,14 @@ pub fn generate_cpp_registration(
                     "\n{}\n",
                     guarded_include(&p, s, "Types", "cpp", "GET_TYPEDEF_CLASSES")
                 ),
-                registration(&p, s, "Types", "addTypes", "GET_TYPEDEF_LIST"),
+                registration(
+                    &p,
+                    s,
+                    "Types",
+                    "addTypes",
+                    "GET_TYPEDEF_LIST",
+                    &format!("register{class_name}Types"),
+                ),
             )
         })
         .unwrap_or_default();
@@ -103,7 +409,14 @@ pub fn generate_cpp_registration(
                     "\n{}\n",
                     guarded_include(&p, s, "Attrs", "cpp", "GET_ATTRDEF_CLASSES")
                 ),
-                registration(&p, s, "Attrs", "addAttributes", "GET_ATTRDEF_LIST"),
+                registration(
+                    &p,
+                    s,
+                    "Attrs",
+                    "addAttributes",
+                    "GET_ATTRDEF_LIST",
+                    &format!("register{class_name}Attributes"),
+                ),
             )
         })
         .unwrap_or_default();
@@ -120,43 +433,291 @@ pub fn generate_cpp_registration(
         })
         .unwrap_or_default();
 
-    let function_interface_include = if generated.use_function_interface {
-        "#include \"mlir/Interfaces/FunctionInterfaces.h\"\n"
+    // Op interface includes (simple: {stem}OpInterfaces.h.inc)
+    let (op_interface_decl_include, op_interface_def_include) = generated
+        .op_interfaces_stem
+        .as_ref()
+        .map(|s| {
+            (
+                format!("\n{}\n", include(&p, s, "OpInterfaces", "h")),
+                format!("\n{}\n", include(&p, s, "OpInterfaces", "cpp")),
+            )
+        })
+        .unwrap_or_default();
+
+    // Type interface includes (simple: {stem}TypeInterfaces.h.inc)
+    let (type_interface_decl_include, type_interface_def_include) = generated
+        .type_interfaces_stem
+        .as_ref()
+        .map(|s| {
+            (
+                format!("\n{}\n", include(&p, s, "TypeInterfaces", "h")),
+                format!("\n{}\n", include(&p, s, "TypeInterfaces", "cpp")),
+            )
+        })
+        .unwrap_or_default();
+
+    // Attr interface includes (simple: {stem}AttrInterfaces.h.inc)
+    let (attr_interface_decl_include, attr_interface_def_include) = generated
+        .attr_interfaces_stem
+        .as_ref()
+        .map(|s| {
+            (
+                format!("\n{}\n", include(&p, s, "AttrInterfaces", "h")),
+                format!("\n{}\n", include(&p, s, "AttrInterfaces", "cpp")),
+            )
+        })
+        .unwrap_or_default();
+
+    // Typed C API constructors for custom types
+    let type_constructors = if generated.type_defs.is_empty() {
+        String::new()
     } else {
-        ""
+        format!(
+            "\n// Typed constructors for custom types\nextern \"C\" {{\n{}}}\n",
+            generated
+                .type_defs
+                .iter()
+                .map(|type_def| constructor(
+                    &class_name,
+                    cpp_namespace,
+                    "Type",
+                    &type_def.class_name,
+                    &type_def.parameters
+                ))
+                .collect::<String>()
+        )
     };
 
-    let code = format!(
-        r#"// Auto-generated by melior-build. Do not edit.
+    // Typed C API constructors for custom attributes
+    let attr_constructors = if generated.attr_defs.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "\n// Typed constructors for custom attributes\n#include \"mlir/CAPI/Support.h\"\n\nextern \"C\" {{\n{}}}\n",
+            generated
+                .attr_defs
+                .iter()
+                .map(|attr_def| constructor(
+                    &class_name,
+                    cpp_namespace,
+                    "Attribute",
+                    &attr_def.class_name,
+                    &attr_def.parameters
+                ))
+                .collect::<String>()
+        )
+    };
 
-// MLIR C API headers
-#include "mlir-c/IR.h"
-#include "mlir/CAPI/Registration.h"
-#include "mlir/CAPI/Wrap.h"
+    // Verification of single operations, which runs the custom verifiers
+    // compiled into this library
+    let verify = if generated.ops_stem.is_some() {
+        format!(
+            r#"
+// Operation verification
+#include "mlir/IR/Verifier.h"
 
-// Core MLIR headers required for dialect/operation definitions
-#include "mlir/IR/Dialect.h"
-#include "mlir/IR/DialectImplementation.h"
-#include "mlir/IR/OpImplementation.h"
-#include "mlir/IR/Builders.h"
-#include "mlir/IR/BuiltinTypes.h"
-#include "mlir/IR/BuiltinAttributes.h"
+extern "C" {signature} {{
+    return ::mlir::succeeded(::mlir::verify(unwrap(operation)));
+}}
+"#,
+            signature = verify_signature(&class_name),
+        )
+    } else {
+        String::new()
+    };
 
-// Additional headers for custom types
-#include "llvm/ADT/TypeSwitch.h"
+    // DRR rewrite patterns, exposed through a registration function
+    let rewriters = generated
+        .rewriters_stem
+        .as_ref()
+        .map(|s| {
+            format!(
+                r#"
+// Generated rewrite patterns
+#include "mlir-c/Rewrite.h"
+#include "mlir/IR/PatternMatch.h"
 
-// Common interface headers
-#include "mlir/Interfaces/InferTypeOpInterface.h"
-{function_interface_include}
+namespace {{
+#include "{p}{s}Rewrites.inc"
+}} // namespace
+
+namespace {cpp_namespace} {{
+
+void register{class_name}CanonicalizationPatterns(::mlir::RewritePatternSet &patterns) {{
+    populateWithGenerated(patterns);
+}}
+
+}} // namespace {cpp_namespace}
+
+extern "C" {signature} {{
+    {cpp_namespace}::register{class_name}CanonicalizationPatterns(
+        *static_cast<::mlir::RewritePatternSet *>(patterns.ptr));
+}}
+"#,
+                signature = canonicalization_signature(&class_name),
+            )
+        })
+        .unwrap_or_default();
+
+    // Pass declarations, exposed through a registration function. The
+    // generated `registerPasses` assumes the default (unnamed) pass group.
+    let passes = generated
+        .passes_stem
+        .as_ref()
+        .map(|s| {
+            format!(
+                r#"
+// Generated passes
+#include "mlir/Pass/Pass.h"
+
+namespace {cpp_namespace} {{
+
+#define GEN_PASS_DECL
+#define GEN_PASS_REGISTRATION
+#include "{p}{s}Passes.h.inc"
+
+void register{class_name}Passes() {{
+    registerPasses();
+}}
+
+}} // namespace {cpp_namespace}
+
+extern "C" {signature} {{
+    {cpp_namespace}::register{class_name}Passes();
+}}
+"#,
+                signature = passes_signature(&class_name),
+            )
+        })
+        .unwrap_or_default();
+
+    // Bytecode readers and writers, exposed through a dialect interface. The
+    // hooks for attributes and types are only overridden if the dialect
+    // defines any, since `-gen-bytecode` only emits the encodings it is given.
+    let bytecode = generated
+        .bytecode_stem
+        .as_ref()
+        .map(|s| {
+            let attribute_hooks = if generated.attrs_stem.is_some() {
+                format!(
+                    r#"
+    ::mlir::Attribute readAttribute(::mlir::DialectBytecodeReader &reader) const override {{
+        return ::{cpp_namespace}::readAttribute(getContext(), reader);
+    }}
+
+    ::mlir::LogicalResult writeAttribute(
+        ::mlir::Attribute attribute, ::mlir::DialectBytecodeWriter &writer) const override {{
+        return ::{cpp_namespace}::writeAttribute(attribute, writer);
+    }}
+"#
+                )
+            } else {
+                String::new()
+            };
+            let type_hooks = if generated.types_stem.is_some() {
+                format!(
+                    r#"
+    ::mlir::Type readType(::mlir::DialectBytecodeReader &reader) const override {{
+        return ::{cpp_namespace}::readType(getContext(), reader);
+    }}
+
+    ::mlir::LogicalResult writeType(
+        ::mlir::Type type, ::mlir::DialectBytecodeWriter &writer) const override {{
+        return ::{cpp_namespace}::writeType(type, writer);
+    }}
+"#
+                )
+            } else {
+                String::new()
+            };
+
+            format!(
+                r#"
+// Generated bytecode readers and writers
+#include "mlir/Bytecode/BytecodeImplementation.h"
+
+namespace {cpp_namespace} {{
+
+namespace {{
+#include "{p}{s}Bytecode.cpp.inc"
+
+struct {class_name}DialectBytecodeInterface : public ::mlir::BytecodeDialectInterface {{
+    using BytecodeDialectInterface::BytecodeDialectInterface;
+{attribute_hooks}{type_hooks}}};
+}} // namespace
+
+void register{class_name}BytecodeInterface({class_name}Dialect *dialect) {{
+    dialect->addInterfaces<{class_name}DialectBytecodeInterface>();
+}}
+
+}} // namespace {cpp_namespace}
+
+extern "C" {signature} {{
+    {cpp_namespace}::register{class_name}BytecodeInterface(
+        unwrap(context)->getOrLoadDialect<{cpp_namespace}::{class_name}Dialect>());
+}}
+"#,
+                signature = bytecode_signature(&class_name),
+            )
+        })
+        .unwrap_or_default();
+
+    // The LLVM IR translation registration is defined in a user-provided file
+    let llvm_translation = if options.llvm_translation {
+        format!(
+            r#"
+// LLVM IR translation, registered by a user-provided C++ file
+namespace {cpp_namespace} {{
+void register{class_name}DialectTranslation(::mlir::MLIRContext &context);
+}} // namespace {cpp_namespace}
+
+extern "C" {signature} {{
+    {cpp_namespace}::register{class_name}DialectTranslation(*unwrap(context));
+}}
+"#,
+            signature = llvm_translation_signature(&class_name),
+        )
+    } else {
+        String::new()
+    };
+
+    // A symbol the Rust bindings read, so linkers keep this translation unit
+    let link_anchor = if options.link_anchor {
+        format!(
+            r#"
+// Link anchor read by the Rust bindings, so that linkers dropping unreferenced
+// sections keep the registration code
+#if defined(_MSC_VER)
+#pragma comment(linker, "/include:{symbol}")
+extern "C" const char {symbol} = 0;
+#else
+extern "C" __attribute__((used)) const char {symbol} = 0;
+#endif
+"#,
+            symbol = link_anchor_symbol(&class_name),
+        )
+    } else {
+        String::new()
+    };
+
+    let function_interface_include = if generated.use_function_interface {
+        "#include \"mlir/Interfaces/FunctionInterfaces.h\"\n"
+    } else {
+        ""
+    };
+
+    format!(
+        r#"{function_interface_include}
 // Include generated dialect declaration
 {dialect_decl_include}
-{type_decl_include}{attr_decl_include}{enum_decl_include}
+{type_interface_decl_include}{type_decl_include}{attr_interface_decl_include}{attr_decl_include}{enum_decl_include}{op_interface_decl_include}
 // Include generated operation declarations (after types/attrs so they can use them)
 {ops_decl_include}
 
 // Include generated dialect definition
 {dialect_def_include}
-{type_def_include}{attr_def_include}{enum_def_include}
+{type_interface_def_include}{type_def_include}{attr_interface_def_include}{attr_def_include}{enum_def_include}{op_interface_def_include}
 // Include generated operation definitions
 {ops_def_include}
 
@@ -175,7 +736,7 @@ void {class_name}Dialect::initialize() {{
 extern "C" {{
 MLIR_DEFINE_CAPI_DIALECT_REGISTRATION({class_name}, {dialect_name}, {cpp_namespace}::{class_name}Dialect)
 }}
-"#,
+{link_anchor}{type_constructors}{attr_constructors}{verify}{rewriters}{passes}{bytecode}{llvm_translation}"#,
         function_interface_include = function_interface_include,
         dialect_decl_include = dialect_decl_include,
         dialect_def_include = dialect_def_include,
@@ -190,14 +751,24 @@ MLIR_DEFINE_CAPI_DIALECT_REGISTRATION({class_name}, {dialect_name}, {cpp_namespa
         attr_registration = attr_registration,
         enum_decl_include = enum_decl_include,
         enum_def_include = enum_def_include,
+        op_interface_decl_include = op_interface_decl_include,
+        op_interface_def_include = op_interface_def_include,
+        type_interface_decl_include = type_interface_decl_include,
+        type_interface_def_include = type_interface_def_include,
+        attr_interface_decl_include = attr_interface_decl_include,
+        attr_interface_def_include = attr_interface_def_include,
+        rewriters = rewriters,
+        passes = passes,
+        bytecode = bytecode,
+        llvm_translation = llvm_translation,
+        link_anchor = link_anchor,
         class_name = class_name,
         dialect_name = dialect_name,
         cpp_namespace = cpp_namespace,
-    );
-
-    fs::write(output_path, code)?;
-
-    Ok(())
+        type_constructors = type_constructors,
+        attr_constructors = attr_constructors,
+        verify = verify,
+    )
 }
 
 #[cfg(test)]
@@ -217,7 +788,7 @@ mod tests {
             types_stem: types.map(String::from),
             attrs_stem: attrs.map(String::from),
             enums_stem: enums.map(String::from),
-            use_function_interface: false,
+            ..Default::default()
         }
     }
 
@@ -228,7 +799,15 @@ mod tests {
         let output_path = temp_dir.join("test_single_file_capi.cpp");
 
         let generated = make_generated(Some("ToyOps"), Some("ToyOps"), None, None, None);
-        generate_cpp_registration("toy", "mlir::toy", &generated, None, &output_path).unwrap();
+        generate_cpp_registration(
+            "toy",
+            "mlir::toy",
+            &generated,
+            &CodegenOptions::default(),
+            None,
+            &output_path,
+        )
+        .unwrap();
 
         let content = std::fs::read_to_string(&output_path).unwrap();
 
@@ -244,6 +823,29 @@ mod tests {
         std::fs::remove_file(&output_path).ok();
     }
 
+    #[test]
+    fn test_generate_cpp_registration_nested_subdir() {
+        let output_path = std::env::temp_dir().join("test_nested_subdir_capi.cpp");
+
+        let generated = make_generated(Some("BrilOps"), Some("BrilOps"), None, None, None);
+        let subdir = Path::new("mycompany").join("bril");
+        generate_cpp_registration(
+            "bril",
+            "mlir::mycompany::bril",
+            &generated,
+            &CodegenOptions::default(),
+            Some(&subdir),
+            &output_path,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains("#include \"mycompany/bril/BrilOps.h.inc\""));
+        assert!(content.contains("mlir::mycompany::bril::BrilDialect"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
     #[test]
     fn test_generate_cpp_registration_multi_file() {
         // Simulates separate TD files: BrilDialect.td, BrilOps.td, BrilTypes.td
@@ -257,8 +859,15 @@ mod tests {
             None,
             None,
         );
-        generate_cpp_registration("bril", "mlir::bril", &generated, Some("bril"), &output_path)
-            .unwrap();
+        generate_cpp_registration(
+            "bril",
+            "mlir::bril",
+            &generated,
+            &CodegenOptions::default(),
+            Some(Path::new("bril")),
+            &output_path,
+        )
+        .unwrap();
 
         let content = std::fs::read_to_string(&output_path).unwrap();
 
@@ -288,6 +897,7 @@ mod tests {
             "my_dialect",
             "mlir::my_dialect",
             &generated,
+            &CodegenOptions::default(),
             None,
             &output_path,
         )
@@ -319,7 +929,15 @@ mod tests {
         let output_path = temp_dir.join("test_ops_only_capi.cpp");
 
         let generated = make_generated(None, Some("TestOps"), None, None, None);
-        generate_cpp_registration("test", "mlir::test", &generated, None, &output_path).unwrap();
+        generate_cpp_registration(
+            "test",
+            "mlir::test",
+            &generated,
+            &CodegenOptions::default(),
+            None,
+            &output_path,
+        )
+        .unwrap();
 
         let content = std::fs::read_to_string(&output_path).unwrap();
 
@@ -327,6 +945,504 @@ mod tests {
         assert!(content.contains("TestOps.h.inc"));
         // Should NOT have dialect includes (empty string)
         assert!(!content.contains("Dialect.h.inc"));
+        // Operations can be verified through the C API
+        assert!(content.contains("#include \"mlir/IR/Verifier.h\""));
+        assert!(
+            content
+                .contains("extern \"C\" bool mlirTestVerifyOperation(MlirOperation operation) {")
+        );
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_cpp_registration_op_interfaces() {
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_op_interfaces_capi.cpp");
+
+        let mut generated = make_generated(Some("MyOps"), Some("MyOps"), None, None, None);
+        generated.op_interfaces_stem = Some("MyInterfaces".to_string());
+        generate_cpp_registration(
+            "my",
+            "mlir::my",
+            &generated,
+            &CodegenOptions::default(),
+            None,
+            &output_path,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+
+        assert!(content.contains("\"MyInterfacesOpInterfaces.h.inc\""));
+        assert!(content.contains("\"MyInterfacesOpInterfaces.cpp.inc\""));
+        // Interface declarations must precede the ops that use them
+        assert!(
+            content.find("MyInterfacesOpInterfaces.h.inc").unwrap()
+                < content.find("MyOps.h.inc").unwrap()
+        );
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_cpp_registration_type_interfaces() {
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_type_interfaces_capi.cpp");
+
+        let mut generated =
+            make_generated(Some("MyOps"), Some("MyOps"), Some("MyTypes"), None, None);
+        generated.type_interfaces_stem = Some("MyInterfaces".to_string());
+        generate_cpp_registration(
+            "my",
+            "mlir::my",
+            &generated,
+            &CodegenOptions::default(),
+            None,
+            &output_path,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+
+        assert!(content.contains("\"MyInterfacesTypeInterfaces.h.inc\""));
+        assert!(content.contains("\"MyInterfacesTypeInterfaces.cpp.inc\""));
+        // Interface declarations must precede the types that implement them
+        assert!(
+            content.find("MyInterfacesTypeInterfaces.h.inc").unwrap()
+                < content.find("MyTypesTypes.h.inc").unwrap()
+        );
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_cpp_registration_attr_interfaces() {
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_attr_interfaces_capi.cpp");
+
+        let mut generated =
+            make_generated(Some("MyOps"), Some("MyOps"), None, Some("MyAttrs"), None);
+        generated.attr_interfaces_stem = Some("MyInterfaces".to_string());
+        generate_cpp_registration(
+            "my",
+            "mlir::my",
+            &generated,
+            &CodegenOptions::default(),
+            None,
+            &output_path,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+
+        assert!(content.contains("\"MyInterfacesAttrInterfaces.h.inc\""));
+        assert!(content.contains("\"MyInterfacesAttrInterfaces.cpp.inc\""));
+        // Interface declarations must precede the attributes that implement them
+        assert!(
+            content.find("MyInterfacesAttrInterfaces.h.inc").unwrap()
+                < content.find("MyAttrsAttrs.h.inc").unwrap()
+        );
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_cpp_registration_rewriters() {
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_rewriters_capi.cpp");
+
+        let mut generated = make_generated(Some("BrilOps"), Some("BrilOps"), None, None, None);
+        generated.rewriters_stem = Some("BrilCanon".to_string());
+        generate_cpp_registration(
+            "bril",
+            "mlir::bril",
+            &generated,
+            &CodegenOptions::default(),
+            None,
+            &output_path,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+
+        assert!(content.contains("#include \"BrilCanonRewrites.inc\""));
+        assert!(content.contains(
+            "void registerBrilCanonicalizationPatterns(::mlir::RewritePatternSet &patterns)"
+        ));
+        assert!(content.contains(
+            "extern \"C\" void mlirBrilPopulateCanonicalizationPatterns(MlirRewritePatternSet patterns)"
+        ));
+        // Patterns refer to the ops, so they must come after their definitions
+        assert!(
+            content.find("BrilCanonRewrites.inc").unwrap()
+                > content.find("BrilOps.cpp.inc").unwrap()
+        );
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_combined_cpp_registration() {
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_combined_capi.cpp");
+
+        let bril = make_generated(Some("BrilOps"), Some("BrilOps"), None, None, None);
+        let toy = make_generated(Some("ToyOps"), Some("ToyOps"), None, None, None);
+        generate_combined_cpp_registration(
+            &[
+                DialectRegistration {
+                    dialect_name: "bril",
+                    cpp_namespace: "mlir::bril",
+                    generated: &bril,
+                    options: &CodegenOptions::default(),
+                    inc_subdir: Some(Path::new("bril")),
+                },
+                DialectRegistration {
+                    dialect_name: "toy",
+                    cpp_namespace: "mlir::toy",
+                    generated: &toy,
+                    options: &CodegenOptions::default(),
+                    inc_subdir: Some(Path::new("toy")),
+                },
+            ],
+            &output_path,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+
+        // Shared headers appear once
+        assert_eq!(content.matches("#include \"mlir-c/IR.h\"").count(), 1);
+        assert!(content.contains("bril/BrilOps.h.inc"));
+        assert!(content.contains("toy/ToyOps.h.inc"));
+        assert!(content.contains("MLIR_DEFINE_CAPI_DIALECT_REGISTRATION(Bril, bril,"));
+        assert!(content.contains("MLIR_DEFINE_CAPI_DIALECT_REGISTRATION(Toy, toy,"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_cpp_registration_type_constructors() {
+        use crate::tblgen::TypeDef;
+
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_type_constructors_capi.cpp");
+
+        let mut generated = make_generated(Some("BrilOps"), None, Some("BrilTypes"), None, None);
+        generated.type_defs = vec![TypeDef {
+            class_name: "PtrType".to_string(),
+            parameters: vec![TypeParameter {
+                name: "pointeeType".to_string(),
+                kind: ParameterKind::Type,
+            }],
+        }];
+        generate_cpp_registration(
+            "bril",
+            "mlir::bril",
+            &generated,
+            &CodegenOptions::default(),
+            None,
+            &output_path,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+
+        assert!(
+            content.contains(
+                "MlirType mlirBrilPtrTypeGet(MlirContext context, MlirType pointeeType) {"
+            )
+        );
+        assert!(content.contains(
+            "return wrap(mlir::bril::PtrType::get(unwrap(context), unwrap(pointeeType)));"
+        ));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_cpp_registration_registered_ops() {
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_registered_ops_capi.cpp");
+
+        let generated = make_generated(Some("Bril"), Some("BrilOps"), None, None, None);
+        let options = CodegenOptions {
+            registered_ops: Some(vec![
+                "::mlir::bril::AddOp".to_string(),
+                "::mlir::bril::SubOp".to_string(),
+            ]),
+            ..Default::default()
+        };
+        generate_cpp_registration(
+            "bril",
+            "mlir::bril",
+            &generated,
+            &options,
+            None,
+            &output_path,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains(
+            "    addOperations<\n        ::mlir::bril::AddOp,\n        ::mlir::bril::SubOp\n    >();"
+        ));
+        assert!(!content.contains("GET_OP_LIST"));
+        // The op classes are still generated
+        assert!(content.contains("#define GET_OP_CLASSES\n#include \"BrilOps.cpp.inc\""));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_cpp_registration_link_anchor() {
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_link_anchor_capi.cpp");
+
+        let generated = make_generated(Some("BrilOps"), None, None, None, None);
+        let options = CodegenOptions {
+            link_anchor: true,
+            ..Default::default()
+        };
+        generate_cpp_registration(
+            "bril",
+            "mlir::bril",
+            &generated,
+            &options,
+            None,
+            &output_path,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains("#pragma comment(linker, \"/include:mlirBrilLinkAnchor\")"));
+        assert!(
+            content
+                .contains("extern \"C\" __attribute__((used)) const char mlirBrilLinkAnchor = 0;")
+        );
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_cpp_registration_attr_constructors() {
+        use crate::tblgen::AttrDef;
+
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_attr_constructors_capi.cpp");
+
+        let mut generated = make_generated(Some("BrilOps"), None, None, Some("BrilAttrs"), None);
+        generated.attr_defs = vec![AttrDef {
+            class_name: "LabelAttr".to_string(),
+            parameters: vec![TypeParameter {
+                name: "name".to_string(),
+                kind: ParameterKind::String,
+            }],
+        }];
+        generate_cpp_registration(
+            "bril",
+            "mlir::bril",
+            &generated,
+            &CodegenOptions::default(),
+            None,
+            &output_path,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+
+        assert!(content.contains("#include \"mlir/CAPI/Support.h\""));
+        assert!(content.contains(
+            "MlirAttribute mlirBrilLabelAttrGet(MlirContext context, MlirStringRef name) {"
+        ));
+        assert!(
+            content.contains(
+                "return wrap(mlir::bril::LabelAttr::get(unwrap(context), unwrap(name)));"
+            )
+        );
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_cpp_registration_type_and_attr_hooks() {
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_type_attr_hooks_capi.cpp");
+
+        let generated = make_generated(
+            Some("BrilDialect"),
+            Some("BrilOps"),
+            Some("BrilTypes"),
+            Some("BrilAttrs"),
+            None,
+        );
+        generate_cpp_registration(
+            "bril",
+            "mlir::bril",
+            &generated,
+            &CodegenOptions::default(),
+            Some(Path::new("bril")),
+            &output_path,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        let initialize = &content[content.find("void BrilDialect::initialize()").unwrap()..];
+
+        assert!(initialize.contains("auto registerBrilTypes = [this] {"));
+        assert!(
+            initialize
+                .contains("#define GET_TYPEDEF_LIST\n#include \"bril/BrilTypesTypes.cpp.inc\"")
+        );
+        assert!(initialize.contains("registerBrilTypes();"));
+        assert!(initialize.contains("auto registerBrilAttributes = [this] {"));
+        assert!(
+            initialize
+                .contains("#define GET_ATTRDEF_LIST\n#include \"bril/BrilAttrsAttrs.cpp.inc\"")
+        );
+        assert!(initialize.contains("registerBrilAttributes();"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_cpp_registration_bytecode() {
+        let output_path = std::env::temp_dir().join("test_bytecode_capi.cpp");
+
+        let mut generated = make_generated(
+            Some("BrilOps"),
+            Some("BrilOps"),
+            Some("BrilTypes"),
+            None,
+            None,
+        );
+        generated.bytecode_stem = Some("BrilBytecode".to_string());
+        generate_cpp_registration(
+            "bril",
+            "mlir::bril",
+            &generated,
+            &CodegenOptions::default(),
+            None,
+            &output_path,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+
+        assert!(content.contains("#include \"BrilBytecodeBytecode.cpp.inc\""));
+        assert!(content.contains("return ::mlir::bril::readType(getContext(), reader);"));
+        assert!(
+            !content.contains("readAttribute"),
+            "The dialect has no attributes"
+        );
+        assert!(content.contains("void registerBrilBytecodeInterface(BrilDialect *dialect) {"));
+        assert!(content.contains(
+            "extern \"C\" void mlirBrilRegisterBytecodeInterface(MlirContext context) {"
+        ));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_cpp_registration_passes() {
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_passes_capi.cpp");
+
+        let mut generated = make_generated(Some("BrilOps"), Some("BrilOps"), None, None, None);
+        generated.passes_stem = Some("BrilPasses".to_string());
+        generate_cpp_registration(
+            "bril",
+            "mlir::bril",
+            &generated,
+            &CodegenOptions::default(),
+            None,
+            &output_path,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+
+        assert!(content.contains("#define GEN_PASS_REGISTRATION"));
+        assert!(content.contains("#include \"BrilPassesPasses.h.inc\""));
+        assert!(content.contains("void registerBrilPasses() {"));
+        assert!(content.contains("extern \"C\" void mlirRegisterBrilPasses(void)"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_cpp_registration_llvm_translation() {
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_llvm_translation_capi.cpp");
+
+        let generated = make_generated(Some("BrilOps"), Some("BrilOps"), None, None, None);
+        let options = CodegenOptions {
+            llvm_translation: true,
+            ..Default::default()
+        };
+        generate_cpp_registration(
+            "bril",
+            "mlir::bril",
+            &generated,
+            &options,
+            None,
+            &output_path,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+
+        assert!(
+            content.contains("void registerBrilDialectTranslation(::mlir::MLIRContext &context);")
+        );
+        assert!(
+            content.contains(
+                "extern \"C\" void mlirBrilRegisterLLVMTranslations(MlirContext context) {"
+            )
+        );
+        assert!(content.contains("mlir::bril::registerBrilDialectTranslation(*unwrap(context));"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_c_header() {
+        use crate::tblgen::TypeDef;
+
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_c_header_bril.h");
+
+        let mut generated = make_generated(
+            Some("BrilOps"),
+            Some("BrilOps"),
+            Some("BrilTypes"),
+            None,
+            None,
+        );
+        generated.type_defs = vec![TypeDef {
+            class_name: "PtrType".to_string(),
+            parameters: vec![TypeParameter {
+                name: "pointeeType".to_string(),
+                kind: ParameterKind::Type,
+            }],
+        }];
+        generated.passes_stem = Some("BrilPasses".to_string());
+        generate_c_header("bril", &generated, &CodegenOptions::default(), &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+
+        assert!(content.contains("#ifndef MELIOR_BUILD_BRIL_H"));
+        assert!(content.contains("extern \"C\" {"));
+        assert!(content.contains("MlirDialectHandle mlirGetDialectHandle__bril__(void);"));
+        assert!(
+            content.contains(
+                "MlirType mlirBrilPtrTypeGet(MlirContext context, MlirType pointeeType);"
+            )
+        );
+        assert!(content.contains("void mlirRegisterBrilPasses(void);"));
+        assert!(content.contains("bool mlirBrilVerifyOperation(MlirOperation operation);"));
+        assert!(!content.contains("PopulateCanonicalizationPatterns"));
 
         std::fs::remove_file(&output_path).ok();
     }
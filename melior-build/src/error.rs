@@ -1,6 +1,6 @@
 //! Error types for melior-build.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Errors that can occur during dialect building.
 #[derive(Debug, thiserror::Error)]
@@ -11,26 +11,185 @@ pub enum Error {
 
     /// LLVM/MLIR installation could not be found.
     #[error(
-        "Could not find LLVM/MLIR installation. Ensure llvm-config is in PATH, or set LLVM_PREFIX."
+        "Could not find LLVM/MLIR installation. Ensure llvm-config is in PATH, or set MLIR_SYS_{{major}}0_PREFIX or LLVM_PREFIX."
     )]
     LlvmNotFound,
 
+    /// A TableGen or C++ file added to the builder does not exist.
+    #[error("File not found: {}", .0.display())]
+    FileNotFound(PathBuf),
+
+    /// A glob pattern passed to `td_glob` matched no files.
+    #[error("No TD files match the pattern '{0}'")]
+    NoTdFilesMatched(String),
+
+    /// An include directory added to the builder does not exist or is not a
+    /// directory.
+    #[error("Include directory not found or not a directory: {}", .0.display())]
+    InvalidIncludeDir(PathBuf),
+
     /// mlir-tblgen binary could not be found.
     #[error("Could not find mlir-tblgen binary at {0}")]
     TblgenNotFound(PathBuf),
 
     /// mlir-tblgen execution failed.
-    #[error("mlir-tblgen failed: {0}")]
-    TblgenFailed(String),
+    #[error(
+        "mlir-tblgen {action} failed for {}:\n{diagnostics}\ncommand: {}",
+        .file.display(),
+        command_line(.tblgen, .args)
+    )]
+    TblgenFailed {
+        /// The TD file being processed
+        file: PathBuf,
+        /// The generator action (e.g., "-gen-op-decls")
+        action: String,
+        /// The mlir-tblgen binary that was run
+        tblgen: PathBuf,
+        /// The full argument vector
+        args: Vec<String>,
+        /// The captured stdout and stderr, labeled, or the exit status if
+        /// both are empty
+        diagnostics: String,
+    },
+
+    /// A dialect manifest could not be parsed.
+    #[error("Invalid dialect manifest {}: {message}", .path.display())]
+    InvalidManifest {
+        /// The manifest file
+        path: PathBuf,
+        /// What is wrong, with the line number if known
+        message: String,
+    },
 
     /// Invalid cpp_namespace format.
     #[error("{0}")]
     InvalidNamespace(String),
 
+    /// Unsupported C++ language standard.
+    #[error("{0}")]
+    InvalidCppStandard(String),
+
+    /// The dialect name cannot be used in the generated C symbols and Rust
+    /// modules.
+    #[error(
+        "Dialect name '{0}' must start with a lowercase ASCII letter and contain only lowercase ASCII letters, digits, and '_'. Did you mean '{suggestion}'?",
+        suggestion = dialect_name_suggestion(.0)
+    )]
+    InvalidDialectName(String),
+
+    /// The Rust module name is not a legal Rust identifier.
+    #[error("rust_module_name '{0}' is not a legal Rust identifier.")]
+    InvalidRustModuleName(String),
+
+    /// The dialect handle symbol is not a legal C identifier.
+    #[error("dialect_handle_symbol '{0}' is not a legal C identifier.")]
+    InvalidDialectHandleSymbol(String),
+
+    /// The C API library name is not a legal archive name.
+    #[error("lib_name '{0}' must be non-empty and contain only ASCII letters, digits, and '_'.")]
+    InvalidLibName(String),
+
+    /// A Rust type name passed to `op_rename` is not a legal identifier.
+    #[error(
+        "op_rename name '{0}' must start with an ASCII letter or '_', contain only ASCII letters, digits, and '_', and not be a Rust keyword."
+    )]
+    InvalidOpRename(String),
+
+    /// A system library has an unknown link kind or no name.
+    #[error(
+        "Invalid system library '{0}'. Use a library name, optionally prefixed with 'static=', 'dylib=', or 'framework='."
+    )]
+    InvalidSystemLib(String),
+
+    /// The dialect name passed to the builder differs from the one declared in
+    /// TableGen.
+    #[error(
+        "Dialect name '{builder}' does not match the name '{tablegen}' declared in TableGen. The C API registration would not find the dialect."
+    )]
+    DialectNameMismatch {
+        /// The name passed to `DialectBuilder::new`
+        builder: String,
+        /// The `let name = "..."` value of the TableGen dialect definition
+        tablegen: String,
+    },
+
+    /// A TD file defines more than one dialect.
+    #[error(
+        "Found {} dialect definitions ({}) in one TD file. Build each dialect with its own DialectBuilder.",
+        .0.len(),
+        .0.join(", ")
+    )]
+    MultipleDialects(Vec<String>),
+
+    /// An operation filter is not a valid regex.
+    #[error("Invalid operation filter: {0}")]
+    InvalidOpFilter(String),
+
     /// Duplicate content type across TD files.
     #[error("{0}")]
     DuplicateContent(String),
 
+    /// The TD files define operations, but neither they nor their includes
+    /// define a dialect.
+    #[error(
+        "{} defines operations, but no TD file or include defines a dialect. Add the TD file with the `Dialect` definition (e.g., {}Dialect.td) with td_file, or include it from the operations file.",
+        .ops_file.display(),
+        .dialect_name
+    )]
+    NoDialectDefinition {
+        /// The TD file defining the operations
+        ops_file: PathBuf,
+        /// The dialect name given to the builder
+        dialect_name: String,
+    },
+
+    /// A multi-dialect build was started without any dialects.
+    #[error("No dialects were added to the multi-dialect builder.")]
+    NoDialects,
+
+    /// A dialect added to a multi-dialect builder sets an option that only
+    /// applies to single-dialect builds.
+    #[error("The {dialect} dialect sets {option}, which is not supported by MultiDialectBuilder.")]
+    UnsupportedMultiDialectOption {
+        /// The name of the dialect
+        dialect: String,
+        /// The unsupported builder option (e.g., "dry_run")
+        option: &'static str,
+    },
+
+    /// The LLVM installation does not have the required major version.
+    #[error("LLVM major version {found} does not match the required version {expected}.")]
+    LlvmVersionMismatch {
+        /// The major version required by the builder
+        expected: u32,
+        /// The major version reported by `llvm-config --version`
+        found: u32,
+    },
+
+    /// A default MLIR library is missing from the LLVM lib directory.
+    #[error(
+        "Could not find the MLIR library {0}. Use auto_link_libs to skip missing default libraries, or clear_default_libs to choose the libraries to link."
+    )]
+    MissingMlirLibrary(String),
+
+    /// Two items generated for a dialect, e.g., a type constructor and a
+    /// registration function, have the same Rust name.
+    #[error(
+        "The {dialect} dialect generates more than one Rust item named `{name}`, e.g., a type or attribute constructor named like another generated function."
+    )]
+    DuplicateRustName {
+        /// The name of the dialect
+        dialect: String,
+        /// The Rust name generated more than once
+        name: String,
+    },
+
+    /// Compiling the generated or additional C++ code failed.
+    #[error(
+        "C++ compilation failed for {0}\nCheck the include directories and that the LLVM installation matches the required version."
+    )]
+    CppCompilationFailed(String),
+
     /// I/O error.
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
@@ -39,3 +198,62 @@ pub enum Error {
     #[error("UTF-8 conversion error: {0}")]
     Utf8(#[from] std::string::FromUtf8Error),
 }
+
+/// Suggests a valid dialect name for an invalid one, e.g., `my_dialect` for
+/// `My-Dialect`.
+fn dialect_name_suggestion(name: &str) -> String {
+    let name = name
+        .trim()
+        .chars()
+        .map(|c| match c.to_ascii_lowercase() {
+            c @ ('a'..='z' | '0'..='9') => c,
+            _ => '_',
+        })
+        .collect::<String>();
+
+    match name.trim_start_matches('_') {
+        "" => "dialect".to_string(),
+        rest if rest.starts_with(|c: char| c.is_ascii_lowercase()) => rest.to_string(),
+        rest => format!("dialect_{}", rest),
+    }
+}
+
+/// Formats the captured output of a failed process, labeling each stream and
+/// falling back to the exit status when both are empty.
+pub(crate) fn diagnostics(stdout: &str, stderr: &str, status: Option<i32>) -> String {
+    let mut sections = Vec::new();
+    if !stdout.trim().is_empty() {
+        sections.push(format!("stdout:\n{}", stdout.trim_end()));
+    }
+    if !stderr.trim().is_empty() {
+        sections.push(format!("stderr:\n{}", stderr.trim_end()));
+    }
+
+    if sections.is_empty() {
+        match status {
+            Some(code) => format!("exited with status code {} and no output", code),
+            None => "terminated by a signal with no output".to_string(),
+        }
+    } else {
+        sections.join("\n")
+    }
+}
+
+/// Format a command as a copy-pasteable shell command line.
+pub(crate) fn command_line(program: &Path, args: &[String]) -> String {
+    std::iter::once(program.display().to_string())
+        .chain(args.iter().cloned())
+        .map(|arg| {
+            if !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_=./:+,@".contains(c))
+            {
+                arg
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,136 @@
+//! Fake `mlir-tblgen` implementations for tests, so no LLVM installation is
+//! needed.
+
+use crate::{Error, tblgen::TblgenExecutor};
+use std::{
+    cell::RefCell,
+    ffi::OsString,
+    fs,
+    os::unix::{fs::PermissionsExt, process::ExitStatusExt},
+    path::{Path, PathBuf},
+    process::{ExitStatus, Output},
+};
+
+/// Records each invocation, writes an empty output file, and prints the given
+/// records for `--print-records` of a non-empty file.
+#[derive(Default)]
+pub(crate) struct FakeExecutor {
+    /// The arguments of each invocation
+    pub invocations: RefCell<Vec<Vec<OsString>>>,
+    /// The `--print-records` output
+    pub records: String,
+}
+
+impl FakeExecutor {
+    /// Returns the action (the first argument) of each invocation.
+    pub fn actions(&self) -> Vec<String> {
+        self.invocations
+            .borrow()
+            .iter()
+            .map(|args| args[0].to_string_lossy().into_owned())
+            .collect()
+    }
+}
+
+impl TblgenExecutor for FakeExecutor {
+    fn run(&self, args: &[OsString]) -> Result<Output, Error> {
+        self.invocations.borrow_mut().push(args.to_vec());
+
+        let mut stdout = Vec::new();
+        if args[0] == "--print-records" {
+            if fs::metadata(&args[1])?.len() > 0 {
+                stdout = self.records.clone().into_bytes();
+            }
+        } else if let Some(position) = args.iter().position(|arg| arg == "-o") {
+            fs::write(&args[position + 1], "")?;
+        }
+
+        Ok(Output {
+            status: ExitStatus::from_raw(0),
+            stdout,
+            stderr: Vec::new(),
+        })
+    }
+}
+
+/// Builder for a fake `mlir-tblgen` shell script, for tests that run the
+/// binary itself.
+///
+/// By default the script writes an empty file to its `-o` output, if any,
+/// and prints no records.
+#[derive(Default)]
+pub(crate) struct FakeTblgen {
+    log: Option<PathBuf>,
+    records: Option<PathBuf>,
+    failure: Option<(String, String, i32)>,
+}
+
+impl FakeTblgen {
+    /// Appends the arguments of each invocation to `path`, one invocation per
+    /// line with the arguments separated by tabs.
+    pub fn log(mut self, path: &Path) -> Self {
+        self.log = Some(path.to_path_buf());
+        self
+    }
+
+    /// Prints the content of `path` for `--print-records` of a non-empty
+    /// file.
+    pub fn records(mut self, path: &Path) -> Self {
+        self.records = Some(path.to_path_buf());
+        self
+    }
+
+    /// Prints the given output and exits with `code` instead of writing
+    /// anything.
+    pub fn fail(mut self, stdout: &str, stderr: &str, code: i32) -> Self {
+        self.failure = Some((stdout.to_string(), stderr.to_string(), code));
+        self
+    }
+
+    /// Writes the script to `path` and makes it executable.
+    pub fn write(self, path: &Path) -> PathBuf {
+        let mut script = String::from("#!/bin/sh\n");
+        if let Some(log) = &self.log {
+            script.push_str(&format!(
+                "(IFS=\"$(printf '\\t')\"; printf '%s\\n' \"$*\") >> {}\n",
+                quote(&log.display().to_string())
+            ));
+        }
+        if let Some((stdout, stderr, code)) = &self.failure {
+            script.push_str(&format!(
+                "printf '%s' {}\nprintf '%s' {} >&2\nexit {}\n",
+                quote(stdout),
+                quote(stderr),
+                code
+            ));
+        }
+        if let Some(records) = &self.records {
+            script.push_str(&format!(
+                "if [ \"$1\" = --print-records ]; then\n  [ -s \"$2\" ] && cat {}\n  exit 0\nfi\n",
+                quote(&records.display().to_string())
+            ));
+        }
+        script.push_str(
+            "out=\nwhile [ $# -gt 0 ]; do\n  [ \"$1\" = -o ] && out=\"$2\"\n  shift\ndone\n\
+             [ -n \"$out\" ] && : > \"$out\"\nexit 0\n",
+        );
+
+        fs::write(path, script).unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_path_buf()
+    }
+}
+
+/// Returns the lines of a [`FakeTblgen::log`] file, split into arguments.
+pub(crate) fn read_log(path: &Path) -> Vec<Vec<String>> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.split('\t').map(str::to_string).collect())
+        .collect()
+}
+
+/// Quotes a string for the shell.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
@@ -10,10 +10,13 @@
 //! process locates LLVM in the following order:
 //!
 //! 1. `llvm-config` in PATH (recommended)
-//! 2. `LLVM_PREFIX` environment variable
+//! 2. `MLIR_SYS_*_PREFIX` environment variables, as set for `mlir-sys` (e.g.,
+//!    `MLIR_SYS_210_PREFIX` for LLVM 21)
+//! 3. `LLVM_PREFIX` environment variable
 //!
-//! If `llvm-config` is available, version-specific environment variables like
-//! `MLIR_SYS_210_PREFIX` (for LLVM 21) are also checked as overrides.
+//! If `llvm-config` is available, the `MLIR_SYS_*_PREFIX` variable matching
+//! its version overrides its prefix. Otherwise, the variable for the newest
+//! LLVM version is used.
 //!
 //! # Usage
 //!
@@ -80,6 +83,72 @@
 //! // Now you can use my_dialect::register(&context)
 //! ```
 //!
+//! ## Multiple Dialects
+//!
+//! To build several dialects into a single static library, combine their
+//! builders with a [`MultiDialectBuilder`]:
+//!
+//! ```rust,ignore
+//! use melior_build::{DialectBuilder, MultiDialectBuilder};
+//!
+//! fn main() {
+//!     MultiDialectBuilder::new("my_dialects")
+//!         .dialect(
+//!             DialectBuilder::new("bril")
+//!                 .td_files(["src/bril/BrilDialect.td", "src/bril/BrilOps.td"])
+//!                 .include_dir("src")
+//!                 .cpp_namespace("mlir::bril"),
+//!         )
+//!         .dialect(DialectBuilder::new("toy").td_file("src/toy/ToyOps.td"))
+//!         .build()
+//!         .expect("Failed to build dialects");
+//! }
+//! ```
+//!
+//! This generates a single `my_dialects_register.rs` with one module per
+//! dialect (e.g., `bril::register(&context)` and `toy::register(&context)`).
+//!
+//! ## Dialect Manifests
+//!
+//! To describe the dialect once, list its name, TD files, C++ namespace, and
+//! include directories in a `dialect.toml` and create the builder with
+//! [`DialectBuilder::from_manifest`]. [`DialectBuilder::write_flattened_td`]
+//! writes the same TD files into the single file the `melior::dialect!` macro
+//! works best with:
+//!
+//! ```rust,ignore
+//! use melior_build::DialectBuilder;
+//!
+//! fn main() {
+//!     let builder = DialectBuilder::from_manifest("dialect.toml").expect("Invalid manifest");
+//!     builder
+//!         .write_flattened_td("src/dialect/my_dialect.flat.td")
+//!         .expect("Failed to flatten TD files");
+//!     builder.build().expect("Failed to build dialect");
+//! }
+//! ```
+//!
+//! ## Prebuilt Bindings
+//!
+//! Crates that vendor the generated `.inc` files and `{name}_capi.cpp`, e.g.,
+//! written with [`DialectBuilder::inc_output_dir`], can build them without
+//! `mlir-tblgen` using [`DialectBuilder::use_prebuilt`]:
+//!
+//! ```rust,ignore
+//! DialectBuilder::new("bril")
+//!     .td_file("src/dialect/Bril.td")
+//!     .use_prebuilt("vendor/inc", "vendor/bril_capi.cpp")
+//!     .build()
+//!     .expect("Failed to build dialect");
+//! ```
+//!
+//! ## Documentation Builds
+//!
+//! docs.rs has no LLVM toolchain, so [`DialectBuilder::build`] fails there.
+//! [`DialectBuilder::skip_if_no_llvm`] builds as usual when LLVM is found,
+//! and otherwise only writes the Rust bindings with a `cargo:warning`, which
+//! is enough to document the crate.
+//!
 //! # melior-build vs melior::dialect! macro
 //!
 //! | Feature | `melior-build` | `melior::dialect!` macro |
@@ -91,27 +160,375 @@
 
 pub mod cpp_gen;
 mod error;
+#[cfg(all(test, unix))]
+mod fake_tblgen;
+mod manifest;
+mod multi;
 pub mod rust_gen;
 pub mod tblgen;
 
 pub use error::Error;
+pub use multi::MultiDialectBuilder;
+pub use tblgen::DetectionMode;
+
+use error::command_line;
+use std::{
+    collections::hash_map::DefaultHasher,
+    ffi::OsString,
+    hash::{Hash, Hasher},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+/// Removes later duplicates from `paths`, keeping the first-seen order.
+///
+/// Paths are compared canonicalized, or as given if they cannot be
+/// canonicalized (e.g., because they do not exist). Returns each removed path
+/// with the kept path it duplicates.
+fn dedup_paths(paths: &mut Vec<PathBuf>) -> Vec<(PathBuf, PathBuf)> {
+    let mut seen = Vec::<(PathBuf, PathBuf)>::new();
+    let mut removed = Vec::new();
+    paths.retain(|path| {
+        let key = std::fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+        match seen.iter().find(|(seen_key, _)| *seen_key == key) {
+            Some((_, kept)) => {
+                removed.push((path.clone(), kept.clone()));
+                false
+            }
+            None => {
+                seen.push((key, path.clone()));
+                true
+            }
+        }
+    });
+    removed
+}
 
-use std::path::{Path, PathBuf};
+/// Returns the `-isystem` flag for a directory, keeping non-UTF-8 paths
+/// intact.
+fn isystem(dir: &Path) -> OsString {
+    let mut flag = OsString::from("-isystem");
+    flag.push(dir);
+    flag
+}
 
 /// Convert a dialect name to CamelCase class name.
 /// e.g., "math_ext" -> "MathExt", "my_dialect" -> "MyDialect"
-pub(crate) fn to_class_name(s: &str) -> String {
+///
+/// Segments matching one of `acronyms` (case-insensitively) are replaced by
+/// the acronym, e.g., "llvm_ir" -> "LLVMIR" with `["LLVM", "IR"]`. Segments
+/// starting with a digit are kept unchanged.
+pub(crate) fn to_class_name(s: &str, acronyms: &[String]) -> String {
     s.split('_')
         .map(|part| {
+            if let Some(acronym) = acronyms
+                .iter()
+                .find(|acronym| acronym.eq_ignore_ascii_case(part))
+            {
+                return acronym.clone();
+            }
+
             let mut chars = part.chars();
             match chars.next() {
                 None => String::new(),
+                Some(first) if first.is_ascii_digit() => part.to_string(),
                 Some(first) => first.to_uppercase().chain(chars).collect(),
             }
         })
         .collect()
 }
 
+/// Convert a CamelCase or camelCase name to snake_case.
+/// e.g., "PtrType" -> "ptr_type", "pointeeType" -> "pointee_type"
+pub(crate) fn to_snake_case(s: &str) -> String {
+    let chars = s.chars().collect::<Vec<_>>();
+    let mut result = String::with_capacity(s.len() + 4);
+
+    for (index, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            let previous = chars[index - 1];
+            let next_is_lower = chars.get(index + 1).is_some_and(|c| c.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_is_lower)
+            {
+                result.push('_');
+            }
+        }
+        result.extend(c.to_lowercase());
+    }
+
+    result
+}
+
+/// Parses the major version from a version string such as `"21.1.0"`.
+fn parse_major_version(version: &str) -> Option<u32> {
+    version.trim().split('.').next()?.parse().ok()
+}
+
+/// Parses the LLVM major version from a variable name such as
+/// `MLIR_SYS_210_PREFIX`.
+fn mlir_sys_prefix_major(var: &str) -> Option<u32> {
+    var.strip_prefix("MLIR_SYS_")?
+        .strip_suffix("0_PREFIX")?
+        .parse()
+        .ok()
+}
+
+/// Recursively collect the files under `dir` with one of the given
+/// extensions, in a deterministic order.
+///
+/// Unreadable directories are skipped.
+fn collect_files(dir: &Path, extensions: &[&str], files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            collect_files(&path, extensions, files);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extensions.iter().any(|ext| extension == *ext))
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// Expands a glob pattern into the files it matches, sorted.
+///
+/// `*` and `?` match within a path component, and a `**` component matches
+/// any number of directories. Relative patterns are resolved against `base`.
+/// Unreadable directories are skipped.
+fn glob_files(base: &Path, pattern: &str) -> Vec<PathBuf> {
+    fn expand(dir: PathBuf, components: &[String], files: &mut Vec<PathBuf>) {
+        let Some((component, rest)) = components.split_first() else {
+            if dir.is_file() {
+                files.push(dir);
+            }
+            return;
+        };
+
+        if component == "**" {
+            expand(dir.clone(), rest, files);
+        } else if !component.contains(['*', '?']) {
+            return expand(dir.join(component), rest, files);
+        }
+
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            return;
+        };
+        let matcher = regex::Regex::new(&format!(
+            "^{}$",
+            regex::escape(component)
+                .replace(r"\*", "[^/]*")
+                .replace(r"\?", "[^/]")
+        ))
+        .unwrap();
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            if component == "**" {
+                if path.is_dir() {
+                    expand(path, components, files);
+                }
+            } else if matcher.is_match(&entry.file_name().to_string_lossy()) {
+                expand(path, rest, files);
+            }
+        }
+    }
+
+    let mut start = base.to_path_buf();
+    let mut components = Vec::new();
+    for component in Path::new(pattern).components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => start.push(component),
+            Component::CurDir => {}
+            _ => components.push(component.as_os_str().to_string_lossy().into_owned()),
+        }
+    }
+
+    let mut files = Vec::new();
+    expand(start, &components, &mut files);
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Returns the order in which to process TD files: files defining the dialect
+/// first, then those defining types, attributes, enums, or interfaces, then
+/// those defining ops, and then the rest. Files keep their given order within
+/// each tier.
+fn processing_order(file_contents: &[tblgen::TdFileContents]) -> Vec<usize> {
+    let tier = |contents: &tblgen::TdFileContents| {
+        if contents.has_dialect {
+            0
+        } else if contents.has_types
+            || contents.has_attrs
+            || contents.has_enums
+            || contents.has_op_interfaces
+            || contents.has_type_interfaces
+            || contents.has_attr_interfaces
+        {
+            1
+        } else if contents.has_ops {
+            2
+        } else {
+            3
+        }
+    };
+
+    let mut order = (0..file_contents.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&index| tier(&file_contents[index]));
+    order
+}
+
+/// Returns the `.inc` files in a directory, sorted.
+fn list_inc_files(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut files = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    files.retain(|path| path.extension().is_some_and(|ext| ext == "inc"));
+    files.sort();
+    Ok(files)
+}
+
+/// Returns `base_dir`, or `{base_dir}/{subdir}` when a subdirectory is given.
+fn output_subdir(base_dir: &Path, subdir: Option<&Path>) -> PathBuf {
+    match subdir {
+        Some(subdir) => base_dir.join(subdir),
+        None => base_dir.to_path_buf(),
+    }
+}
+
+/// A hook transforming the generated C++ registration code before it is
+/// compiled.
+#[derive(Clone)]
+struct CppPostprocess(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl std::fmt::Debug for CppPostprocess {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("CppPostprocess(..)")
+    }
+}
+
+/// Extracts the subdirectory path of the `.inc` files from a C++ namespace.
+///
+/// Returns:
+/// - `Ok(Some("bril"))` for `"mlir::bril"`
+/// - `Ok(Some("mycompany/bril"))` for `"mlir::mycompany::bril"`
+/// - `Ok(None)` for an empty namespace
+/// - `Err` for namespaces not nested under `mlir` (must use `mlir::X` pattern)
+/// - `Err` for malformed namespaces (leading/trailing `::` or empty segments)
+fn namespace_to_subdir(ns: &str) -> Result<Option<PathBuf>, Error> {
+    let parts = namespace_segments(ns)?;
+    if parts.is_empty() {
+        return Ok(None);
+    }
+
+    match mlir_subdir(&parts) {
+        Some(subdir) => Ok(Some(subdir)),
+        None => Err(Error::InvalidNamespace(format!(
+            "cpp_namespace '{}' must use the 'mlir::namespace' pattern. \
+             Did you mean 'mlir::{}'?",
+            ns,
+            parts.last().copied().unwrap_or_default()
+        ))),
+    }
+}
+
+/// Splits a C++ namespace into its segments, which are empty for an empty
+/// namespace.
+///
+/// Fails for malformed namespaces (leading/trailing `::` or empty segments).
+fn namespace_segments(ns: &str) -> Result<Vec<&str>, Error> {
+    let trimmed = ns.trim();
+    if trimmed.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Reject leading or trailing ::
+    if trimmed.starts_with("::") || trimmed.ends_with("::") {
+        return Err(Error::InvalidNamespace(format!(
+            "cpp_namespace '{}' has invalid leading or trailing '::'.",
+            ns
+        )));
+    }
+
+    let parts = trimmed.split("::").map(str::trim).collect::<Vec<_>>();
+    if parts.iter().any(|part| part.is_empty()) {
+        return Err(Error::InvalidNamespace(format!(
+            "cpp_namespace '{}' has an empty segment.",
+            ns
+        )));
+    }
+
+    Ok(parts)
+}
+
+/// Returns the subdirectory of the `.inc` files for the segments of a
+/// namespace nested under `mlir`, or `None` for any other namespace.
+fn mlir_subdir(parts: &[&str]) -> Option<PathBuf> {
+    match parts {
+        ["mlir", rest @ ..] if !rest.is_empty() => Some(rest.iter().collect()),
+        _ => None,
+    }
+}
+
+/// The files produced by a build.
+#[derive(Debug, Clone)]
+pub struct BuildArtifacts {
+    /// The generated Rust bindings (e.g., `{name}_register.rs`)
+    pub register_file: PathBuf,
+    /// The generated C++ registration code (e.g., `{name}_capi.cpp`)
+    pub capi_file: PathBuf,
+    /// The `.inc` files generated by `mlir-tblgen`
+    pub inc_files: Vec<PathBuf>,
+    /// The directory of the generated Markdown documentation, if enabled
+    pub docs_dir: Option<PathBuf>,
+    /// The generated C headers (e.g., `{name}.h`), if enabled
+    pub c_headers: Vec<PathBuf>,
+    /// The detected contents of each TD file
+    pub td_contents: Vec<(PathBuf, tblgen::TdFileContents)>,
+    /// The resolved LLVM installation prefix
+    pub llvm_prefix: PathBuf,
+}
+
+/// The builder options that affect the generated C++ and Rust code, as
+/// opposed to the [`GeneratedFiles`](tblgen::GeneratedFiles) produced by
+/// `mlir-tblgen`.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct CodegenOptions {
+    /// Acronyms kept uppercase when deriving class names
+    pub class_name_acronyms: Vec<String>,
+    /// Rust type names of operations by full operation name, overriding the
+    /// derived names
+    pub op_renames: Vec<(String, String)>,
+    /// Name of the C API library to link (defaults to "{name}_dialect")
+    pub lib_name: Option<String>,
+    /// Name of the generated Rust module (defaults to "{name}_registration",
+    /// or "{name}" for a combined library)
+    pub rust_module_name: Option<String>,
+    /// Symbol of the dialect handle getter the Rust bindings link to
+    /// (defaults to "mlirGetDialectHandle__{name}__")
+    pub dialect_handle_symbol: Option<String>,
+    /// Whether a user-provided LLVM IR translation registration is compiled in
+    pub llvm_translation: bool,
+    /// Whether to generate a function creating a context with the dialect
+    pub context_helper: bool,
+    /// Whether to generate a symbol the Rust bindings read to keep the C API
+    /// library linked in
+    pub link_anchor: bool,
+    /// The C++ classes of the operations the dialect registers (e.g.,
+    /// "::mlir::bril::AddOp"), or `None` to register all generated ones
+    pub registered_ops: Option<Vec<String>>,
+}
+
 /// Builder for compiling and registering a custom MLIR dialect.
 ///
 /// This builder handles:
@@ -121,22 +538,122 @@ pub(crate) fn to_class_name(s: &str) -> String {
 /// 4. Generating Rust FFI bindings
 ///
 /// The builder auto-detects what each TableGen file contains (dialect
-/// definitions, operations, types, attributes, enums, FunctionOpInterface) and
-/// runs only the relevant generators.
+/// definitions, operations, types, attributes, enums, op, type, and attr
+/// interfaces, FunctionOpInterface, rewrite patterns, passes) and runs only the
+/// relevant generators.
 #[derive(Debug, Clone)]
 pub struct DialectBuilder {
     /// The dialect name (e.g., "toy")
     name: String,
     /// The C++ namespace for the dialect (e.g., "mlir::toy")
     cpp_namespace: Option<String>,
+    /// Manifest the builder was created from, if any
+    manifest: Option<PathBuf>,
     /// TableGen files to process
     td_files: Vec<PathBuf>,
+    /// Glob patterns passed to `td_glob` that matched no files
+    unmatched_td_globs: Vec<String>,
     /// Include directories for TableGen
     include_dirs: Vec<PathBuf>,
+    /// Include directories for TableGen used only for specific TD files
+    td_file_include_dirs: Vec<(PathBuf, Vec<PathBuf>)>,
     /// Additional C++ source files to compile
     cpp_files: Vec<PathBuf>,
+    /// Include directories for the C++ compilation only
+    cpp_include_dirs: Vec<PathBuf>,
     /// Output directory (defaults to OUT_DIR)
     output_dir: Option<PathBuf>,
+    /// Directory for the generated .inc files (defaults to "{output_dir}/inc")
+    inc_output_dir: Option<PathBuf>,
+    /// Vendored `.inc` directory and C++ registration file to build from
+    /// instead of running mlir-tblgen
+    prebuilt: Option<(PathBuf, PathBuf)>,
+    /// Directory for intermediate files (defaults to the output directory)
+    scratch_dir: Option<PathBuf>,
+    /// File name of the generated Rust bindings (defaults to
+    /// "{name}_register.rs")
+    register_file_name: Option<String>,
+    /// File name of the generated C++ code (defaults to "{name}_capi.cpp")
+    capi_file_name: Option<String>,
+    /// Hook transforming the generated C++ code before compilation
+    cpp_postprocess: Option<CppPostprocess>,
+    /// Name of the compiled C API library (defaults to "{name}_dialect")
+    lib_name: Option<String>,
+    /// Name of the generated Rust module (defaults to "{name}_registration")
+    rust_module_name: Option<String>,
+    /// Symbol of the dialect handle getter (defaults to
+    /// "mlirGetDialectHandle__{name}__")
+    dialect_handle_symbol: Option<String>,
+    /// C++ language standard (defaults to "c++17")
+    cpp_std: Option<String>,
+    /// Preprocessor defines for the C++ compilation
+    defines: Vec<(String, Option<String>)>,
+    /// C++ file registering the dialect's translation to LLVM IR
+    llvm_translation: Option<PathBuf>,
+    /// Whether to compile the C++ code with RTTI
+    enable_rtti: bool,
+    /// Whether to compile the C++ code with exceptions
+    enable_exceptions: bool,
+    /// Whether to compile position-independent code (defaults to cc's choice
+    /// for the target)
+    force_pic: Option<bool>,
+    /// Optimization level of the C++ compilation (defaults to Cargo's
+    /// `OPT_LEVEL`)
+    opt_level: Option<u8>,
+    /// Whether to compile the C++ code with debug info (defaults to Cargo's
+    /// `DEBUG`)
+    debug_info: Option<bool>,
+    /// Explicit mlir-tblgen binary (defaults to
+    /// `{llvm_prefix}/bin/mlir-tblgen`)
+    tblgen_path: Option<PathBuf>,
+    /// Extra flags passed to every mlir-tblgen invocation
+    tblgen_flags: Vec<String>,
+    /// TableGen preprocessor macros defined for every mlir-tblgen invocation
+    tablegen_defines: Vec<String>,
+    /// Regex selecting the ops to generate
+    op_include_regex: Option<String>,
+    /// Regex selecting the ops to skip
+    op_exclude_regex: Option<String>,
+    /// Full names of the ops to register (defaults to all generated ops)
+    register_only: Option<Vec<String>>,
+    /// How TableGen file contents are detected
+    detection_mode: DetectionMode,
+    /// Whether detection also covers the files a TD file includes
+    follow_includes: bool,
+    /// Additional MLIR libraries to link
+    link_libs: Vec<String>,
+    /// System libraries to link after the MLIR libraries, as `[kind=]name`
+    system_libs: Vec<String>,
+    /// Whether to link the default MLIR libraries
+    default_link_libs: bool,
+    /// Whether to skip default MLIR libraries missing from the lib directory
+    auto_link_libs: bool,
+    /// Whether to link MLIR statically
+    link_static: bool,
+    /// Required LLVM major version
+    llvm_version: Option<u32>,
+    /// Acronyms kept uppercase when deriving class names
+    class_name_acronyms: Vec<String>,
+    /// Rust type names of operations by full operation name
+    op_renames: Vec<(String, String)>,
+    /// Whether to generate Markdown documentation
+    generate_docs: bool,
+    /// Whether to generate typed Rust builder functions for operations
+    generate_op_builders: bool,
+    /// Whether to generate a function creating a context with the dialect
+    emit_context_helper: bool,
+    /// Whether to omit the symbol keeping the C API library linked in
+    disable_link_anchor: bool,
+    /// Whether to write a compile_commands.json for the C++ code
+    emit_compile_commands: bool,
+    /// Whether to write a JSON summary of the build
+    emit_build_info: bool,
+    /// Whether to write a C header declaring the dialect's C API
+    emit_c_header: bool,
+    /// Whether to log the build steps
+    verbose: bool,
+    /// Whether to only validate and print the planned build steps
+    dry_run: bool,
 }
 
 impl DialectBuilder {
@@ -147,10 +664,56 @@ impl DialectBuilder {
         Self {
             name: name.into(),
             cpp_namespace: None,
+            manifest: None,
             td_files: Vec::new(),
+            unmatched_td_globs: Vec::new(),
             include_dirs: Vec::new(),
+            td_file_include_dirs: Vec::new(),
             cpp_files: Vec::new(),
+            cpp_include_dirs: Vec::new(),
             output_dir: None,
+            inc_output_dir: None,
+            prebuilt: None,
+            scratch_dir: None,
+            register_file_name: None,
+            capi_file_name: None,
+            cpp_postprocess: None,
+            lib_name: None,
+            rust_module_name: None,
+            dialect_handle_symbol: None,
+            cpp_std: None,
+            defines: Vec::new(),
+            llvm_translation: None,
+            enable_rtti: false,
+            enable_exceptions: false,
+            force_pic: None,
+            opt_level: None,
+            debug_info: None,
+            tblgen_path: None,
+            tblgen_flags: Vec::new(),
+            tablegen_defines: Vec::new(),
+            op_include_regex: None,
+            op_exclude_regex: None,
+            register_only: None,
+            detection_mode: DetectionMode::default(),
+            follow_includes: false,
+            link_libs: Vec::new(),
+            system_libs: Vec::new(),
+            default_link_libs: true,
+            auto_link_libs: false,
+            link_static: false,
+            llvm_version: None,
+            class_name_acronyms: Vec::new(),
+            op_renames: Vec::new(),
+            generate_docs: false,
+            generate_op_builders: false,
+            emit_context_helper: false,
+            disable_link_anchor: false,
+            emit_compile_commands: false,
+            emit_build_info: false,
+            emit_c_header: false,
+            verbose: false,
+            dry_run: false,
         }
     }
 
@@ -158,60 +721,97 @@ impl DialectBuilder {
     ///
     /// Returns:
     /// - `Ok(Some("bril"))` for `"mlir::bril"`
-    /// - `Ok(None)` if cpp_namespace is not set
-    /// - `Err` for single-level namespaces (must use `mlir::X` pattern)
-    /// - `Err` for namespaces with 3+ levels
-    /// - `Err` for malformed namespaces (leading/trailing `::`)
-    fn namespace_subdir(&self) -> Result<Option<String>, Error> {
+    /// - `Ok(Some("mycompany/bril"))` for `"mlir::mycompany::bril"`
+    /// - `Ok(None)` if cpp_namespace is not set and the TableGen dialect's
+    ///   `cppNamespace` is not nested under `mlir`, or neither is set
+    /// - `Err` for namespaces not nested under `mlir` (must use `mlir::X`
+    ///   pattern)
+    /// - `Err` for malformed namespaces (leading/trailing `::` or empty
+    ///   segments), including a malformed TableGen `cppNamespace`
+    fn namespace_subdir(&self) -> Result<Option<PathBuf>, Error> {
         match &self.cpp_namespace {
-            None => Ok(None),
-            Some(ns) => {
-                let trimmed = ns.trim();
-                if trimmed.is_empty() {
-                    return Ok(None);
-                }
+            Some(ns) => namespace_to_subdir(ns),
+            // A TableGen namespace outside of `mlir` is still valid C++, and
+            // only leaves the `.inc` files without a subdirectory
+            None => match self.tablegen_cpp_namespace()? {
+                Some(ns) => Ok(mlir_subdir(&namespace_segments(&ns)?)),
+                None => Ok(None),
+            },
+        }
+    }
 
-                // Reject leading or trailing ::
-                if trimmed.starts_with("::") || trimmed.ends_with("::") {
-                    return Err(Error::InvalidNamespace(format!(
-                        "cpp_namespace '{}' has invalid leading or trailing '::'.",
-                        ns
-                    )));
-                }
+    /// Returns the `cppNamespace` of the TableGen dialect definition, without
+    /// TableGen's leading `::`.
+    fn tablegen_cpp_namespace(&self) -> Result<Option<String>, Error> {
+        for file in self.dialect_search_files()? {
+            if let Some(ns) = tblgen::dialect_cpp_namespace(&file)? {
+                return Ok(Some(ns.trim().trim_start_matches("::").to_string()));
+            }
+        }
 
-                let parts: Vec<&str> = trimmed.split("::").collect();
-                match parts.as_slice() {
-                    ["mlir", dialect] => Ok(Some((*dialect).to_string())),
-                    [single] => Err(Error::InvalidNamespace(format!(
-                        "cpp_namespace '{}' must use the 'mlir::namespace' pattern. \
-                         Did you mean 'mlir::{}'?",
-                        ns, single
-                    ))),
-                    _ => Err(Error::InvalidNamespace(format!(
-                        "cpp_namespace '{}' has more than 2 levels. \
-                         Only 'mlir::namespace' pattern is supported.",
-                        ns
-                    ))),
+        Ok(None)
+    }
+
+    /// Create a dialect builder from a manifest file, so `build.rs` and the
+    /// `melior::dialect!` invocation can share one description of the dialect.
+    ///
+    /// The manifest is a TOML file with a `[dialect]` table; only `name` is
+    /// required, and other tables are ignored:
+    ///
+    /// ```toml
+    /// [dialect]
+    /// name = "math_ext"
+    /// td_files = ["src/dialect/MathDialect.td"]
+    /// cpp_namespace = "mlir::math_ext"
+    /// include_dirs = ["src/dialect"]
+    /// ```
+    ///
+    /// Paths are relative to the manifest's directory. The build reruns when
+    /// the manifest changes, and the returned builder can be configured
+    /// further.
+    pub fn from_manifest(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        if !path.is_file() {
+            return Err(Error::FileNotFound(path.to_path_buf()));
+        }
+        let manifest =
+            manifest::parse_manifest(&std::fs::read_to_string(path)?).map_err(|message| {
+                Error::InvalidManifest {
+                    path: path.to_path_buf(),
+                    message,
                 }
-            }
+            })?;
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        let mut builder = Self::new(manifest.name)
+            .td_files(manifest.td_files.iter().map(|file| base.join(file)))
+            .include_dirs(manifest.include_dirs.iter().map(|dir| base.join(dir)));
+        if let Some(namespace) = manifest.cpp_namespace {
+            builder = builder.cpp_namespace(namespace);
         }
+        builder.manifest = Some(path.to_path_buf());
+        Ok(builder)
     }
 
     /// Set the C++ namespace for the dialect.
     ///
-    /// The namespace must follow the `mlir::name` pattern (e.g., `mlir::bril`).
-    /// This determines both the C++ namespace wrapping and the subdirectory
-    /// for generated `.inc` files (e.g., `inc/bril/BrilOps.h.inc`).
+    /// The namespace must be nested under `mlir` (e.g., `mlir::bril` or
+    /// `mlir::mycompany::bril`). This determines both the C++ namespace
+    /// wrapping and the subdirectory for generated `.inc` files (e.g.,
+    /// `inc/bril/BrilOps.h.inc` or `inc/mycompany/bril/BrilOps.h.inc`).
     ///
-    /// If not set, defaults to `mlir::{name}` and files are placed directly
-    /// in the `inc/` directory without a subdirectory.
+    /// If not set, defaults to the `cppNamespace` of the TableGen dialect
+    /// definition (without its leading `::`), or `mlir::{name}` if it has
+    /// none. A namespace taken from TableGen only places the files in a
+    /// subdirectory if it is nested under `mlir`; otherwise, or without a
+    /// TableGen namespace, they are placed directly in the `inc/` directory.
     ///
     /// # Errors
     ///
     /// The build will fail if the namespace:
-    /// - Has only one level (e.g., `"bril"` instead of `"mlir::bril"`)
-    /// - Has more than two levels (e.g., `"mlir::foo::bar"`)
+    /// - Is not nested under `mlir` (e.g., `"bril"` instead of `"mlir::bril"`)
     /// - Has leading or trailing `::` (e.g., `"mlir::bril::"`)
+    /// - Has empty segments (e.g., `"mlir::::bril"`)
     pub fn cpp_namespace(mut self, namespace: impl Into<String>) -> Self {
         self.cpp_namespace = Some(namespace.into());
         self
@@ -223,6 +823,29 @@ impl DialectBuilder {
         self
     }
 
+    /// Add a TableGen file to process with its own include directories.
+    ///
+    /// The directories are searched, after the ones added with
+    /// [`include_dir`](Self::include_dir), only when running `mlir-tblgen` on
+    /// this file. Like the global ones, they are also passed to the C++
+    /// compiler.
+    pub fn td_file_with_includes<P: AsRef<Path>>(
+        mut self,
+        path: impl AsRef<Path>,
+        include_dirs: &[P],
+    ) -> Self {
+        let path = path.as_ref().to_path_buf();
+        self.td_files.push(path.clone());
+        self.td_file_include_dirs.push((
+            path,
+            include_dirs
+                .iter()
+                .map(|dir| dir.as_ref().to_path_buf())
+                .collect(),
+        ));
+        self
+    }
+
     /// Add multiple TableGen files to process.
     pub fn td_files<P: AsRef<Path>>(mut self, paths: impl IntoIterator<Item = P>) -> Self {
         self.td_files
@@ -230,6 +853,34 @@ impl DialectBuilder {
         self
     }
 
+    /// Add the TableGen files matching a glob pattern, e.g.,
+    /// `"src/dialect/*.td"`.
+    ///
+    /// `*` and `?` match within a path component, and `**` matches any number
+    /// of directories. Relative patterns are resolved against
+    /// `CARGO_MANIFEST_DIR`. The matches are added sorted by path, with files
+    /// defining a dialect first, so the order is stable as files are added.
+    ///
+    /// The build fails with [`Error::NoTdFilesMatched`] if nothing matches.
+    pub fn td_glob(mut self, pattern: &str) -> Self {
+        let base = std::env::var_os("CARGO_MANIFEST_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        let mut files = glob_files(&base, pattern);
+        if files.is_empty() {
+            self.unmatched_td_globs.push(pattern.to_string());
+        }
+
+        files
+            .sort_by_key(|path| tblgen::dialect_names(path).map_or(true, |names| names.is_empty()));
+        for file in files {
+            if !self.td_files.contains(&file) {
+                self.td_files.push(file);
+            }
+        }
+        self
+    }
+
     /// Add an include directory for TableGen processing.
     pub fn include_dir(mut self, path: impl AsRef<Path>) -> Self {
         self.include_dirs.push(path.as_ref().to_path_buf());
@@ -243,6 +894,23 @@ impl DialectBuilder {
         self
     }
 
+    /// Add an include directory for the C++ compilation only.
+    ///
+    /// Unlike [`include_dir`](Self::include_dir), which is used by both
+    /// TableGen and the C++ compiler, this is not passed to `mlir-tblgen`. Use
+    /// it for the headers of external libraries used by additional C++ files.
+    pub fn cpp_include_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.cpp_include_dirs.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Add multiple include directories for the C++ compilation only.
+    pub fn cpp_include_dirs<P: AsRef<Path>>(mut self, paths: impl IntoIterator<Item = P>) -> Self {
+        self.cpp_include_dirs
+            .extend(paths.into_iter().map(|p| p.as_ref().to_path_buf()));
+        self
+    }
+
     /// Add an additional C++ source file to compile.
     ///
     /// Use this for custom verifiers, canonicalizers, builders, or other
@@ -259,7 +927,7 @@ impl DialectBuilder {
         self
     }
 
-    /// Set the output directory for generated files.
+    /// Set the output directory for generated files and the compiled library.
     ///
     /// If not set, defaults to the `OUT_DIR` environment variable.
     pub fn output_dir(mut self, path: impl AsRef<Path>) -> Self {
@@ -267,302 +935,4719 @@ impl DialectBuilder {
         self
     }
 
-    /// Build the dialect registration code.
-    ///
-    /// This will:
-    /// 1. Run `mlir-tblgen` to generate C++ `.inc` files
-    /// 2. Generate a C++ wrapper file with registration code
-    /// 3. Compile the C++ code into a static library
-    /// 4. Generate Rust FFI bindings
+    /// Set the directory for intermediate files needed during the build.
     ///
-    /// The generated Rust file should be included in your crate:
-    /// ```rust,ignore
-    /// include!(concat!(env!("OUT_DIR"), "/{name}_register.rs"));
-    /// ```
-    pub fn build(self) -> Result<(), Error> {
-        let output_dir = self.get_output_dir()?;
-        let llvm_prefix = self.get_llvm_prefix()?;
-        let cpp_namespace = self
-            .cpp_namespace
-            .clone()
-            .unwrap_or_else(|| format!("mlir::{}", self.name));
-
-        std::fs::create_dir_all(&output_dir)?;
-
-        let tblgen_runner = tblgen::TblgenRunner::new(&llvm_prefix)?;
+    /// If not set, defaults to the output directory (`OUT_DIR`), so that
+    /// builds never write to the system temp directory, which may be
+    /// read-only in sandboxed CI. The directory is created if missing.
+    pub fn scratch_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.scratch_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
 
-        // Create base inc/ directory
-        let inc_base = output_dir.join("inc");
-        std::fs::create_dir_all(&inc_base)?;
+    /// Set the directory for the generated `.inc` files.
+    ///
+    /// Use this to keep the generated headers somewhere visible to IDEs, or to
+    /// commit them. The directory is created if missing, and the C++ code is
+    /// compiled against it. If not set, defaults to `{output_dir}/inc`.
+    pub fn inc_output_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.inc_output_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
 
-        // Get namespace-based subdirectory (e.g., "mlir::bril" -> "bril")
-        let inc_subdir = self.namespace_subdir()?;
+    /// Build from vendored `.inc` files and C++ registration code instead of
+    /// generating them.
+    ///
+    /// `inc_dir` is the base directory the `.inc` files are included from,
+    /// as written by an earlier build (see
+    /// [`inc_output_dir`](Self::inc_output_dir)), and `capi_cpp` that build's
+    /// `{name}_capi.cpp`. `build()` then only compiles them and emits the Rust
+    /// bindings, never running `mlir-tblgen`, which need not be installed.
+    /// The MLIR headers and libraries are still required.
+    ///
+    /// The Rust bindings are derived from the TD files, if any, without
+    /// `mlir-tblgen`: functions such as `verify` are emitted for the contents
+    /// the files define, but typed constructors, enums, and operation
+    /// builders are not. Not supported by [`MultiDialectBuilder`].
+    pub fn use_prebuilt(mut self, inc_dir: impl AsRef<Path>, capi_cpp: impl AsRef<Path>) -> Self {
+        self.prebuilt = Some((
+            inc_dir.as_ref().to_path_buf(),
+            capi_cpp.as_ref().to_path_buf(),
+        ));
+        self
+    }
 
-        // Create the actual output directory for .inc files
-        let inc_dir = match &inc_subdir {
-            Some(subdir) => {
-                let dir = inc_base.join(subdir);
-                std::fs::create_dir_all(&dir)?;
-                dir
-            }
-            None => inc_base.clone(),
-        };
+    /// Set the file name of the generated Rust bindings.
+    ///
+    /// If not set, defaults to `{name}_register.rs`.
+    pub fn register_file_name(mut self, name: impl Into<String>) -> Self {
+        self.register_file_name = Some(name.into());
+        self
+    }
 
-        // Track which TD file stems generated which content types
-        let mut generated = tblgen::GeneratedFiles::default();
+    /// Set the file name of the generated C++ registration code.
+    ///
+    /// If not set, defaults to `{name}_capi.cpp`.
+    pub fn capi_file_name(mut self, name: impl Into<String>) -> Self {
+        self.capi_file_name = Some(name.into());
+        self
+    }
 
-        for td_file in &self.td_files {
-            let contents = tblgen::detect_td_contents(td_file)?;
-
-            let stem = td_file
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .ok_or_else(|| {
-                    Error::Io(std::io::Error::new(
-                        std::io::ErrorKind::InvalidInput,
-                        format!("Invalid TD file path: {}", td_file.display()),
-                    ))
-                })?
-                .to_string();
+    /// Transform the generated C++ registration code before it is compiled.
+    ///
+    /// The hook receives the contents of the generated `{name}_capi.cpp` and
+    /// returns the source to write back and compile, e.g., with an anchor
+    /// symbol appended. In a [`MultiDialectBuilder`], the hooks of all
+    /// dialects are applied to the combined file in order.
+    pub fn cpp_postprocess(
+        mut self,
+        postprocess: impl Fn(&str) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.cpp_postprocess = Some(CppPostprocess(Arc::new(postprocess)));
+        self
+    }
 
-            // Helper to check for duplicates and track stems
-            let track_stem =
-                |has_content: bool, slot: &mut Option<String>, kind: &str| -> Result<(), Error> {
-                    if has_content {
-                        if let Some(existing) = slot.as_ref() {
-                            return Err(Error::DuplicateContent(format!(
-                                "{} defined in multiple TD files: {} and {}",
-                                kind, existing, stem
-                            )));
-                        }
-                        *slot = Some(stem.clone());
-                    }
-                    Ok(())
-                };
+    /// Set the name of the compiled C API library.
+    ///
+    /// Use this to avoid link-time collisions between crates that build a
+    /// dialect with the same name. The name may only contain ASCII letters,
+    /// digits, and `_`. If not set, defaults to `{name}_dialect`.
+    pub fn lib_name(mut self, name: impl Into<String>) -> Self {
+        self.lib_name = Some(name.into());
+        self
+    }
 
-            track_stem(contents.has_dialect, &mut generated.dialect_stem, "Dialect")?;
-            track_stem(contents.has_ops, &mut generated.ops_stem, "Ops")?;
-            track_stem(contents.has_types, &mut generated.types_stem, "Types")?;
-            track_stem(contents.has_attrs, &mut generated.attrs_stem, "Attrs")?;
-            track_stem(contents.has_enums, &mut generated.enums_stem, "Enums")?;
+    /// Set the name of the module wrapping the generated Rust bindings.
+    ///
+    /// The bindings are defined in this module and re-exported from the
+    /// generated file, so the name only matters when it would otherwise clash
+    /// with an item of the including module. It must be a legal Rust
+    /// identifier. If not set, defaults to `{name}_registration`; in a
+    /// [`MultiDialectBuilder`], it replaces the public per-dialect module
+    /// named `{name}`.
+    pub fn rust_module_name(mut self, name: impl Into<String>) -> Self {
+        self.rust_module_name = Some(name.into());
+        self
+    }
 
-            generated.use_function_interface |= contents.has_function_interface;
+    /// Set the symbol the Rust bindings call to get the dialect handle.
+    ///
+    /// If not set, defaults to `mlirGetDialectHandle__{name}__`, which the
+    /// generated C++ code defines. The generated code keeps defining the
+    /// default symbol, so a custom one must be provided by a linked library,
+    /// e.g., an upstream MLIR C API or one of the `cpp_file`s. It must be a
+    /// legal C identifier.
+    pub fn dialect_handle_symbol(mut self, symbol: impl Into<String>) -> Self {
+        self.dialect_handle_symbol = Some(symbol.into());
+        self
+    }
 
-            tblgen_runner.generate_for_file(
-                td_file,
-                &self.include_dirs,
-                &inc_dir,
-                &self.name,
-                &contents,
-            )?;
-        }
+    /// Set the C++ language standard used to compile the generated code.
+    ///
+    /// Accepted values are `"c++14"`, `"c++17"`, `"c++20"`, and `"c++23"`.
+    /// If not set, defaults to `"c++17"`.
+    ///
+    /// # Errors
+    ///
+    /// The build will fail with [`Error::InvalidCppStandard`] if the value is
+    /// not one of the accepted standards.
+    pub fn cpp_std(mut self, std: impl Into<String>) -> Self {
+        self.cpp_std = Some(std.into());
+        self
+    }
 
-        let cpp_file = output_dir.join(format!("{}_capi.cpp", self.name));
-        cpp_gen::generate_cpp_registration(
-            &self.name,
-            &cpp_namespace,
-            &generated,
-            inc_subdir.as_deref(),
-            &cpp_file,
-        )?;
+    /// Define a preprocessor macro for the C++ compilation.
+    ///
+    /// A `None` value results in a bare `#define KEY`. The defines apply to
+    /// the generated registration code and the additional C++ files.
+    pub fn define(mut self, key: impl Into<String>, value: Option<String>) -> Self {
+        self.defines.push((key.into(), value));
+        self
+    }
 
-        Self::compile_cpp(
-            &self.name,
-            &cpp_file,
-            &self.cpp_files,
-            &self.include_dirs,
-            &inc_base, // Use base inc/ dir so includes like "bril/BrilOps.h.inc" resolve
-            &llvm_prefix,
-        )?;
+    /// Define multiple preprocessor macros for the C++ compilation.
+    pub fn defines<K: Into<String>>(
+        mut self,
+        defines: impl IntoIterator<Item = (K, Option<String>)>,
+    ) -> Self {
+        self.defines
+            .extend(defines.into_iter().map(|(key, value)| (key.into(), value)));
+        self
+    }
 
-        let rust_file = output_dir.join(format!("{}_register.rs", self.name));
-        rust_gen::generate_rust_ffi(&self.name, &rust_file)?;
+    /// Compile a C++ file registering the dialect's translation to LLVM IR.
+    ///
+    /// The file must define
+    /// `void register{Class}DialectTranslation(mlir::MLIRContext &)` in the
+    /// dialect's C++ namespace, typically attaching an
+    /// `LLVMTranslationDialectInterface` to the dialect. The generated bindings
+    /// expose it as `register_llvm_translations(context)`.
+    ///
+    /// Translation to LLVM IR needs libraries beyond the defaults, e.g.,
+    /// `MLIRTargetLLVMIRExport`; add them with [`link_lib`](Self::link_lib).
+    pub fn with_llvm_translation(mut self, cpp_file: impl AsRef<Path>) -> Self {
+        self.llvm_translation = Some(cpp_file.as_ref().to_path_buf());
+        self
+    }
 
-        for td_file in &self.td_files {
-            println!("cargo:rerun-if-changed={}", td_file.display());
-        }
+    /// Compile the C++ code with RTTI.
+    ///
+    /// By default `-fno-rtti` is passed, matching the default LLVM build.
+    /// Enable this when linking against an MLIR built with
+    /// `LLVM_ENABLE_RTTI=ON`, or when additional C++ files rely on
+    /// `dynamic_cast` or `typeid`.
+    pub fn enable_rtti(mut self, enable: bool) -> Self {
+        self.enable_rtti = enable;
+        self
+    }
 
-        for cpp_file in &self.cpp_files {
-            println!("cargo:rerun-if-changed={}", cpp_file.display());
-        }
+    /// Compile the C++ code with exceptions.
+    ///
+    /// By default `-fno-exceptions` is passed. Enable this when additional C++
+    /// files throw or catch exceptions.
+    pub fn enable_exceptions(mut self, enable: bool) -> Self {
+        self.enable_exceptions = enable;
+        self
+    }
 
-        Ok(())
+    /// Compile the C++ code as position-independent code (`-fPIC`), or not.
+    ///
+    /// If not set, `cc` decides per target, enabling it everywhere except on
+    /// Windows, bare-metal, and WebAssembly targets. Build scripts cannot see
+    /// the crate type, so force it on when linking the dialect into a
+    /// `cdylib` for a target where `cc` would not, e.g., in some
+    /// cross-compilation setups.
+    pub fn force_pic(mut self, pic: bool) -> Self {
+        self.force_pic = Some(pic);
+        self
     }
 
-    fn get_output_dir(&self) -> Result<PathBuf, Error> {
-        if let Some(ref dir) = self.output_dir {
-            Ok(dir.clone())
+    /// Set the optimization level (`0` to `3`) of the C++ compilation.
+    ///
+    /// If not set, the level of the Cargo profile (`OPT_LEVEL`) is used. The
+    /// generated op definitions are large, so raising this in debug builds
+    /// can noticeably speed up dialect registration, parsing, and
+    /// verification. Only the dialect library is affected; the crate itself
+    /// still follows its profile.
+    ///
+    /// Flags probed with `flag_if_supported` (e.g., `-fno-rtti`) are always
+    /// checked at `-O0` without debug info, so this setting does not affect
+    /// which of them are passed.
+    pub fn opt_level(mut self, level: u8) -> Self {
+        self.opt_level = Some(level);
+        self
+    }
+
+    /// Compile the C++ code with debug info.
+    ///
+    /// If not set, the setting of the Cargo profile (`DEBUG`) is used. Enable
+    /// this in release builds to step into verifiers and other generated
+    /// code. As with [`opt_level`](Self::opt_level), it does not affect which
+    /// flags `flag_if_supported` accepts.
+    pub fn debug_info(mut self, debug_info: bool) -> Self {
+        self.debug_info = Some(debug_info);
+        self
+    }
+
+    /// Set an explicit path to the `mlir-tblgen` binary.
+    ///
+    /// Use this when `mlir-tblgen` lives outside the LLVM prefix, e.g. in a
+    /// separately built tools directory or behind a wrapper script. If not
+    /// set, defaults to `{llvm_prefix}/bin/mlir-tblgen`.
+    pub fn tblgen_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.tblgen_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Add an extra flag to pass to every `mlir-tblgen` invocation.
+    ///
+    /// Flags are appended after the include directories and dialect
+    /// selection, in the order they were added, so they can override the
+    /// defaults (e.g., `--asmformat-error-is-fatal=false`).
+    pub fn tblgen_flag(mut self, flag: impl Into<String>) -> Self {
+        self.tblgen_flags.push(flag.into());
+        self
+    }
+
+    /// Add multiple extra flags to pass to every `mlir-tblgen` invocation.
+    pub fn tblgen_flags<S: Into<String>>(mut self, flags: impl IntoIterator<Item = S>) -> Self {
+        self.tblgen_flags.extend(flags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Define a TableGen preprocessor macro for every `mlir-tblgen`
+    /// invocation.
+    ///
+    /// Each name is passed as `-D{name}`, enabling `#ifdef {name}` blocks in
+    /// the TD files, e.g., to toggle experimental operations. Unlike
+    /// [`define`](Self::define), which sets C++ preprocessor macros for the
+    /// compilation of the generated code, this only affects what TableGen
+    /// sees. Content detection with [`DetectionMode::Regex`] ignores `#ifdef`s.
+    pub fn tablegen_define(mut self, name: impl Into<String>) -> Self {
+        self.tablegen_defines.push(name.into());
+        self
+    }
+
+    /// Define multiple TableGen preprocessor macros for every `mlir-tblgen`
+    /// invocation.
+    pub fn tablegen_defines<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.tablegen_defines
+            .extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only generate the operations whose full name (e.g., `bril.add`)
+    /// matches the given regex.
+    ///
+    /// The regex is passed to `mlir-tblgen` as `--op-include-regex` when
+    /// generating op declarations and definitions, which speeds up building
+    /// large dialects during development. Filtered-out operations are not
+    /// registered with the dialect and are left out of the generated Rust
+    /// code, so building them fails at runtime.
+    pub fn op_filter(mut self, regex: impl Into<String>) -> Self {
+        self.op_include_regex = Some(regex.into());
+        self
+    }
+
+    /// Skip the operations whose full name matches the given regex.
+    ///
+    /// The regex is passed to `mlir-tblgen` as `--op-exclude-regex`. As with
+    /// [`op_filter`](Self::op_filter), the skipped operations are not
+    /// registered.
+    pub fn op_exclude_filter(mut self, regex: impl Into<String>) -> Self {
+        self.op_exclude_regex = Some(regex.into());
+        self
+    }
+
+    /// Only register the operations with the given full names (e.g.,
+    /// `bril.add`) with the dialect, e.g., to teach a subset of it.
+    ///
+    /// Unlike [`op_filter`](Self::op_filter), this does not change what
+    /// `mlir-tblgen` generates: all operations are still compiled, but the
+    /// dialect's `initialize()` only adds the listed ones, and the generated
+    /// Rust code leaves the others out. Each operation generated but not
+    /// registered is reported with a `cargo:warning`.
+    pub fn register_only<S: AsRef<str>>(mut self, ops: impl IntoIterator<Item = S>) -> Self {
+        self.register_only
+            .get_or_insert_with(Vec::new)
+            .extend(ops.into_iter().map(|op| op.as_ref().to_string()));
+        self
+    }
+
+    /// Set how the contents of each TableGen file are detected.
+    ///
+    /// Defaults to [`DetectionMode::Regex`]. Use [`DetectionMode::Records`]
+    /// for files that define operations through `foreach`, `multiclass`, or
+    /// `defvar` expansion.
+    pub fn detection_mode(mut self, mode: DetectionMode) -> Self {
+        self.detection_mode = mode;
+        self
+    }
+
+    /// Detect the contents of the files each TD file includes as well.
+    ///
+    /// When enabled, `include "..."` directives resolvable from the including
+    /// file's directory or the include directories are followed, and the
+    /// definitions found there are attributed to the including file. This
+    /// lets a split layout pass only `BrilOps.td`, which includes
+    /// `BrilDialect.td`, and still generate the dialect declarations.
+    ///
+    /// Included files that are passed as TD files themselves, or that an
+    /// earlier TD file already includes, are skipped, so every definition is
+    /// generated once.
+    pub fn follow_includes(mut self, follow: bool) -> Self {
+        self.follow_includes = follow;
+        self
+    }
+
+    /// Add a library to link in addition to the default MLIR libraries.
+    ///
+    /// Use this for upstream interfaces your dialect depends on, e.g.,
+    /// `MLIRFunctionInterfaces` or `MLIRInferTypeOpInterface`.
+    pub fn link_lib(mut self, lib: impl Into<String>) -> Self {
+        self.link_libs.push(lib.into());
+        self
+    }
+
+    /// Add multiple libraries to link in addition to the default MLIR
+    /// libraries.
+    pub fn link_libs<S: Into<String>>(mut self, libs: impl IntoIterator<Item = S>) -> Self {
+        self.link_libs.extend(libs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Add a system library to link after the MLIR libraries.
+    ///
+    /// Use this for libraries the C++ files added with
+    /// [`cpp_file`](Self::cpp_file) depend on, e.g., `z` for zlib. The link
+    /// kind may be given as in Cargo's `rustc-link-lib` directive, e.g.,
+    /// `static=z`, `dylib=z`, or `framework=CoreFoundation`.
+    pub fn system_lib(mut self, lib: impl Into<String>) -> Self {
+        self.system_libs.push(lib.into());
+        self
+    }
+
+    /// Add multiple system libraries to link after the MLIR libraries.
+    pub fn system_libs<S: Into<String>>(mut self, libs: impl IntoIterator<Item = S>) -> Self {
+        self.system_libs.extend(libs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Do not link the default MLIR libraries (`MLIRIR`, `MLIRSupport`, and
+    /// `MLIRCAPIIR`).
+    ///
+    /// Only the libraries added with [`link_lib`](Self::link_lib) are linked.
+    pub fn clear_default_libs(mut self) -> Self {
+        self.default_link_libs = false;
+        self
+    }
+
+    /// Only link the default MLIR libraries that exist in the LLVM lib
+    /// directory.
+    ///
+    /// Some MLIR distributions merge or split the default libraries
+    /// differently. When enabled, each default library is looked up in the
+    /// lib directory (as a static archive or a shared library), and missing
+    /// ones are skipped with a `cargo:warning` instead of failing the build.
+    /// Otherwise, a missing default library is reported as
+    /// [`Error::MissingMlirLibrary`].
+    /// Libraries added with [`link_lib`](Self::link_lib) are always linked.
+    pub fn auto_link_libs(mut self, auto_link_libs: bool) -> Self {
+        self.auto_link_libs = auto_link_libs;
+        self
+    }
+
+    /// Link the MLIR libraries statically.
+    ///
+    /// When enabled, the MLIR libraries are linked as static archives, along
+    /// with the LLVM archives they depend on as reported by
+    /// `llvm-config --link-static --libs`.
+    ///
+    /// Static LLVM archives also depend on system libraries (commonly `libz`,
+    /// `libtinfo`, `libxml2`, `librt`, and `libdl` on Linux). These are taken
+    /// from `llvm-config --link-static --system-libs` and linked dynamically,
+    /// so their development packages must be installed on the build machine.
+    pub fn link_static(mut self, link_static: bool) -> Self {
+        self.link_static = link_static;
+        self
+    }
+
+    /// Set acronyms that are kept uppercase when deriving the dialect's C++
+    /// class name from its name.
+    ///
+    /// For example, with `["LLVM", "IR"]` the dialect `llvm_ir` maps to the
+    /// `LLVMIRDialect` class instead of `LlvmIrDialect`.
+    pub fn class_name_acronyms(
+        mut self,
+        acronyms: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.class_name_acronyms
+            .extend(acronyms.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set the name of the Rust type generated for an operation (see
+    /// [`generate_op_builders`](Self::generate_op_builders)).
+    ///
+    /// `op_name` is the operation's full name (e.g., `bril.add`). Operations
+    /// without a rename get their mnemonic in CamelCase followed by
+    /// `Operation` (e.g., `AddOperation`), as with the `melior::dialect!`
+    /// macro. `rust_name` must be a Rust identifier other than a keyword.
+    pub fn op_rename(mut self, op_name: impl Into<String>, rust_name: impl Into<String>) -> Self {
+        self.op_renames.push((op_name.into(), rust_name.into()));
+        self
+    }
+
+    /// Generate Markdown documentation for the dialect.
+    ///
+    /// When enabled, `mlir-tblgen -gen-dialect-doc` and `-gen-op-doc` are run
+    /// for the TD files defining the dialect and its operations, writing
+    /// `{stem}Dialect.md` and `{stem}.md` into the `docs/` subdirectory of the
+    /// output directory. The location is reported as a `cargo:warning`.
+    pub fn generate_docs(mut self, generate_docs: bool) -> Self {
+        self.generate_docs = generate_docs;
+        self
+    }
+
+    /// Generate typed Rust builder functions for the dialect's operations.
+    ///
+    /// When enabled, the generated bindings get a `build_{op}` function per
+    /// operation (e.g., `build_add(context, location, lhs, rhs, result_type)`)
+    /// that creates the operation with `OperationBuilder`, taking each operand,
+    /// attribute, and result type as a parameter. This needs no
+    /// `melior::dialect!` macro and works with TD files split across includes.
+    ///
+    /// Every operation also gets a type wrapping an `Operation` (e.g.,
+    /// `AddOperation` for `bril.add`, see [`op_rename`](Self::op_rename)) that
+    /// implements `OperationLike`. It is created with
+    /// `TryFrom<Operation>`, which checks the operation name and returns an
+    /// `OperationMismatch` holding the operation otherwise, and converts back
+    /// with `From`. Types of operations with regions also get an accessor per
+    /// region (e.g., `body_region()`) and `add_entry_block(&[Type])`, which
+    /// appends a block to the first region.
+    ///
+    /// Builder functions are only generated for operations with a fixed number
+    /// of operands and results and without regions, successors, or properties
+    /// for now; the others are marked with a TODO comment.
+    pub fn generate_op_builders(mut self, generate: bool) -> Self {
+        self.generate_op_builders = generate;
+        self
+    }
+
+    /// Generate a function creating a context with the dialect loaded.
+    ///
+    /// When enabled, the generated bindings get a `context_with_dialect()`
+    /// function that creates a context, registers and loads all of MLIR's
+    /// built-in dialects with `melior::utility::register_all_dialects`, and
+    /// then loads this dialect. Disabled by default, since it links every
+    /// upstream dialect into the final binary.
+    pub fn emit_context_helper(mut self, emit: bool) -> Self {
+        self.emit_context_helper = emit;
+        self
+    }
+
+    /// Do not generate the link anchor of the C API library.
+    ///
+    /// By default, the generated C++ code defines a `mlir{Dialect}LinkAnchor`
+    /// symbol that the Rust bindings read whenever they get the dialect
+    /// handle. This keeps linkers that drop unreferenced sections (e.g., with
+    /// `--gc-sections`) from stripping the registration code out of the static
+    /// library.
+    pub fn disable_link_anchor(mut self, disable: bool) -> Self {
+        self.disable_link_anchor = disable;
+        self
+    }
+
+    /// Write a `compile_commands.json` for the C++ code.
+    ///
+    /// When enabled, the output directory gets a compilation database with the
+    /// exact compiler invocation for the registration wrapper and each
+    /// additional C++ file, so that tools like clangd can resolve the MLIR
+    /// headers and generated `.inc` files.
+    pub fn emit_compile_commands(mut self, emit: bool) -> Self {
+        self.emit_compile_commands = emit;
+        self
+    }
+
+    /// Write a `{name}_build_info.json` summarizing the build.
+    ///
+    /// When enabled, the output directory gets a machine-readable record of
+    /// the LLVM installation, the `mlir-tblgen` binary, each TD file with a
+    /// hash of its content and its detected contents, and the generated
+    /// outputs, e.g., for reproducibility audits. The hashes detect changes
+    /// but are not cryptographic. Not supported by [`MultiDialectBuilder`].
+    pub fn emit_build_info(mut self, emit: bool) -> Self {
+        self.emit_build_info = emit;
+        self
+    }
+
+    /// Write a C header declaring the dialect's C API.
+    ///
+    /// When enabled, `{name}.h` is written to the output directory, declaring
+    /// `mlirGetDialectHandle__{name}__()` and the generated type
+    /// constructors, canonicalization, and pass registration functions with C
+    /// linkage, for consumers of the library outside of Rust.
+    pub fn emit_c_header(mut self, emit: bool) -> Self {
+        self.emit_c_header = emit;
+        self
+    }
+
+    /// Log the build steps as `cargo:warning`s.
+    ///
+    /// When enabled, the resolved LLVM prefix, the detected contents of each
+    /// TD file, every `mlir-tblgen` command line, and the generated `.inc`
+    /// files are printed. Setting `MELIOR_BUILD_VERBOSE=1` has the same
+    /// effect.
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Only validate the configuration and print the planned build steps.
+    ///
+    /// When enabled, `build()` checks the inputs, the namespace, the C++
+    /// standard, and the dialect name, detects the contents of the TD files
+    /// with [`DetectionMode::Regex`], and prints the `mlir-tblgen` invocations
+    /// and the C++ compile as `cargo:warning`s, without running `mlir-tblgen`
+    /// or the C++ compiler or writing any files. A missing LLVM installation
+    /// or `mlir-tblgen` binary is reported as a warning rather than an error.
+    ///
+    /// The returned artifacts hold the paths a real build would produce, with
+    /// no `.inc` files. Not supported by [`MultiDialectBuilder`].
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Require a specific LLVM major version (e.g., `21`).
+    ///
+    /// `build()` compares it against `llvm-config --version` and fails with
+    /// [`Error::LlvmVersionMismatch`] if they disagree.
+    pub fn require_llvm_version(mut self, major: u32) -> Self {
+        self.llvm_version = Some(major);
+        self
+    }
+
+    /// Build the dialect registration code.
+    ///
+    /// This will:
+    /// 1. Run `mlir-tblgen` to generate C++ `.inc` files
+    /// 2. Generate a C++ wrapper file with registration code
+    /// 3. Compile the C++ code into a static library in the output directory,
+    ///    unless the sources, generated `.inc` files, headers, compiler flags,
+    ///    and LLVM version are unchanged since the last build
+    /// 4. Generate Rust FFI bindings
+    ///
+    /// The generated Rust file should be included in your crate:
+    /// ```rust,ignore
+    /// include!(concat!(env!("OUT_DIR"), "/{name}_register.rs"));
+    /// ```
+    ///
+    /// If a different name is set with
+    /// [`register_file_name`](Self::register_file_name), include that file
+    /// instead.
+    ///
+    /// TD files and include directories added more than once (e.g., with
+    /// different spellings of the same path) are only used once, in the order
+    /// they were first added.
+    ///
+    /// Fails with [`Error::NoDialectDefinition`] if the TD files define
+    /// operations, but neither they nor their includes define the dialect.
+    ///
+    /// Returns the paths of the generated files.
+    pub fn build(mut self) -> Result<BuildArtifacts, Error> {
+        self.deduplicate_inputs();
+        self.check_inputs()?;
+        self.check_dialect_name()?;
+        self.check_cpp_namespace()?;
+        let output_dir = self.get_output_dir()?;
+        if self.dry_run {
+            return self.plan(&output_dir);
+        }
+        let llvm = self.get_llvm()?;
+        self.check_llvm_version(&llvm)?;
+
+        let artifacts = match &self.prebuilt {
+            Some((_, capi_file)) => {
+                self.generate_prebuilt(&output_dir, capi_file, llvm.prefix.clone())?
+            }
+            None => self.generate(&output_dir, llvm.prefix.clone())?,
+        };
+
+        let build = self.cpp_build(
+            &artifacts.capi_file,
+            // Use base inc/ dir so includes like "bril/BrilOps.h.inc" resolve
+            &self.inc_base_dir(&output_dir),
+            &llvm,
+        )?;
+        if self.emit_compile_commands {
+            self.write_compile_commands(&build, &output_dir)?;
+        }
+        self.compile_cpp(&build, &llvm, &output_dir)?;
+        if self.emit_build_info {
+            std::fs::write(
+                output_dir.join(format!("{}_build_info.json", self.name)),
+                self.build_info(&artifacts, &llvm)?,
+            )?;
+        }
+
+        self.emit_rerun_if_changed()?;
+
+        Ok(artifacts)
+    }
+
+    /// Build the dialect, or skip the build if no LLVM installation is found.
+    ///
+    /// Without LLVM, this prints a `cargo:warning` and only writes the Rust
+    /// bindings, derived from the TD files as with
+    /// [`use_prebuilt`](Self::use_prebuilt), so that `include!`ing them still
+    /// compiles. Nothing is linked, so this suits documentation builds (e.g.,
+    /// on docs.rs, which has no LLVM toolchain), but not binaries. Use
+    /// [`build`](Self::build) where a missing LLVM should fail the build:
+    ///
+    /// ```rust,ignore
+    /// let builder = DialectBuilder::new("bril").td_file("src/dialect/Bril.td");
+    /// if std::env::var_os("DOCS_RS").is_some() {
+    ///     builder.skip_if_no_llvm().expect("Failed to build dialect");
+    /// } else {
+    ///     builder.build().expect("Failed to build dialect");
+    /// }
+    /// ```
+    pub fn skip_if_no_llvm(self) -> Result<(), Error> {
+        let llvm = self.get_llvm();
+        self.build_or_skip(llvm)
+    }
+
+    /// Builds the dialect if LLVM was found, and writes only the Rust bindings
+    /// otherwise.
+    fn build_or_skip(mut self, llvm: Result<LlvmConfig, Error>) -> Result<(), Error> {
+        match llvm {
+            Ok(_) => self.build().map(drop),
+            Err(Error::LlvmNotFound) => {
+                self.deduplicate_inputs();
+                self.check_inputs()?;
+                println!(
+                    "cargo:warning=melior-build: {} The {} dialect is not built, and its Rust \
+                     bindings are not linked.",
+                    Error::LlvmNotFound,
+                    self.name
+                );
+                self.generate_rust_without_tblgen(&self.get_output_dir()?)?;
+                Ok(())
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Returns the prefix of the LLVM installation `build()` would use.
+    ///
+    /// This runs the same discovery as `build()` (`llvm-config`, then the
+    /// `MLIR_SYS_*_PREFIX` and `LLVM_PREFIX` environment variables) without
+    /// generating or compiling anything, e.g., to locate other LLVM tools or
+    /// libraries from a build script. The version required by
+    /// [`require_llvm_version`](Self::require_llvm_version) is not checked.
+    pub fn resolve_llvm_prefix(&self) -> Result<PathBuf, Error> {
+        Ok(self.get_llvm()?.prefix)
+    }
+
+    /// Returns the path of the `mlir-tblgen` binary `build()` would run.
+    ///
+    /// This is the path set with [`tblgen_path`](Self::tblgen_path), or
+    /// `{llvm_prefix}/bin/mlir-tblgen` otherwise. Fails if the binary does not
+    /// exist.
+    pub fn resolve_tblgen_path(&self) -> Result<PathBuf, Error> {
+        let tblgen_path = match &self.tblgen_path {
+            Some(path) => path.clone(),
+            None => tblgen::default_tblgen_path(&self.resolve_llvm_prefix()?),
+        };
+
+        if tblgen_path.exists() {
+            Ok(tblgen_path)
         } else {
-            std::env::var("OUT_DIR")
-                .map(PathBuf::from)
-                .map_err(|_| Error::MissingOutDir)
+            Err(Error::TblgenNotFound(tblgen_path))
+        }
+    }
+
+    /// Returns the detected contents of each TD file, without generating or
+    /// compiling anything.
+    ///
+    /// Detection scans the TD files themselves, and their includes when
+    /// [`follow_includes`](Self::follow_includes) is enabled, as `build()`
+    /// does in the default [`DetectionMode::Regex`]. This lets a build script
+    /// branch on what the dialect defines, e.g., to link an extra library only
+    /// when it defines types.
+    pub fn inspect(&self) -> Result<Vec<(PathBuf, tblgen::TdFileContents)>, Error> {
+        self.check_inputs()?;
+        // Regex detection does not run mlir-tblgen
+        let tblgen_runner = tblgen::TblgenRunner::dry_run(Path::new(""), None);
+        let file_contents = self.detect_contents(&tblgen_runner, DetectionMode::Regex)?;
+
+        Ok(self.td_files.iter().cloned().zip(file_contents).collect())
+    }
+
+    /// Write the TableGen files, with their includes inlined, into one file
+    /// for the `melior::dialect!` macro, and return its path.
+    ///
+    /// Includes are inlined as by [`tblgen::flatten_includes`], and files
+    /// included by several TD files are inlined once. The file is only
+    /// rewritten when its content changes, so it can be kept in the source
+    /// tree, where the macro can reference it, without triggering rebuilds.
+    pub fn write_flattened_td(&self, path: impl AsRef<Path>) -> Result<PathBuf, Error> {
+        self.check_inputs()?;
+        let path = path.as_ref();
+
+        let mut inlined = Vec::new();
+        let mut output = String::new();
+        for td_file in &self.td_files {
+            tblgen::flatten_into(
+                td_file,
+                &self.tblgen_include_dirs(td_file),
+                &mut inlined,
+                &mut output,
+            )?;
+        }
+
+        if std::fs::read_to_string(path).ok().as_deref() != Some(output.as_str()) {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, output)?;
         }
+        Ok(path.to_path_buf())
+    }
+
+    /// Validates the remaining configuration and prints the planned
+    /// `mlir-tblgen` invocations and C++ compile, without running either.
+    fn plan(&self, output_dir: &Path) -> Result<BuildArtifacts, Error> {
+        let inc_subdir = self.namespace_subdir()?;
+        self.resolved_cpp_std()?;
+
+        let llvm = match self.get_llvm() {
+            Ok(llvm) => {
+                self.check_llvm_version(&llvm)?;
+                llvm
+            }
+            Err(error) => {
+                println!("cargo:warning=melior-build: {}", error);
+                LlvmConfig::from_prefix("")
+            }
+        };
+        // Fail on invalid operation filters, as a real build would
+        let _ = self.op_selector()?;
+        let tblgen_runner =
+            tblgen::TblgenRunner::dry_run(&llvm.prefix, self.tblgen_path.as_deref())
+                .extra_flags(self.tblgen_flags.iter().cloned())
+                .defines(self.tablegen_defines.iter().cloned())
+                .op_filter(
+                    self.op_include_regex.as_deref(),
+                    self.op_exclude_regex.as_deref(),
+                );
+        if !tblgen_runner.tblgen_path().exists() {
+            println!(
+                "cargo:warning=melior-build: {}",
+                Error::TblgenNotFound(tblgen_runner.tblgen_path().to_path_buf())
+            );
+        }
+
+        let file_contents = self.detect_contents(&tblgen_runner, DetectionMode::Regex)?;
+        let generated = self.track_contents(&file_contents)?;
+
+        let inc_base_dir = self.inc_base_dir(output_dir);
+        let inc_dir = output_subdir(&inc_base_dir, inc_subdir.as_deref());
+        for index in processing_order(&file_contents) {
+            let (td_file, contents) = (&self.td_files[index], &file_contents[index]);
+            println!(
+                "cargo:warning=melior-build: {}: {:?}",
+                td_file.display(),
+                contents
+            );
+            tblgen_runner.generate_for_file(
+                td_file,
+                &self.tblgen_include_dirs(td_file),
+                &inc_dir,
+                &self.name,
+                contents,
+            )?;
+        }
+
+        let capi_file = self.capi_file(output_dir);
+        let build = self.cpp_build(&capi_file, &inc_base_dir, &llvm)?;
+        let files = build
+            .get_files()
+            .map(|file| file.display().to_string())
+            .collect::<Vec<_>>();
+        match build.try_get_compiler() {
+            Ok(compiler) => println!(
+                "cargo:warning=melior-build: would compile {}",
+                command_line(
+                    compiler.path(),
+                    &compiler
+                        .args()
+                        .iter()
+                        .map(|arg| arg.to_string_lossy().into_owned())
+                        .chain(files)
+                        .collect::<Vec<_>>()
+                )
+            ),
+            Err(_) => println!(
+                "cargo:warning=melior-build: would compile {}",
+                files.join(" ")
+            ),
+        }
+
+        Ok(BuildArtifacts {
+            register_file: self.register_file(output_dir),
+            capi_file,
+            inc_files: Vec::new(),
+            docs_dir: self
+                .generate_docs
+                .then(|| output_subdir(&output_dir.join("docs"), inc_subdir.as_deref())),
+            c_headers: Vec::new(),
+            td_contents: generated.td_contents,
+            llvm_prefix: llvm.prefix,
+        })
+    }
+
+    /// Returns the path of the generated C++ registration code.
+    fn capi_file(&self, output_dir: &Path) -> PathBuf {
+        output_dir.join(
+            self.capi_file_name
+                .clone()
+                .unwrap_or_else(|| format!("{}_capi.cpp", self.name)),
+        )
+    }
+
+    /// Returns the path of the generated Rust bindings.
+    fn register_file(&self, output_dir: &Path) -> PathBuf {
+        output_dir.join(
+            self.register_file_name
+                .clone()
+                .unwrap_or_else(|| format!("{}_register.rs", self.name)),
+        )
+    }
+
+    /// Checks that the dialect name is valid, that the TD and C++ files exist,
+    /// and that the include directories are directories.
+    pub(crate) fn check_inputs(&self) -> Result<(), Error> {
+        // The name is used verbatim in C symbols and Rust module names
+        if !self.name.starts_with(|c: char| c.is_ascii_lowercase())
+            || !self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            return Err(Error::InvalidDialectName(self.name.clone()));
+        }
+
+        if let Some(pattern) = self.unmatched_td_globs.first() {
+            return Err(Error::NoTdFilesMatched(pattern.clone()));
+        }
+
+        if let Some(path) = self
+            .td_files
+            .iter()
+            .chain(&self.cpp_files)
+            .chain(&self.llvm_translation)
+            .chain(self.prebuilt.iter().map(|(_, capi_file)| capi_file))
+            .find(|path| !path.is_file())
+        {
+            return Err(Error::FileNotFound(path.clone()));
+        }
+
+        if let Some(dir) = self
+            .all_include_dirs()
+            .chain(&self.cpp_include_dirs)
+            .chain(self.prebuilt.iter().map(|(inc_dir, _)| inc_dir))
+            .find(|dir| !dir.is_dir())
+        {
+            return Err(Error::InvalidIncludeDir(dir.clone()));
+        }
+
+        if let Some(name) = &self.lib_name
+            && (name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+        {
+            return Err(Error::InvalidLibName(name.clone()));
+        }
+
+        if let Some((_, name)) = self
+            .op_renames
+            .iter()
+            .find(|(_, name)| !rust_gen::is_rust_identifier(name))
+        {
+            return Err(Error::InvalidOpRename(name.clone()));
+        }
+
+        if let Some(lib) = self.system_libs.iter().find(|lib| {
+            let name = match lib.split_once('=') {
+                Some((kind, name)) if ["static", "dylib", "framework"].contains(&kind) => name,
+                Some(_) => return true,
+                None => lib.as_str(),
+            };
+            name.is_empty()
+        }) {
+            return Err(Error::InvalidSystemLib(lib.clone()));
+        }
+
+        if let Some(name) = &self.rust_module_name
+            && !rust_gen::is_rust_identifier(name)
+        {
+            return Err(Error::InvalidRustModuleName(name.clone()));
+        }
+
+        if let Some(symbol) = &self.dialect_handle_symbol
+            && (!symbol.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                || !symbol
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_'))
+        {
+            return Err(Error::InvalidDialectHandleSymbol(symbol.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that no TD file defines more than one dialect and that the name
+    /// of the TableGen dialect definition, if it can be extracted, matches the
+    /// builder's name.
+    pub(crate) fn check_dialect_name(&self) -> Result<(), Error> {
+        for td_file in &self.td_files {
+            let names = tblgen::dialect_names(td_file)?;
+            if names.len() > 1 {
+                return Err(Error::MultipleDialects(names));
+            }
+        }
+
+        for file in self.dialect_search_files()? {
+            if let Some(name) = tblgen::dialect_name(&file)? {
+                if name != self.name {
+                    return Err(Error::DialectNameMismatch {
+                        builder: self.name.clone(),
+                        tablegen: name,
+                    });
+                }
+                return Ok(());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Warns if the C++ namespace set on the builder differs from the
+    /// `cppNamespace` of the TableGen dialect definition.
+    pub(crate) fn check_cpp_namespace(&self) -> Result<(), Error> {
+        if let Some(ns) = &self.cpp_namespace
+            && let Some(tablegen) = self.tablegen_cpp_namespace()?
+            && ns.trim().trim_start_matches("::") != tablegen
+        {
+            println!(
+                "cargo:warning=cpp_namespace '{}' of the {} dialect differs from the \
+                 cppNamespace '::{}' of its TableGen definition",
+                ns, self.name, tablegen
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns the TableGen include directories for a TD file: the global ones
+    /// followed by the ones added for this file.
+    fn tblgen_include_dirs(&self, td_file: &Path) -> Vec<PathBuf> {
+        let mut include_dirs = self
+            .include_dirs
+            .iter()
+            .chain(
+                self.td_file_include_dirs
+                    .iter()
+                    .filter(|(path, _)| path == td_file)
+                    .flat_map(|(_, dirs)| dirs),
+            )
+            .cloned()
+            .collect();
+        dedup_paths(&mut include_dirs);
+
+        include_dirs
+    }
+
+    /// Removes duplicate include directories and TD files, e.g., ones added
+    /// both by a workspace helper and by hand, keeping the first-seen order.
+    fn deduplicate_inputs(&mut self) {
+        dedup_paths(&mut self.include_dirs);
+        for (removed, kept) in dedup_paths(&mut self.td_files) {
+            // Per-file include directories follow the kept spelling
+            for (path, _) in &mut self.td_file_include_dirs {
+                if *path == removed {
+                    path.clone_from(&kept);
+                }
+            }
+        }
+        for (_, dirs) in &mut self.td_file_include_dirs {
+            dedup_paths(dirs);
+        }
+    }
+
+    /// Returns all TableGen include directories, global and per-file.
+    fn all_include_dirs(&self) -> impl Iterator<Item = &PathBuf> {
+        self.include_dirs
+            .iter()
+            .chain(self.td_file_include_dirs.iter().flat_map(|(_, dirs)| dirs))
+    }
+
+    /// Returns the files searched for the TableGen dialect definition: the TD
+    /// files, each followed by its includes when following them is enabled.
+    fn dialect_search_files(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut files = Vec::new();
+        for td_file in &self.td_files {
+            files.push(td_file.clone());
+            if self.follow_includes {
+                files.extend(tblgen::collect_includes(
+                    td_file,
+                    &self.tblgen_include_dirs(td_file),
+                )?);
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Generates the `.inc` files, the C++ registration code, and the Rust
+    /// bindings, without compiling anything.
+    fn generate(&self, output_dir: &Path, llvm_prefix: PathBuf) -> Result<BuildArtifacts, Error> {
+        std::fs::create_dir_all(output_dir)?;
+
+        // Get namespace-based subdirectory (e.g., "mlir::a::b" -> "a/b")
+        let inc_subdir = self.namespace_subdir()?;
+        let inc_base_dir = self.inc_base_dir(output_dir);
+        let generated = self.generate_inc_files(
+            &llvm_prefix,
+            output_dir,
+            &inc_base_dir,
+            inc_subdir.as_deref(),
+        )?;
+
+        let options = self.codegen_options(&generated);
+
+        let cpp_file = self.capi_file(output_dir);
+        cpp_gen::generate_cpp_registration(
+            &self.name,
+            &self.resolved_cpp_namespace()?,
+            &generated,
+            &options,
+            inc_subdir.as_deref(),
+            &cpp_file,
+        )?;
+        self.postprocess_cpp(&cpp_file)?;
+
+        let rust_file = self.register_file(output_dir);
+        rust_gen::generate_rust_ffi(&self.name, &generated, &options, &rust_file)?;
+
+        let c_headers = self.write_c_header(&generated, output_dir)?;
+
+        Ok(BuildArtifacts {
+            register_file: rust_file,
+            capi_file: cpp_file,
+            inc_files: list_inc_files(&output_subdir(&inc_base_dir, inc_subdir.as_deref()))?,
+            docs_dir: self
+                .generate_docs
+                .then(|| output_subdir(&output_dir.join("docs"), inc_subdir.as_deref())),
+            c_headers,
+            td_contents: generated.td_contents,
+            llvm_prefix,
+        })
+    }
+
+    /// Emits the Rust bindings for the vendored C++ code set with
+    /// [`use_prebuilt`](Self::use_prebuilt), without running `mlir-tblgen`.
+    fn generate_prebuilt(
+        &self,
+        output_dir: &Path,
+        capi_file: &Path,
+        llvm_prefix: PathBuf,
+    ) -> Result<BuildArtifacts, Error> {
+        let (rust_file, generated) = self.generate_rust_without_tblgen(output_dir)?;
+        let c_headers = self.write_c_header(&generated, output_dir)?;
+
+        let inc_dir = output_subdir(
+            &self.inc_base_dir(output_dir),
+            self.namespace_subdir()?.as_deref(),
+        );
+        Ok(BuildArtifacts {
+            register_file: rust_file,
+            capi_file: capi_file.to_path_buf(),
+            inc_files: if inc_dir.is_dir() {
+                list_inc_files(&inc_dir)?
+            } else {
+                Vec::new()
+            },
+            docs_dir: None,
+            c_headers,
+            td_contents: generated.td_contents,
+            llvm_prefix,
+        })
+    }
+
+    /// Writes the Rust bindings derived from the regex-detected contents of
+    /// the TD files, returning their path and the detected contents.
+    fn generate_rust_without_tblgen(
+        &self,
+        output_dir: &Path,
+    ) -> Result<(PathBuf, tblgen::GeneratedFiles), Error> {
+        std::fs::create_dir_all(output_dir)?;
+
+        // Regex detection does not run mlir-tblgen
+        let tblgen_runner = tblgen::TblgenRunner::dry_run(Path::new(""), None);
+        let file_contents = self.detect_contents(&tblgen_runner, DetectionMode::Regex)?;
+        let generated = self.track_contents(&file_contents)?;
+
+        let rust_file = self.register_file(output_dir);
+        rust_gen::generate_rust_ffi(
+            &self.name,
+            &generated,
+            &self.codegen_options(&generated),
+            &rust_file,
+        )?;
+
+        Ok((rust_file, generated))
+    }
+
+    /// Applies the [`cpp_postprocess`](Self::cpp_postprocess) hook, if any, to
+    /// a generated C++ file.
+    pub(crate) fn postprocess_cpp(&self, cpp_file: &Path) -> Result<(), Error> {
+        if let Some(CppPostprocess(postprocess)) = &self.cpp_postprocess {
+            let source = std::fs::read_to_string(cpp_file)?;
+            std::fs::write(cpp_file, postprocess(&source))?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes `{name}.h` into the output directory if enabled, returning the
+    /// written headers.
+    pub(crate) fn write_c_header(
+        &self,
+        generated: &tblgen::GeneratedFiles,
+        output_dir: &Path,
+    ) -> Result<Vec<PathBuf>, Error> {
+        if !self.emit_c_header {
+            return Ok(Vec::new());
+        }
+
+        let header = output_dir.join(format!("{}.h", self.name));
+        cpp_gen::generate_c_header(
+            &self.name,
+            generated,
+            &self.codegen_options(generated),
+            &header,
+        )?;
+        Ok(vec![header])
+    }
+
+    /// Returns the options for generating the C++ and Rust code of the
+    /// dialect from the `generated` files.
+    pub(crate) fn codegen_options(&self, generated: &tblgen::GeneratedFiles) -> CodegenOptions {
+        CodegenOptions {
+            class_name_acronyms: self.class_name_acronyms.clone(),
+            op_renames: self.op_renames.clone(),
+            lib_name: self.lib_name.clone(),
+            rust_module_name: self.rust_module_name.clone(),
+            dialect_handle_symbol: self.dialect_handle_symbol.clone(),
+            llvm_translation: self.llvm_translation.is_some(),
+            context_helper: self.emit_context_helper,
+            link_anchor: !self.disable_link_anchor,
+            // The operations are restricted to register_only already
+            registered_ops: self.register_only.as_ref().map(|_| {
+                generated
+                    .operations
+                    .iter()
+                    .filter_map(|operation| {
+                        generated
+                            .op_classes
+                            .iter()
+                            .find(|(name, _)| name == operation)
+                            .map(|(_, class)| class.clone())
+                    })
+                    .collect()
+            }),
+        }
+    }
+
+    /// Returns the C++ namespace, defaulting to the TableGen dialect's
+    /// `cppNamespace` and then to `mlir::{name}`.
+    fn resolved_cpp_namespace(&self) -> Result<String, Error> {
+        Ok(match &self.cpp_namespace {
+            Some(ns) => ns.clone(),
+            None => self
+                .tablegen_cpp_namespace()?
+                .unwrap_or_else(|| format!("mlir::{}", self.name)),
+        })
+    }
+
+    /// Returns the base directory of the `.inc` files, which the C++ code is
+    /// compiled against.
+    fn inc_base_dir(&self, output_dir: &Path) -> PathBuf {
+        match &self.prebuilt {
+            Some((inc_dir, _)) => inc_dir.clone(),
+            None => self
+                .inc_output_dir
+                .clone()
+                .unwrap_or_else(|| output_dir.join("inc")),
+        }
+    }
+
+    /// Detects the contents of every TD file, including those of its
+    /// includes when following them is enabled.
+    fn detect_contents(
+        &self,
+        tblgen_runner: &tblgen::TblgenRunner,
+        mode: DetectionMode,
+    ) -> Result<Vec<tblgen::TdFileContents>, Error> {
+        let detect = |td_file: &Path, include_dirs: &[PathBuf]| match mode {
+            DetectionMode::Regex => tblgen::detect_td_contents(td_file),
+            DetectionMode::Records => {
+                tblgen_runner.detect_td_contents_from_records(td_file, include_dirs)
+            }
+        };
+
+        // Files whose definitions are already attributed to a TD file
+        let mut claimed = Vec::new();
+        if self.follow_includes {
+            for td_file in &self.td_files {
+                claimed.push(std::fs::canonicalize(td_file)?);
+            }
+        }
+
+        let mut file_contents = Vec::with_capacity(self.td_files.len());
+        for td_file in &self.td_files {
+            let include_dirs = self.tblgen_include_dirs(td_file);
+            let mut contents = detect(td_file, &include_dirs)?;
+            if self.follow_includes {
+                for include in tblgen::collect_includes(td_file, &include_dirs)? {
+                    let canonical = std::fs::canonicalize(&include)?;
+                    if !claimed.contains(&canonical) {
+                        contents.merge(&detect(&include, &include_dirs)?);
+                        claimed.push(canonical);
+                    }
+                }
+            }
+
+            self.log(format_args!("{}: {:?}", td_file.display(), contents));
+            if contents.needs_custom_asm && self.cpp_files.is_empty() {
+                println!(
+                    "cargo:warning=melior-build: {} sets hasCustomAssemblyFormat, but no C++ \
+                     files were added. Add the hand-written parser and printer with \
+                     `.cpp_file(...)`.",
+                    td_file.display()
+                );
+            }
+            if contents.needs_verifier_impl && self.cpp_files.is_empty() {
+                println!(
+                    "cargo:warning=melior-build: {} sets hasVerifier or hasRegionVerifier, but \
+                     no C++ files were added. Add the hand-written verifier with \
+                     `.cpp_file(...)`.",
+                    td_file.display()
+                );
+            }
+            file_contents.push(contents);
+        }
+
+        Ok(file_contents)
+    }
+
+    /// Fails if the TD files define operations but no dialect.
+    ///
+    /// Without the dialect definition, the generated operations reference an
+    /// undefined dialect class and only the C++ compile fails. The includes
+    /// of every TD file are searched too, even if following them is disabled.
+    fn check_dialect_definition(
+        &self,
+        file_contents: &[tblgen::TdFileContents],
+    ) -> Result<(), Error> {
+        let Some(ops_index) = file_contents.iter().position(|contents| contents.has_ops) else {
+            return Ok(());
+        };
+        if file_contents.iter().any(|contents| contents.has_dialect) {
+            return Ok(());
+        }
+
+        for td_file in &self.td_files {
+            let include_dirs = self.tblgen_include_dirs(td_file);
+            for include in tblgen::collect_includes(td_file, &include_dirs)? {
+                if tblgen::detect_td_contents(&include)?.has_dialect {
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(Error::NoDialectDefinition {
+            ops_file: self.td_files[ops_index].clone(),
+            dialect_name: to_class_name(&self.name, &self.class_name_acronyms),
+        })
+    }
+
+    /// Records which TD file stems generate which content types, failing if
+    /// a content type is defined by more than one file.
+    fn track_contents(
+        &self,
+        file_contents: &[tblgen::TdFileContents],
+    ) -> Result<tblgen::GeneratedFiles, Error> {
+        // Track which TD file stems generated which content types
+        let mut generated = tblgen::GeneratedFiles {
+            td_contents: self
+                .td_files
+                .iter()
+                .cloned()
+                .zip(file_contents.iter().cloned())
+                .collect(),
+            ..Default::default()
+        };
+
+        for (td_file, contents) in self.td_files.iter().zip(file_contents) {
+            let stem = tblgen::td_file_stem(td_file)?;
+            if td_file.file_stem().and_then(|s| s.to_str()).is_none() {
+                println!(
+                    "cargo:warning=melior-build: the name of {} is not valid UTF-8, so its \
+                     generated files are named '{}'",
+                    td_file.display(),
+                    stem
+                );
+            }
+
+            // Helper to check for duplicates and track stems
+            let track_stem =
+                |has_content: bool, slot: &mut Option<String>, kind: &str| -> Result<(), Error> {
+                    if has_content {
+                        if let Some(existing) = slot.as_ref() {
+                            return Err(Error::DuplicateContent(format!(
+                                "{} defined in multiple TD files: {} and {}",
+                                kind, existing, stem
+                            )));
+                        }
+                        *slot = Some(stem.clone());
+                    }
+                    Ok(())
+                };
+
+            track_stem(contents.has_dialect, &mut generated.dialect_stem, "Dialect")?;
+            track_stem(contents.has_ops, &mut generated.ops_stem, "Ops")?;
+            track_stem(contents.has_types, &mut generated.types_stem, "Types")?;
+            track_stem(contents.has_attrs, &mut generated.attrs_stem, "Attrs")?;
+            track_stem(contents.has_enums, &mut generated.enums_stem, "Enums")?;
+            track_stem(
+                contents.has_op_interfaces,
+                &mut generated.op_interfaces_stem,
+                "OpInterfaces",
+            )?;
+            track_stem(
+                contents.has_type_interfaces,
+                &mut generated.type_interfaces_stem,
+                "TypeInterfaces",
+            )?;
+            track_stem(
+                contents.has_attr_interfaces,
+                &mut generated.attr_interfaces_stem,
+                "AttrInterfaces",
+            )?;
+            track_stem(
+                contents.has_rewriters,
+                &mut generated.rewriters_stem,
+                "Rewrite patterns",
+            )?;
+            track_stem(contents.has_passes, &mut generated.passes_stem, "Passes")?;
+            track_stem(
+                contents.has_bytecode,
+                &mut generated.bytecode_stem,
+                "Bytecode encodings",
+            )?;
+
+            generated.use_function_interface |= contents.has_function_interface;
+        }
+
+        Ok(generated)
+    }
+
+    /// Runs `mlir-tblgen` on every TD file, writing the `.inc` files into
+    /// `inc_base_dir` (or `{inc_base_dir}/{inc_subdir}` when a subdirectory is
+    /// given), and the documentation, if enabled, into the matching
+    /// `{output_dir}/docs` directory.
+    ///
+    /// Returns which TD file stems generated which content types.
+    fn generate_inc_files(
+        &self,
+        llvm_prefix: &Path,
+        output_dir: &Path,
+        inc_base_dir: &Path,
+        inc_subdir: Option<&Path>,
+    ) -> Result<tblgen::GeneratedFiles, Error> {
+        let is_op_selected = self.op_selector()?;
+        let mut tblgen_runner =
+            tblgen::TblgenRunner::new(llvm_prefix, self.tblgen_path.as_deref())?
+                .extra_flags(self.tblgen_flags.iter().cloned())
+                .defines(self.tablegen_defines.iter().cloned())
+                .op_filter(
+                    self.op_include_regex.as_deref(),
+                    self.op_exclude_regex.as_deref(),
+                )
+                .scratch_dir(self.scratch_dir.as_deref().unwrap_or(output_dir))
+                .verbose(self.is_verbose());
+        if let Some(dir) = &self.scratch_dir {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        // Create the actual output directory for .inc files
+        let inc_dir = output_subdir(inc_base_dir, inc_subdir);
+        std::fs::create_dir_all(&inc_dir)?;
+
+        let docs_dir = output_subdir(&output_dir.join("docs"), inc_subdir);
+        if self.generate_docs {
+            std::fs::create_dir_all(&docs_dir)?;
+            tblgen_runner = tblgen_runner.docs_dir(&docs_dir);
+        }
+
+        let file_contents = self.detect_contents(&tblgen_runner, self.detection_mode)?;
+        self.check_dialect_definition(&file_contents)?;
+        let mut generated = self.track_contents(&file_contents)?;
+
+        // Each TD file produces distinct outputs, so mlir-tblgen runs for all
+        // files concurrently. Files are started and their results collected
+        // in processing order, independent of the order they were added in,
+        // so the first error and the generated definitions stay deterministic.
+        let results = std::thread::scope(|scope| {
+            let handles = processing_order(&file_contents)
+                .into_iter()
+                .map(|index| (&self.td_files[index], &file_contents[index]))
+                .map(|(td_file, contents)| {
+                    let tblgen_runner = &tblgen_runner;
+                    let inc_dir = &inc_dir;
+                    scope.spawn(move || {
+                        let include_dirs = self.tblgen_include_dirs(td_file);
+                        tblgen_runner.generate_for_file(
+                            td_file,
+                            &include_dirs,
+                            inc_dir,
+                            &self.name,
+                            contents,
+                        )?;
+
+                        // Everything below is read from one records pass
+                        let needs_records = contents.has_types
+                            || contents.has_attrs
+                            || contents.has_enums
+                            || contents.has_dialect
+                            || contents.has_ops;
+                        let records = if needs_records {
+                            Some(tblgen_runner.records(td_file, &include_dirs)?)
+                        } else {
+                            None
+                        };
+                        let type_defs = if let Some(records) = &records
+                            && contents.has_types
+                        {
+                            records.type_defs(&self.name)
+                        } else {
+                            Vec::new()
+                        };
+                        let attr_defs = if let Some(records) = &records
+                            && contents.has_attrs
+                        {
+                            records.attr_defs(&self.name)
+                        } else {
+                            Default::default()
+                        };
+                        let enum_defs = if let Some(records) = &records
+                            && contents.has_enums
+                        {
+                            records.enum_defs()
+                        } else {
+                            Vec::new()
+                        };
+                        let summaries = if let Some(records) = &records
+                            && (contents.has_dialect || contents.has_ops)
+                        {
+                            records.summaries(&self.name)
+                        } else {
+                            Default::default()
+                        };
+                        let operations = if let Some(records) = &records
+                            && contents.has_ops
+                        {
+                            records.operations(&self.name)
+                        } else {
+                            Vec::new()
+                        };
+                        let op_defs = if let Some(records) = &records
+                            && contents.has_ops
+                            && self.generate_op_builders
+                        {
+                            records.op_defs(&self.name)
+                        } else {
+                            Vec::new()
+                        };
+                        let op_classes = if let Some(records) = &records
+                            && contents.has_ops
+                            && self.register_only.is_some()
+                        {
+                            records.op_classes(&self.name)
+                        } else {
+                            Vec::new()
+                        };
+
+                        Ok::<_, Error>((
+                            type_defs, attr_defs, enum_defs, summaries, operations, op_defs,
+                            op_classes,
+                        ))
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+                })
+                .collect::<Vec<_>>()
+        });
+
+        for result in results {
+            let (
+                type_defs,
+                (attr_defs, skipped_attrs),
+                enum_defs,
+                (dialect_summary, op_docs),
+                operations,
+                op_defs,
+                classes,
+            ) = result?;
+            generated.op_classes.extend(classes);
+            generated.type_defs.extend(type_defs);
+            generated.attr_defs.extend(attr_defs);
+            for attr in skipped_attrs {
+                println!(
+                    "cargo:warning=melior-build: {} of the {} dialect has parameters that \
+                     cannot be passed through the C API, so no constructor is generated for it",
+                    attr, self.name
+                );
+            }
+            generated.op_defs.extend(
+                op_defs
+                    .into_iter()
+                    .filter(|op_def| is_op_selected(&op_def.name)),
+            );
+            generated.enum_defs.extend(enum_defs);
+            generated.dialect_summary = generated.dialect_summary.or(dialect_summary);
+            generated.op_docs.extend(
+                op_docs
+                    .into_iter()
+                    .filter(|op_doc| is_op_selected(&op_doc.name)),
+            );
+            for operation in operations {
+                if is_op_selected(&operation) && !generated.operations.contains(&operation) {
+                    generated.operations.push(operation);
+                }
+            }
+        }
+
+        if let Some(register_only) = &self.register_only {
+            self.select_registered_ops(&mut generated, register_only);
+        }
+
+        if self.is_verbose() {
+            for path in list_inc_files(&inc_dir)? {
+                self.log(format_args!("generated {}", path.display()));
+            }
+        }
+
+        if self.generate_docs {
+            println!(
+                "cargo:warning=Documentation for the {} dialect written to {}",
+                self.name,
+                docs_dir.display()
+            );
+        }
+
+        Ok(generated)
+    }
+
+    /// Restricts the registered operations to `register_only`, leaving the
+    /// others out of the generated Rust code and warning about each of them.
+    /// [`codegen_options`](Self::codegen_options) then registers only the C++
+    /// classes of the remaining operations.
+    fn select_registered_ops(
+        &self,
+        generated: &mut tblgen::GeneratedFiles,
+        register_only: &[String],
+    ) {
+        for operation in &generated.operations {
+            if !register_only.contains(operation) {
+                println!(
+                    "cargo:warning=melior-build: {} is generated but not registered, as it is \
+                     not passed to register_only",
+                    operation
+                );
+            }
+        }
+        for operation in register_only {
+            if !generated.operations.contains(operation) {
+                println!(
+                    "cargo:warning=melior-build: register_only names {}, which the {} dialect \
+                     does not generate",
+                    operation, self.name
+                );
+            }
+        }
+
+        let is_registered = |name: &String| register_only.contains(name);
+        generated.operations.retain(is_registered);
+        generated
+            .op_defs
+            .retain(|op_def| is_registered(&op_def.name));
+        generated
+            .op_docs
+            .retain(|op_doc| is_registered(&op_doc.name));
+    }
+
+    /// Returns a predicate telling whether an operation passes the
+    /// [`op_filter`](Self::op_filter) and
+    /// [`op_exclude_filter`](Self::op_exclude_filter) regexes.
+    fn op_selector(&self) -> Result<impl Fn(&str) -> bool + use<>, Error> {
+        let compile = |regex: &Option<String>| {
+            regex
+                .as_deref()
+                .map(|regex| {
+                    regex::Regex::new(regex)
+                        .map_err(|error| Error::InvalidOpFilter(error.to_string()))
+                })
+                .transpose()
+        };
+        let include = compile(&self.op_include_regex)?;
+        let exclude = compile(&self.op_exclude_regex)?;
+
+        Ok(move |name: &str| {
+            include.as_ref().is_none_or(|regex| regex.is_match(name))
+                && !exclude.as_ref().is_some_and(|regex| regex.is_match(name))
+        })
+    }
+
+    /// Returns every path Cargo should watch for changes: the TD files and
+    /// their transitive includes, the additional C++ files, the include
+    /// directories, and all `.td` files found recursively within the include
+    /// directories.
+    fn rerun_if_changed_paths(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut paths = Vec::new();
+        paths.extend(self.manifest.iter().cloned());
+        paths.extend(self.td_files.iter().cloned());
+        paths.extend(self.cpp_files.iter().cloned());
+        paths.extend(self.llvm_translation.iter().cloned());
+        if let Some((inc_dir, capi_file)) = &self.prebuilt {
+            paths.push(capi_file.clone());
+            paths.push(inc_dir.clone());
+            collect_files(inc_dir, &["inc"], &mut paths);
+        }
+
+        for dir in self.all_include_dirs() {
+            if !paths.contains(dir) {
+                paths.push(dir.clone());
+                collect_files(dir, &["td"], &mut paths);
+            }
+        }
+
+        for td_file in &self.td_files {
+            for include in tblgen::collect_includes(td_file, &self.tblgen_include_dirs(td_file))? {
+                if !paths.contains(&include) {
+                    paths.push(include);
+                }
+            }
+        }
+
+        Ok(paths)
+    }
+
+    fn emit_rerun_if_changed(&self) -> Result<(), Error> {
+        for path in self.rerun_if_changed_paths()? {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+
+        Ok(())
+    }
+
+    fn get_output_dir(&self) -> Result<PathBuf, Error> {
+        if let Some(ref dir) = self.output_dir {
+            Ok(dir.clone())
+        } else {
+            std::env::var("OUT_DIR")
+                .map(PathBuf::from)
+                .map_err(|_| Error::MissingOutDir)
+        }
+    }
+
+    /// Locates the LLVM installation, running `llvm-config` at most once.
+    pub(crate) fn get_llvm(&self) -> Result<LlvmConfig, Error> {
+        let (llvm, source) =
+            Self::find_llvm(LlvmConfig::query(), &std::env::vars().collect::<Vec<_>>())?;
+        self.log(format_args!(
+            "LLVM prefix {} (from {})",
+            llvm.prefix.display(),
+            source
+        ));
+        Ok(llvm)
+    }
+
+    /// Returns the LLVM installation and where it was found, given what
+    /// `llvm-config` reported (if it ran) and the environment variables.
+    fn find_llvm(
+        llvm_config: Option<LlvmConfig>,
+        vars: &[(String, String)],
+    ) -> Result<(LlvmConfig, String), Error> {
+        let var = |name: &str| {
+            vars.iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| PathBuf::from(value))
+        };
+
+        // Try llvm-config first (most reliable when available)
+        if let Some(llvm) = llvm_config {
+            // Also check version-specific env var in case user wants to override
+            if let Some(major) = llvm.version.as_deref().and_then(parse_major_version) {
+                let name = format!("MLIR_SYS_{}0_PREFIX", major);
+                if let Some(prefix) = var(&name) {
+                    return Ok((
+                        LlvmConfig {
+                            version: llvm.version,
+                            ..LlvmConfig::from_prefix(prefix)
+                        },
+                        name,
+                    ));
+                }
+            }
+            return Ok((llvm, "llvm-config".to_string()));
+        }
+
+        // Then any MLIR_SYS_*_PREFIX, as set up for mlir-sys (e.g., on Windows
+        // where llvm-config is often not in PATH), preferring the newest version
+        if let Some((name, prefix)) = vars
+            .iter()
+            .filter_map(|(key, value)| Some((mlir_sys_prefix_major(key)?, key, value)))
+            .max_by_key(|(major, _, _)| *major)
+            .map(|(_, key, value)| (key.clone(), PathBuf::from(value)))
+        {
+            return Ok((LlvmConfig::from_prefix(prefix), name));
+        }
+
+        // Fallback to generic env var
+        if let Some(prefix) = var("LLVM_PREFIX") {
+            return Ok((LlvmConfig::from_prefix(prefix), "LLVM_PREFIX".to_string()));
+        }
+
+        Err(Error::LlvmNotFound)
+    }
+
+    /// Whether build steps are logged, via [`Self::verbose`] or the
+    /// `MELIOR_BUILD_VERBOSE` environment variable.
+    fn is_verbose(&self) -> bool {
+        self.verbose || std::env::var("MELIOR_BUILD_VERBOSE").is_ok_and(|value| value == "1")
+    }
+
+    /// Prints a `cargo:warning` if verbose logging is enabled.
+    fn log(&self, message: std::fmt::Arguments<'_>) {
+        if self.is_verbose() {
+            println!("cargo:warning=melior-build: {}", message);
+        }
+    }
+
+    /// Checks the LLVM major version against the required one, and warns when
+    /// it differs from a `MLIR_SYS_*_PREFIX` variable that is set.
+    pub(crate) fn check_llvm_version(&self, llvm: &LlvmConfig) -> Result<(), Error> {
+        let version = llvm.version.clone().or_else(|| {
+            let output = std::process::Command::new(llvm.prefix.join("bin").join("llvm-config"))
+                .arg("--version")
+                .output()
+                .ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        });
+        let Some(found) = version.as_deref().and_then(parse_major_version) else {
+            if let Some(expected) = self.llvm_version {
+                println!(
+                    "cargo:warning=Could not determine the LLVM version to check against the required version {}",
+                    expected
+                );
+            }
+            return Ok(());
+        };
+
+        if let Some(expected) = self.llvm_version
+            && expected != found
+        {
+            return Err(Error::LlvmVersionMismatch { expected, found });
+        }
+
+        let mut vars = std::env::vars()
+            .filter_map(|(var, _)| Some((mlir_sys_prefix_major(&var)?, var)))
+            .collect::<Vec<_>>();
+        vars.sort();
+        for (major, var) in vars {
+            if major != found {
+                println!(
+                    "cargo:warning={} is set, but llvm-config reports LLVM {}",
+                    var, found
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn llvm_config_args(args: &[&str]) -> Option<String> {
+        let output = std::process::Command::new("llvm-config")
+            .args(args)
+            .output()
+            .ok()?;
+        if output.status.success() {
+            Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the validated C++ standard, defaulting to `"c++17"`.
+    fn resolved_cpp_std(&self) -> Result<&str, Error> {
+        const SUPPORTED: &[&str] = &["c++14", "c++17", "c++20", "c++23"];
+
+        match self.cpp_std.as_deref() {
+            None => Ok("c++17"),
+            Some(std) if SUPPORTED.contains(&std) => Ok(std),
+            Some(std) => Err(Error::InvalidCppStandard(format!(
+                "cpp_std '{}' is not supported. Expected one of: {}.",
+                std,
+                SUPPORTED.join(", ")
+            ))),
+        }
+    }
+
+    /// Configures the `cc::Build` used to compile the registration wrapper and
+    /// any additional C++ sources.
+    fn cpp_build(
+        &self,
+        cpp_file: &Path,
+        inc_dir: &Path,
+        llvm: &LlvmConfig,
+    ) -> Result<cc::Build, Error> {
+        let mut build = cc::Build::new();
+        build
+            .file(cpp_file)
+            .cpp(true)
+            .std(self.resolved_cpp_std()?)
+            .include(inc_dir)
+            .define("MLIR_CAPI_BUILDING_LIBRARY", "1");
+        // Suppress warnings from LLVM/MLIR headers and generated code
+        for dir in llvm.include_dirs() {
+            build.flag_if_supported(isystem(&dir));
+        }
+        build
+            .flag_if_supported(isystem(inc_dir))
+            .flag_if_supported("-Wno-unused-parameter");
+
+        if !self.enable_rtti {
+            build.flag_if_supported("-fno-rtti");
+        }
+        if !self.enable_exceptions {
+            build.flag_if_supported("-fno-exceptions");
+        }
+        if let Some(pic) = self.force_pic {
+            build.pic(pic);
+        }
+
+        // Unset, cc falls back to the Cargo profile
+        if let Some(level) = self.opt_level {
+            build.opt_level(level.into());
+        }
+        if let Some(debug_info) = self.debug_info {
+            build.debug(debug_info);
+        }
+
+        for (key, value) in &self.defines {
+            build.define(key, value.as_deref());
+        }
+
+        // Add user-specified include directories
+        for dir in self.all_include_dirs().chain(&self.cpp_include_dirs) {
+            build.include(dir);
+        }
+
+        // Add additional C++ source files
+        for file in self.cpp_files.iter().chain(&self.llvm_translation) {
+            build.file(file);
+        }
+
+        if let Some(stdlib) = CPP_LINK_STDLIB {
+            build.cpp_link_stdlib(stdlib);
+        }
+
+        Ok(build)
+    }
+
+    fn compile_cpp(
+        &self,
+        build: &cc::Build,
+        llvm: &LlvmConfig,
+        output_dir: &Path,
+    ) -> Result<(), Error> {
+        self.check_link_libs(llvm)?;
+        let hash = compile_hash(
+            build,
+            llvm,
+            &self.inc_base_dir(output_dir),
+            self.all_include_dirs().chain(&self.cpp_include_dirs),
+        );
+        self.compile_cpp_cached(
+            build,
+            &self.resolved_lib_name(),
+            hash,
+            output_dir,
+            |build| self.try_compile(build),
+        )?;
+
+        for directive in self.link_directives(llvm) {
+            println!("{}", directive);
+        }
+
+        Ok(())
+    }
+
+    /// Compiles the C++ code into the library `lib_name` in `output_dir` with
+    /// `compile`, unless `hash`, as computed by [`compile_hash`], is unchanged
+    /// since the last successful compile, as recorded in
+    /// `{output_dir}/{lib_name}.compile.stamp`.
+    ///
+    /// Either way, the library's link directives are printed by
+    /// [`cpp_lib_directives`] rather than by `cc`, so a skipped compile links
+    /// the same way. Returns whether `compile` was called.
+    pub(crate) fn compile_cpp_cached(
+        &self,
+        build: &cc::Build,
+        lib_name: &str,
+        hash: Option<String>,
+        output_dir: &Path,
+        compile: impl FnOnce(&cc::Build) -> Result<(), Error>,
+    ) -> Result<bool, Error> {
+        let stamp = output_dir.join(format!("{}.compile.stamp", lib_name));
+
+        let compiled = if let Some(hash) = &hash
+            && lib_exists(output_dir, lib_name)
+            && std::fs::read_to_string(&stamp).is_ok_and(|existing| existing == *hash)
+        {
+            self.log(format_args!("{} is up to date", lib_name));
+            false
+        } else {
+            // A failed compile must not leave the stamp of earlier inputs behind
+            std::fs::remove_file(&stamp).ok();
+            let mut build = build.clone();
+            build.out_dir(output_dir).cargo_metadata(false);
+            compile(&build)?;
+            if let Some(hash) = hash {
+                std::fs::write(&stamp, hash)?;
+            }
+            true
+        };
+
+        for directive in cpp_lib_directives(lib_name, output_dir) {
+            println!("{}", directive);
+        }
+
+        Ok(compiled)
+    }
+
+    /// Returns the name of the compiled C API library.
+    fn resolved_lib_name(&self) -> String {
+        self.lib_name
+            .clone()
+            .unwrap_or_else(|| format!("{}_dialect", self.name))
+    }
+
+    /// Compiles the C++ code into the C API library.
+    fn try_compile(&self, build: &cc::Build) -> Result<(), Error> {
+        build
+            .try_compile(&self.resolved_lib_name())
+            .map_err(|error| {
+                Error::CppCompilationFailed(format!("the '{}' dialect: {}", self.name, error))
+            })
+    }
+
+    /// Writes `{output_dir}/compile_commands.json` for the files compiled by
+    /// `build`.
+    pub(crate) fn write_compile_commands(
+        &self,
+        build: &cc::Build,
+        output_dir: &Path,
+    ) -> Result<(), Error> {
+        let compiler = build.try_get_compiler().map_err(|error| {
+            Error::CppCompilationFailed(format!("the '{}' dialect: {}", self.name, error))
+        })?;
+        let args = compiler
+            .args()
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+
+        std::fs::write(
+            output_dir.join("compile_commands.json"),
+            compile_commands(compiler.path(), &args, build.get_files(), output_dir),
+        )?;
+
+        Ok(())
+    }
+
+    /// Formats the `{name}_build_info.json` summary of a build that produced
+    /// `artifacts` with the LLVM installation `llvm`.
+    fn build_info(&self, artifacts: &BuildArtifacts, llvm: &LlvmConfig) -> Result<String, Error> {
+        let path = |path: &Path| json_string(&path.display().to_string());
+        let paths = |paths: &[PathBuf]| {
+            let paths = paths
+                .iter()
+                .map(|file| format!("\n    {}", path(file)))
+                .collect::<Vec<_>>();
+            if paths.is_empty() {
+                "[]".to_string()
+            } else {
+                format!("[{}\n  ]", paths.join(","))
+            }
+        };
+        let tblgen_path = self
+            .tblgen_path
+            .clone()
+            .unwrap_or_else(|| tblgen::default_tblgen_path(&llvm.prefix));
+
+        let mut td_files = Vec::new();
+        for (td_file, contents) in &artifacts.td_contents {
+            let mut hasher = DefaultHasher::new();
+            std::fs::read(td_file)?.hash(&mut hasher);
+            td_files.push(format!(
+                "    {{\n      \"path\": {},\n      \"hash\": \"{:016x}\",\n      \"contents\": [{}]\n    }}",
+                path(td_file),
+                hasher.finish(),
+                contents
+                    .names()
+                    .into_iter()
+                    .map(json_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ));
+        }
+
+        Ok(format!(
+            r#"{{
+  "name": {name},
+  "llvm_prefix": {llvm_prefix},
+  "llvm_version": {llvm_version},
+  "tblgen": {tblgen},
+  "td_files": [
+{td_files}
+  ],
+  "register_file": {register_file},
+  "capi_file": {capi_file},
+  "library": {library},
+  "inc_files": {inc_files},
+  "docs_dir": {docs_dir},
+  "c_headers": {c_headers}
+}}
+"#,
+            name = json_string(&self.name),
+            llvm_prefix = path(&llvm.prefix),
+            llvm_version = llvm
+                .version
+                .as_deref()
+                .map_or_else(|| "null".to_string(), json_string),
+            tblgen = path(&tblgen_path),
+            td_files = td_files.join(",\n"),
+            register_file = path(&artifacts.register_file),
+            capi_file = path(&artifacts.capi_file),
+            library = json_string(&self.resolved_lib_name()),
+            inc_files = paths(&artifacts.inc_files),
+            docs_dir = artifacts
+                .docs_dir
+                .as_deref()
+                .map_or_else(|| "null".to_string(), path),
+            c_headers = paths(&artifacts.c_headers),
+        ))
+    }
+
+    /// Checks that the default MLIR libraries exist in the LLVM lib directory,
+    /// so a missing one fails the build script rather than the final link.
+    ///
+    /// Skipped with `auto_link_libs`, which drops missing libraries instead.
+    pub(crate) fn check_link_libs(&self, llvm: &LlvmConfig) -> Result<(), Error> {
+        if !self.default_link_libs || self.auto_link_libs {
+            return Ok(());
+        }
+
+        match DEFAULT_LINK_LIBS
+            .iter()
+            .find(|lib| !lib_exists(&llvm.lib_dir, lib))
+        {
+            Some(lib) => Err(Error::MissingMlirLibrary(format!(
+                "{} in {}",
+                lib,
+                llvm.lib_dir.display()
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the Cargo directives for linking against MLIR.
+    fn link_directives(&self, llvm: &LlvmConfig) -> Vec<String> {
+        let lib_dir = &llvm.lib_dir;
+        let kind = if self.link_static { "static=" } else { "" };
+        let (default_libs, missing_libs) = DEFAULT_LINK_LIBS
+            .iter()
+            .copied()
+            .filter(|_| self.default_link_libs)
+            .partition::<Vec<_>, _>(|lib| !self.auto_link_libs || lib_exists(lib_dir, lib));
+
+        let mut directives = vec![format!(
+            "cargo:rustc-link-search=native={}",
+            lib_dir.display()
+        )];
+        directives.extend(missing_libs.into_iter().map(|lib| {
+            format!(
+                "cargo:warning=The default MLIR library {} was not found in {}, so it is not linked",
+                lib,
+                lib_dir.display()
+            )
+        }));
+        directives.extend(
+            default_libs
+                .into_iter()
+                .chain(self.link_libs.iter().map(String::as_str))
+                .map(|lib| format!("cargo:rustc-link-lib={}{}", kind, lib)),
+        );
+
+        // Static archives must be followed by everything they depend on
+        if self.link_static {
+            if let Some(libs) = Self::llvm_config_args(&["--link-static", "--libs"]) {
+                directives.extend(llvm_config_link_directives(&libs, "static="));
+            }
+            if let Some(libs) = Self::llvm_config_args(&["--link-static", "--system-libs"]) {
+                directives.extend(llvm_config_link_directives(&libs, ""));
+            }
+        }
+
+        directives.extend(
+            self.system_libs
+                .iter()
+                .map(|lib| format!("cargo:rustc-link-lib={}", lib)),
+        );
+
+        directives
+    }
+}
+
+/// The C++ standard library the compiled code is linked against.
+#[cfg(target_os = "macos")]
+const CPP_LINK_STDLIB: Option<&str> = Some("c++");
+#[cfg(target_os = "linux")]
+const CPP_LINK_STDLIB: Option<&str> = Some("stdc++");
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+const CPP_LINK_STDLIB: Option<&str> = None;
+
+/// The MLIR libraries linked unless `clear_default_libs` is set.
+const DEFAULT_LINK_LIBS: &[&str] = &["MLIRIR", "MLIRSupport", "MLIRCAPIIR"];
+
+/// Returns the directives linking the C API library `lib_name` compiled into
+/// `lib_dir`, as `cc` prints them for a C++ library.
+fn cpp_lib_directives(lib_name: &str, lib_dir: &Path) -> Vec<String> {
+    let mut directives = vec![
+        format!("cargo:rustc-link-lib=static={}", lib_name),
+        format!("cargo:rustc-link-search=native={}", lib_dir.display()),
+    ];
+    if let Some(stdlib) = CPP_LINK_STDLIB {
+        directives.push(format!("cargo:rustc-link-lib={}", stdlib));
+    }
+    directives
+}
+
+/// The extensions of the C++ headers hashed by [`compile_hash`]; other files
+/// in include directories, such as TD files, only affect the compile through
+/// the generated `.inc` files.
+const CPP_HEADER_EXTENSIONS: &[&str] = &["h", "hh", "hpp", "hxx", "inc", "def"];
+
+/// Hashes everything that affects a compiled C API library: the compiler and
+/// archiver command lines, the LLVM version and its MLIR C API header, the
+/// C++ sources, the generated `.inc` files in `inc_dir`, and the C++ headers
+/// in the `include_dirs`.
+///
+/// Returns `None` if any of them cannot be read, so the code is compiled.
+fn compile_hash<'a>(
+    build: &cc::Build,
+    llvm: &LlvmConfig,
+    inc_dir: &Path,
+    include_dirs: impl IntoIterator<Item = &'a PathBuf>,
+) -> Option<String> {
+    // Looking up the tools also makes cc track their environment variables,
+    // so Cargo reruns the build when they change even if nothing is compiled
+    let compiler = build.try_get_compiler().ok()?;
+    let archiver = build.try_get_archiver().ok()?;
+    let mut files = build.get_files().map(Path::to_path_buf).collect::<Vec<_>>();
+    collect_files(inc_dir, &["inc"], &mut files);
+    for dir in include_dirs {
+        collect_files(dir, CPP_HEADER_EXTENSIONS, &mut files);
+    }
+
+    let mut hasher = DefaultHasher::new();
+    compiler.path().hash(&mut hasher);
+    compiler.args().hash(&mut hasher);
+    archiver.get_program().hash(&mut hasher);
+    archiver.get_args().collect::<Vec<_>>().hash(&mut hasher);
+    // An LLVM upgrade under the same prefix keeps the include paths, and the
+    // version is unknown without llvm-config, so hash a header as well
+    llvm.version.hash(&mut hasher);
+    std::fs::read(llvm.include_dir.join("mlir-c").join("IR.h"))
+        .ok()
+        .hash(&mut hasher);
+    for file in files {
+        file.hash(&mut hasher);
+        std::fs::read(&file).ok()?.hash(&mut hasher);
+    }
+    Some(format!("{:016x}", hasher.finish()))
+}
+
+/// Returns whether a static or shared library is present in a directory.
+fn lib_exists(lib_dir: &Path, lib: &str) -> bool {
+    [
+        format!("lib{}.a", lib),
+        format!("lib{}.so", lib),
+        format!("lib{}.dylib", lib),
+        format!("{}.lib", lib),
+    ]
+    .iter()
+    .any(|file| lib_dir.join(file).is_file())
+}
+
+/// Formats a `compile_commands.json` compilation database with one entry per
+/// file, each compiled by `compiler` with `args` from `directory`.
+fn compile_commands<'a>(
+    compiler: &Path,
+    args: &[String],
+    files: impl IntoIterator<Item = &'a Path>,
+    directory: &Path,
+) -> String {
+    let entries = files
+        .into_iter()
+        .map(|file| {
+            let mut file_args = args.to_vec();
+            file_args.push("-c".to_string());
+            file_args.push(file.display().to_string());
+
+            format!(
+                "  {{\n    \"directory\": {},\n    \"file\": {},\n    \"command\": {}\n  }}",
+                json_string(&directory.display().to_string()),
+                json_string(&file.display().to_string()),
+                json_string(&command_line(compiler, &file_args)),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    format!("[\n{}\n]\n", entries.join(",\n"))
+}
+
+/// Quotes a string as a JSON string literal.
+fn json_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Paths and version of an LLVM installation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LlvmConfig {
+    /// Installation prefix
+    prefix: PathBuf,
+    /// Full version string (e.g., "21.1.0"), if known
+    version: Option<String>,
+    /// Directory containing the LLVM and MLIR headers
+    include_dir: PathBuf,
+    /// Directory containing the LLVM and MLIR libraries
+    lib_dir: PathBuf,
+}
+
+impl LlvmConfig {
+    /// Runs `llvm-config --prefix --version --includedir --libdir` once.
+    fn query() -> Option<Self> {
+        Self::parse(&DialectBuilder::llvm_config_args(&[
+            "--prefix",
+            "--version",
+            "--includedir",
+            "--libdir",
+        ])?)
+    }
+
+    /// Parses the output of [`Self::query`], one value per line in the order
+    /// the flags were given.
+    fn parse(output: &str) -> Option<Self> {
+        let mut lines = output.lines().map(str::trim);
+        let mut next = || lines.next().filter(|line| !line.is_empty());
+
+        Some(Self {
+            prefix: next()?.into(),
+            version: Some(next()?.to_string()),
+            include_dir: next()?.into(),
+            lib_dir: next()?.into(),
+        })
+    }
+
+    /// Derives the paths from the prefix for an installation found without
+    /// `llvm-config`.
+    fn from_prefix(prefix: impl Into<PathBuf>) -> Self {
+        let prefix = prefix.into();
+
+        Self {
+            include_dir: prefix.join("include"),
+            lib_dir: prefix.join("lib"),
+            version: None,
+            prefix,
+        }
+    }
+
+    /// Returns the directories to search for LLVM and MLIR headers.
+    ///
+    /// Besides the reported include directory, this covers `{prefix}/include`
+    /// when it differs, and the `mlir-c` and `mlir` subdirectories of both,
+    /// since packaged distributions split the headers differently. Directories
+    /// other than the reported one are only returned if they exist.
+    fn include_dirs(&self) -> Vec<PathBuf> {
+        let mut bases = vec![self.include_dir.clone()];
+        let prefix_include = self.prefix.join("include");
+        if prefix_include != self.include_dir && prefix_include.is_dir() {
+            bases.push(prefix_include);
+        }
+
+        let mut dirs = bases.clone();
+        for base in &bases {
+            dirs.extend(
+                ["mlir-c", "mlir"]
+                    .into_iter()
+                    .map(|subdir| base.join(subdir))
+                    .filter(|dir| dir.is_dir()),
+            );
+        }
+        dirs
+    }
+}
+
+/// Convert linker flags reported by `llvm-config` into Cargo directives.
+///
+/// Handles `-lfoo`, `-L/dir`, and full library paths such as
+/// `/usr/lib/libz3.so`, prefixing library names with `kind` (e.g., `static=`).
+fn llvm_config_link_directives(output: &str, kind: &str) -> Vec<String> {
+    let mut directives = Vec::new();
+
+    for token in output.split_whitespace() {
+        if let Some(lib) = token.strip_prefix("-l") {
+            directives.push(format!("cargo:rustc-link-lib={}{}", kind, lib));
+        } else if let Some(dir) = token.strip_prefix("-L") {
+            directives.push(format!("cargo:rustc-link-search=native={}", dir));
+        } else {
+            let path = Path::new(token);
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            // Windows reports `LLVMSupport.lib`, Unix reports `libLLVMSupport.a`
+            let lib = stem
+                .strip_prefix("lib")
+                .filter(|_| path.extension().is_some_and(|ext| ext != "lib"))
+                .unwrap_or(stem);
+
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                directives.push(format!("cargo:rustc-link-search=native={}", dir.display()));
+            }
+            directives.push(format!("cargo:rustc-link-lib={}{}", kind, lib));
+        }
+    }
+
+    directives
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the target triple of the host running the tests.
+    fn host_target() -> String {
+        use std::env::consts::{ARCH, OS};
+
+        match OS {
+            "linux" if cfg!(target_env = "musl") => format!("{}-unknown-linux-musl", ARCH),
+            "linux" => format!("{}-unknown-linux-gnu", ARCH),
+            "macos" => format!("{}-apple-darwin", ARCH),
+            "windows" if cfg!(target_env = "msvc") => format!("{}-pc-windows-msvc", ARCH),
+            "windows" => format!("{}-pc-windows-gnu", ARCH),
+            os => format!("{}-unknown-{}", ARCH, os),
+        }
+    }
+
+    /// Returns the file name cc gives the static library `name` on the host.
+    fn static_lib_file(name: &str) -> String {
+        if cfg!(target_env = "msvc") {
+            format!("{}.lib", name)
+        } else {
+            format!("lib{}.a", name)
+        }
+    }
+
+    #[test]
+    fn test_namespace_subdir_mlir_prefix() {
+        let builder = DialectBuilder::new("test").cpp_namespace("mlir::bril");
+        assert_eq!(
+            builder.namespace_subdir().unwrap(),
+            Some(PathBuf::from("bril"))
+        );
+    }
+
+    #[test]
+    fn test_namespace_subdir_single_level_errors() {
+        // Single-level namespace should error - must use mlir::X pattern
+        let builder = DialectBuilder::new("test").cpp_namespace("bril");
+        let err = builder.namespace_subdir().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("must use the 'mlir::namespace' pattern")
+        );
+        assert!(err.to_string().contains("Did you mean 'mlir::bril'"));
+    }
+
+    #[test]
+    fn test_namespace_subdir_nested() {
+        let builder = DialectBuilder::new("test").cpp_namespace("mlir::a::b");
+        assert_eq!(
+            builder.namespace_subdir().unwrap(),
+            Some(Path::new("a").join("b"))
+        );
+    }
+
+    #[test]
+    fn test_namespace_subdir_not_under_mlir() {
+        let builder = DialectBuilder::new("test").cpp_namespace("foo::bril");
+        let err = builder.namespace_subdir().unwrap_err();
+        assert!(err.to_string().contains("Did you mean 'mlir::bril'"));
+    }
+
+    #[test]
+    fn test_namespace_subdir_empty_segment() {
+        let builder = DialectBuilder::new("test").cpp_namespace("mlir::::bril");
+        let err = builder.namespace_subdir().unwrap_err();
+        assert!(err.to_string().contains("has an empty segment"));
+    }
+
+    #[test]
+    fn test_namespace_subdir_none() {
+        let builder = DialectBuilder::new("test");
+        assert_eq!(builder.namespace_subdir().unwrap(), None);
+    }
+
+    #[test]
+    fn test_namespace_subdir_empty_string() {
+        let builder = DialectBuilder::new("test").cpp_namespace("");
+        assert_eq!(builder.namespace_subdir().unwrap(), None);
+    }
+
+    #[test]
+    fn test_namespace_subdir_whitespace() {
+        let builder = DialectBuilder::new("test").cpp_namespace("  mlir::bril  ");
+        assert_eq!(
+            builder.namespace_subdir().unwrap(),
+            Some(PathBuf::from("bril"))
+        );
+    }
+
+    #[test]
+    fn test_namespace_subdir_only_mlir() {
+        // "mlir" alone should error (single-level)
+        let builder = DialectBuilder::new("test").cpp_namespace("mlir");
+        let err = builder.namespace_subdir().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("must use the 'mlir::namespace' pattern")
+        );
+    }
+
+    #[test]
+    fn test_namespace_subdir_trailing_colons() {
+        let builder = DialectBuilder::new("test").cpp_namespace("mlir::bril::");
+        let err = builder.namespace_subdir().unwrap_err();
+        assert!(err.to_string().contains("invalid leading or trailing '::'"));
+    }
+
+    #[test]
+    fn test_namespace_subdir_leading_colons() {
+        let builder = DialectBuilder::new("test").cpp_namespace("::mlir::bril");
+        let err = builder.namespace_subdir().unwrap_err();
+        assert!(err.to_string().contains("invalid leading or trailing '::'"));
+    }
+
+    #[test]
+    fn test_cpp_std_default() {
+        let builder = DialectBuilder::new("test");
+        assert_eq!(builder.resolved_cpp_std().unwrap(), "c++17");
+    }
+
+    #[test]
+    fn test_cpp_std_invalid() {
+        let builder = DialectBuilder::new("test").cpp_std("c++98");
+        let err = builder.resolved_cpp_std().unwrap_err();
+        assert!(matches!(err, Error::InvalidCppStandard(_)));
+        assert!(err.to_string().contains("cpp_std 'c++98' is not supported"));
+    }
+
+    #[test]
+    fn test_cpp_std_reaches_cc_build() {
+        let temp_dir = std::env::temp_dir();
+        let builder = DialectBuilder::new("test").cpp_std("c++20");
+        let mut build = builder
+            .cpp_build(
+                &temp_dir.join("test_cpp_std_capi.cpp"),
+                &temp_dir,
+                &LlvmConfig::from_prefix("/opt/llvm"),
+            )
+            .unwrap();
+        build
+            .target("x86_64-unknown-linux-gnu")
+            .host("x86_64-unknown-linux-gnu")
+            .opt_level(0)
+            .out_dir(&temp_dir)
+            .cargo_metadata(false);
+
+        let compiler = build.get_compiler();
+        assert!(
+            compiler
+                .args()
+                .iter()
+                .any(|arg| arg.to_str() == Some("-std=c++20"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_generate_bril_split_includes() {
+        use crate::fake_tblgen::FakeTblgen;
+
+        let dir = std::env::temp_dir().join("test_generate_bril_split_includes");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let tblgen_path = FakeTblgen::default().write(&dir.join("mlir-tblgen"));
+
+        // The split layout of the bril example
+        let example_dir =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/bril_dialect/src/dialect");
+        let artifacts = DialectBuilder::new("bril")
+            .td_files(
+                ["BrilDialect.td", "BrilTypes.td", "BrilOps.td"]
+                    .map(|name| example_dir.join("bril").join(name)),
+            )
+            .include_dir(&example_dir)
+            .cpp_namespace("mlir::bril")
+            .tblgen_path(&tblgen_path)
+            .generate(&dir, PathBuf::from("/opt/llvm"))
+            .unwrap();
+
+        let content = std::fs::read_to_string(&artifacts.capi_file).unwrap();
+        for include in [
+            "bril/BrilDialectDialect.h.inc",
+            "bril/BrilTypesTypes.h.inc",
+            "bril/BrilTypesTypes.cpp.inc",
+            "bril/BrilOps.h.inc",
+            "bril/BrilOps.cpp.inc",
+        ] {
+            assert!(
+                content.contains(&format!("#include \"{include}\"")),
+                "missing include of {include}"
+            );
+        }
+        assert!(!content.contains("bril/BrilOpsTypes"));
+        assert!(!content.contains("bril/BrilTypes.h.inc"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_generate_inc_files_multiple_files() {
+        use crate::fake_tblgen::FakeTblgen;
+
+        let dir = std::env::temp_dir().join("test_generate_multiple_files");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let tblgen_path = FakeTblgen::default().write(&dir.join("mlir-tblgen"));
+
+        let td_files = [
+            ("MultiDialect.td", "def Multi_Dialect : Dialect {}\n"),
+            ("MultiOps.td", "def Multi_AddOp : Multi_Op<\"add\">;\n"),
+            (
+                "MultiTypes.td",
+                "def Multi_PtrType : Multi_Type<\"Ptr\">;\n",
+            ),
+            (
+                "MultiAttrs.td",
+                "def Multi_UnitAttr : Multi_Attr<\"Unit\">;\n",
+            ),
+        ]
+        .map(|(name, content)| {
+            let path = dir.join(name);
+            std::fs::write(&path, content).unwrap();
+            path
+        });
+
+        let generated = DialectBuilder::new("multi")
+            .td_files(&td_files)
+            .tblgen_path(&tblgen_path)
+            .generate_inc_files(Path::new("/opt/llvm"), &dir, &dir.join("inc"), None)
+            .unwrap();
+
+        assert_eq!(generated.dialect_stem.as_deref(), Some("MultiDialect"));
+        assert_eq!(generated.ops_stem.as_deref(), Some("MultiOps"));
+        assert_eq!(generated.types_stem.as_deref(), Some("MultiTypes"));
+        assert_eq!(generated.attrs_stem.as_deref(), Some("MultiAttrs"));
+        for name in [
+            "MultiDialectDialect.h.inc",
+            "MultiDialectDialect.cpp.inc",
+            "MultiOps.h.inc",
+            "MultiOps.cpp.inc",
+            "MultiTypesTypes.h.inc",
+            "MultiTypesTypes.cpp.inc",
+            "MultiAttrsAttrs.h.inc",
+            "MultiAttrsAttrs.cpp.inc",
+        ] {
+            assert!(dir.join("inc").join(name).exists(), "{name} not generated");
+        }
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_defines_reach_cc_build() {
+        let temp_dir = std::env::temp_dir();
+        let builder = DialectBuilder::new("test")
+            .define("BRIL_FEATURE", Some("2".to_string()))
+            .defines([("BRIL_BARE", None)]);
+        let mut build = builder
+            .cpp_build(
+                &temp_dir.join("test_defines_capi.cpp"),
+                &temp_dir,
+                &LlvmConfig::from_prefix("/opt/llvm"),
+            )
+            .unwrap();
+        build
+            .target("x86_64-unknown-linux-gnu")
+            .host("x86_64-unknown-linux-gnu")
+            .opt_level(0)
+            .out_dir(&temp_dir)
+            .cargo_metadata(false);
+
+        let compiler = build.get_compiler();
+        let args = compiler
+            .args()
+            .iter()
+            .filter_map(|arg| arg.to_str())
+            .collect::<Vec<_>>();
+        assert!(args.contains(&"-DBRIL_FEATURE=2"));
+        assert!(args.contains(&"-DBRIL_BARE"));
+        assert!(args.contains(&"-DMLIR_CAPI_BUILDING_LIBRARY=1"));
+    }
+
+    #[test]
+    fn test_cpp_compilation_failure() {
+        let dir = std::env::temp_dir().join("test_cpp_compilation_failure");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let cpp_file = dir.join("bogus.cpp");
+        std::fs::write(&cpp_file, "this is not C++;\n").unwrap();
+
+        let builder = DialectBuilder::new("bogus");
+        let mut build = builder
+            .cpp_build(&cpp_file, &dir, &LlvmConfig::from_prefix("/opt/llvm"))
+            .unwrap();
+        build
+            .target("x86_64-unknown-linux-gnu")
+            .host("x86_64-unknown-linux-gnu")
+            .opt_level(0)
+            .out_dir(&dir)
+            .cargo_metadata(false);
+
+        let error = builder.try_compile(&build).unwrap_err();
+        assert!(
+            matches!(&error, Error::CppCompilationFailed(message) if message.contains("'bogus'")),
+            "unexpected error: {error}"
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn test_lib_name_reaches_compile() {
+        let dir = std::env::temp_dir().join("test_lib_name_reaches_compile");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let cpp_file = dir.join("bril_capi.cpp");
+        std::fs::write(&cpp_file, "int bril_answer() { return 42; }\n").unwrap();
+
+        let builder = DialectBuilder::new("bril").lib_name("bril_example_capi");
+        let mut build = builder
+            .cpp_build(&cpp_file, &dir, &LlvmConfig::from_prefix("/opt/llvm"))
+            .unwrap();
+        build
+            .target("x86_64-unknown-linux-gnu")
+            .host("x86_64-unknown-linux-gnu")
+            .opt_level(0)
+            .out_dir(&dir)
+            .cargo_metadata(false);
+        builder.try_compile(&build).unwrap();
+
+        assert!(dir.join("libbril_example_capi.a").exists());
+        assert!(!dir.join("libbril_dialect.a").exists());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_invalid_dialect_name() {
+        for (name, suggestion) in [
+            ("my-dialect", "my_dialect"),
+            ("2d", "dialect_2d"),
+            ("My Dialect", "my_dialect"),
+            ("", "dialect"),
+            ("_bril", "bril"),
+        ] {
+            let err = DialectBuilder::new(name).check_inputs().unwrap_err();
+            assert!(matches!(&err, Error::InvalidDialectName(invalid) if invalid == name));
+            assert!(
+                err.to_string()
+                    .ends_with(&format!("Did you mean '{suggestion}'?")),
+                "{err}"
+            );
+        }
+
+        for name in ["bril", "my_dialect", "d2"] {
+            assert!(DialectBuilder::new(name).check_inputs().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_invalid_rust_module_name() {
+        for name in ["", "bril-registration", "crate::bril", "mod"] {
+            assert!(matches!(
+                DialectBuilder::new("bril")
+                    .rust_module_name(name)
+                    .check_inputs(),
+                Err(Error::InvalidRustModuleName(_))
+            ));
+        }
+        assert!(
+            DialectBuilder::new("bril")
+                .rust_module_name("bril_bindings")
+                .check_inputs()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_invalid_dialect_handle_symbol() {
+        for symbol in ["", "1handle", "mlir-handle", "mlir handle"] {
+            assert!(matches!(
+                DialectBuilder::new("bril")
+                    .dialect_handle_symbol(symbol)
+                    .check_inputs(),
+                Err(Error::InvalidDialectHandleSymbol(_))
+            ));
+        }
+        assert!(
+            DialectBuilder::new("bril")
+                .dialect_handle_symbol("mlirGetDialectHandle__llvm__")
+                .check_inputs()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_invalid_lib_name() {
+        for name in ["", "bril-dialect", "bril/capi"] {
+            assert!(matches!(
+                DialectBuilder::new("bril").lib_name(name).check_inputs(),
+                Err(Error::InvalidLibName(_))
+            ));
+        }
+        assert!(
+            DialectBuilder::new("bril")
+                .lib_name("bril_capi2")
+                .check_inputs()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_invalid_op_rename() {
+        for name in ["", "2Add", "bril::Add", "type", "Self", "_"] {
+            assert!(matches!(
+                DialectBuilder::new("bril")
+                    .op_rename("bril.add", name)
+                    .check_inputs(),
+                Err(Error::InvalidOpRename(_))
+            ));
+        }
+        assert!(
+            DialectBuilder::new("bril")
+                .op_rename("bril.add", "Type")
+                .check_inputs()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_dialect_name_matches_tablegen() {
+        let dir = std::env::temp_dir().join("test_dialect_name_check");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let td_file = dir.join("BrilDialect.td");
+        std::fs::write(
+            &td_file,
+            "def Bril_Dialect : Dialect {\n  let name = \"bril\";\n}\n",
+        )
+        .unwrap();
+
+        DialectBuilder::new("bril")
+            .td_file(&td_file)
+            .check_dialect_name()
+            .unwrap();
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_dialect_name_mismatch() {
+        let dir = std::env::temp_dir().join("test_dialect_name_mismatch");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let td_file = dir.join("BrilDialect.td");
+        std::fs::write(
+            &td_file,
+            "def Bril_Dialect : Dialect {\n  let name = \"brill\";\n}\n",
+        )
+        .unwrap();
+
+        let err = DialectBuilder::new("bril")
+            .td_file(&td_file)
+            .check_dialect_name()
+            .unwrap_err();
+        assert!(matches!(
+            &err,
+            Error::DialectNameMismatch { builder, tablegen }
+                if builder == "bril" && tablegen == "brill"
+        ));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_resolve_llvm_prefix() {
+        let builder = DialectBuilder::new("bril");
+        match (builder.resolve_llvm_prefix(), builder.get_llvm()) {
+            (Ok(prefix), Ok(llvm)) => assert_eq!(prefix, llvm.prefix),
+            (Err(Error::LlvmNotFound), Err(Error::LlvmNotFound)) => {}
+            (prefix, llvm) => panic!("inconsistent LLVM discovery: {prefix:?} and {llvm:?}"),
+        }
+    }
+
+    #[test]
+    fn test_resolve_tblgen_path() {
+        let dir = std::env::temp_dir().join("test_resolve_tblgen_path");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        let tblgen_path = dir.join("mlir-tblgen");
+        std::fs::write(&tblgen_path, "").unwrap();
+
+        let resolved = DialectBuilder::new("bril")
+            .tblgen_path(&tblgen_path)
+            .resolve_tblgen_path()
+            .unwrap();
+        assert_eq!(resolved, tblgen_path);
+        assert_eq!(
+            tblgen::TblgenRunner::new(Path::new("/opt/llvm"), Some(&tblgen_path))
+                .unwrap()
+                .tblgen_path(),
+            resolved
+        );
+
+        let err = DialectBuilder::new("bril")
+            .tblgen_path(dir.join("missing"))
+            .resolve_tblgen_path()
+            .unwrap_err();
+        assert!(matches!(err, Error::TblgenNotFound(path) if path == dir.join("missing")));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_td_contents_per_file() {
+        let dir = std::env::temp_dir().join("test_td_contents_per_file");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let types_file = dir.join("BrilTypes.td");
+        std::fs::write(&types_file, "def Bril_PtrType : Bril_Type<\"Ptr\">;\n").unwrap();
+        let ops_file = dir.join("BrilOps.td");
+        std::fs::write(&ops_file, "def Bril_AddOp : Bril_Op<\"add\">;\n").unwrap();
+
+        let builder = DialectBuilder::new("bril")
+            .td_file(&types_file)
+            .td_file(&ops_file)
+            .tblgen_path("/nonexistent/mlir-tblgen")
+            .output_dir(dir.join("out"))
+            .dry_run(true);
+        let runner = tblgen::TblgenRunner::dry_run(Path::new("/opt/llvm"), None);
+        let generated = builder
+            .track_contents(
+                &builder
+                    .detect_contents(&runner, DetectionMode::Regex)
+                    .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(generated.types_stem.as_deref(), Some("BrilTypes"));
+        assert_eq!(generated.ops_stem.as_deref(), Some("BrilOps"));
+
+        let artifacts = builder.build().unwrap();
+        let [(types_path, types), (ops_path, ops)] = artifacts.td_contents.as_slice() else {
+            panic!("expected one entry per TD file");
+        };
+        assert_eq!(types_path, &types_file);
+        assert!(types.has_types && !types.has_ops);
+        assert_eq!(ops_path, &ops_file);
+        assert!(ops.has_ops && !ops.has_types);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_op_selector() {
+        let builder = DialectBuilder::new("bril")
+            .op_filter(r"^bril\.(add|sub)$")
+            .op_exclude_filter("sub");
+        let is_op_selected = builder.op_selector().unwrap();
+        assert!(is_op_selected("bril.add"));
+        assert!(!is_op_selected("bril.sub"));
+        assert!(!is_op_selected("bril.mul"));
+
+        let is_op_selected = DialectBuilder::new("bril").op_selector().unwrap();
+        assert!(is_op_selected("bril.mul"));
+
+        let err = DialectBuilder::new("bril")
+            .op_filter("bril.(add")
+            .op_selector()
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidOpFilter(_)));
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let dir = std::env::temp_dir().join("test_dry_run");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let td_file = dir.join("Bril.td");
+        std::fs::write(
+            &td_file,
+            "def Bril_Dialect : Dialect {\n  let name = \"bril\";\n}\n\
+             def Bril_ConstOp : Op<Bril_Dialect, \"const\"> {}\n",
+        )
+        .unwrap();
+        let output_dir = dir.join("out");
+
+        let artifacts = DialectBuilder::new("bril")
+            .td_file(&td_file)
+            .tblgen_path("/nonexistent/mlir-tblgen")
+            .output_dir(&output_dir)
+            .dry_run(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(artifacts.register_file, output_dir.join("bril_register.rs"));
+        assert_eq!(artifacts.capi_file, output_dir.join("bril_capi.cpp"));
+        assert!(artifacts.inc_files.is_empty());
+        assert!(!output_dir.exists());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_tablegen_cpp_namespace() {
+        let dir = std::env::temp_dir().join("test_tablegen_cpp_namespace");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        let td_file = dir.join("BrilDialect.td");
+
+        for (namespace, resolved, subdir) in [
+            ("::mlir::bril", "mlir::bril", Some("bril")),
+            ("mlir::bril", "mlir::bril", Some("bril")),
+            ("::circt::bril", "circt::bril", None),
+        ] {
+            std::fs::write(
+                &td_file,
+                format!(
+                    "def Bril_Dialect : Dialect {{\n  let name = \"bril\";\n  \
+                     let cppNamespace = \"{namespace}\";\n}}\n"
+                ),
+            )
+            .unwrap();
+
+            let builder = DialectBuilder::new("bril").td_file(&td_file);
+            assert_eq!(builder.resolved_cpp_namespace().unwrap(), resolved);
+            assert_eq!(
+                builder.namespace_subdir().unwrap(),
+                subdir.map(PathBuf::from)
+            );
+        }
+
+        // A malformed TableGen namespace is an error, not a missing subdirectory
+        std::fs::write(
+            &td_file,
+            "def Bril_Dialect : Dialect {\n  let name = \"bril\";\n  \
+             let cppNamespace = \"::mlir::::bril\";\n}\n",
+        )
+        .unwrap();
+        let err = DialectBuilder::new("bril")
+            .td_file(&td_file)
+            .namespace_subdir()
+            .unwrap_err();
+        assert!(err.to_string().contains("has an empty segment"));
+
+        // An explicit namespace takes precedence
+        let builder = DialectBuilder::new("bril")
+            .td_file(&td_file)
+            .cpp_namespace("mlir::other");
+        assert_eq!(builder.resolved_cpp_namespace().unwrap(), "mlir::other");
+        assert_eq!(
+            builder.namespace_subdir().unwrap(),
+            Some(PathBuf::from("other"))
+        );
+        builder.check_cpp_namespace().unwrap();
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_multiple_dialects() {
+        let dir = std::env::temp_dir().join("test_multiple_dialects");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let td_file = dir.join("BrilDialect.td");
+        std::fs::write(
+            &td_file,
+            "def Bril_Dialect : Dialect {\n  let name = \"bril\";\n}\n\
+             def Toy_Dialect : Dialect {\n  let name = \"toy\";\n}\n",
+        )
+        .unwrap();
+
+        let err = DialectBuilder::new("bril")
+            .td_file(&td_file)
+            .check_dialect_name()
+            .unwrap_err();
+        assert!(matches!(&err, Error::MultipleDialects(names) if names == &["bril", "toy"]));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_includes() {
+        use crate::fake_tblgen::FakeTblgen;
+
+        let dir = std::env::temp_dir().join("test_follow_includes");
+        std::fs::remove_dir_all(&dir).ok();
+        let include_dir = dir.join("include");
+        std::fs::create_dir_all(include_dir.join("bril")).unwrap();
+
+        let tblgen_path = FakeTblgen::default().write(&dir.join("mlir-tblgen"));
+
+        std::fs::write(
+            include_dir.join("bril/BrilDialect.td"),
+            "include \"mlir/IR/OpBase.td\"\ndef Bril_Dialect : Dialect {\n  let name = \"bril\";\n}\n",
+        )
+        .unwrap();
+        let ops_file = dir.join("BrilOps.td");
+        std::fs::write(
+            &ops_file,
+            "include \"bril/BrilDialect.td\"\ndef Bril_AddOp : Bril_Op<\"add\">;\n",
+        )
+        .unwrap();
+        let umbrella_file = dir.join("Bril.td");
+        std::fs::write(&umbrella_file, "include \"BrilOps.td\"\n").unwrap();
+
+        let generate = |td_file: &Path, follow_includes: bool| {
+            DialectBuilder::new("bril")
+                .td_file(td_file)
+                .include_dir(&include_dir)
+                .tblgen_path(&tblgen_path)
+                .follow_includes(follow_includes)
+                .generate_inc_files(Path::new("/opt/llvm"), &dir, &dir.join("inc"), None)
+                .unwrap()
+        };
+
+        let generated = generate(&ops_file, false);
+        assert_eq!(generated.dialect_stem, None);
+        assert_eq!(generated.ops_stem.as_deref(), Some("BrilOps"));
+
+        let generated = generate(&ops_file, true);
+        assert_eq!(generated.dialect_stem.as_deref(), Some("BrilOps"));
+        assert_eq!(generated.ops_stem.as_deref(), Some("BrilOps"));
+
+        // A file that only includes others reports their combined contents
+        let generated = generate(&umbrella_file, true);
+        assert_eq!(generated.dialect_stem.as_deref(), Some("Bril"));
+        assert_eq!(generated.ops_stem.as_deref(), Some("Bril"));
+
+        // Included files that are passed directly keep their own definitions
+        let generated = DialectBuilder::new("bril")
+            .td_files([&ops_file, &include_dir.join("bril/BrilDialect.td")])
+            .include_dir(&include_dir)
+            .tblgen_path(&tblgen_path)
+            .follow_includes(true)
+            .generate_inc_files(Path::new("/opt/llvm"), &dir, &dir.join("inc"), None)
+            .unwrap();
+        assert_eq!(generated.dialect_stem.as_deref(), Some("BrilDialect"));
+        assert_eq!(generated.ops_stem.as_deref(), Some("BrilOps"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_no_dialect_definition() {
+        use crate::fake_tblgen::FakeTblgen;
+
+        let dir = std::env::temp_dir().join("test_no_dialect_definition");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let tblgen_path = FakeTblgen::default().write(&dir.join("mlir-tblgen"));
+
+        let ops_file = dir.join("BrilOps.td");
+        std::fs::write(
+            &ops_file,
+            "include \"mlir/IR/OpBase.td\"\ndef Bril_AddOp : Bril_Op<\"add\">;\n",
+        )
+        .unwrap();
+        let type_file = dir.join("BrilTypes.td");
+        std::fs::write(
+            &type_file,
+            "def Bril_PtrType : TypeDef<Bril_Dialect, \"Ptr\">;\n",
+        )
+        .unwrap();
+
+        let err = DialectBuilder::new("bril")
+            .td_files([&type_file, &ops_file])
+            .tblgen_path(&tblgen_path)
+            .generate_inc_files(Path::new("/opt/llvm"), &dir, &dir.join("inc"), None)
+            .unwrap_err();
+        assert!(
+            matches!(&err, Error::NoDialectDefinition { ops_file: file, .. } if file == &ops_file)
+        );
+        assert!(err.to_string().contains("(e.g., BrilDialect.td)"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_td_file_with_includes() {
+        use crate::fake_tblgen::FakeTblgen;
+
+        let dir = std::env::temp_dir().join("test_td_file_with_includes");
+        std::fs::remove_dir_all(&dir).ok();
+        let common_dir = dir.join("common");
+        let sibling_dir = dir.join("sibling");
+        std::fs::create_dir_all(&common_dir).unwrap();
+        std::fs::create_dir_all(&sibling_dir).unwrap();
+
+        let log_path = dir.join("log");
+        let tblgen_path = FakeTblgen::default()
+            .log(&log_path)
+            .write(&dir.join("mlir-tblgen"));
+
+        let dialect_file = dir.join("Ext.td");
+        std::fs::write(
+            &dialect_file,
+            "def Ext_Dialect : Dialect {\n  let name = \"ext\";\n}\n",
+        )
+        .unwrap();
+        let ops_file = dir.join("ExtOps.td");
+        std::fs::write(&ops_file, "def Ext_AddOp : Ext_Op<\"add\">;\n").unwrap();
+
+        let builder = DialectBuilder::new("ext")
+            .td_file(&dialect_file)
+            .td_file_with_includes(&ops_file, &[&sibling_dir])
+            .include_dir(&common_dir)
+            .tblgen_path(&tblgen_path);
+        builder.check_inputs().unwrap();
+        builder
+            .generate_inc_files(Path::new("/opt/llvm"), &dir, &dir.join("inc"), None)
+            .unwrap();
+
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        let sibling = sibling_dir.display().to_string();
+        let common = common_dir.display().to_string();
+        for line in log.lines() {
+            assert!(line.contains(&common));
+            // Scratch files are named after the TD file, e.g., `*_ExtOps_includes.td`
+            assert_eq!(line.contains(&sibling), line.contains("ExtOps"));
+        }
+        assert!(log.contains("-gen-op-defs"));
+        assert!(log.contains("-gen-dialect-defs"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_deduplicate_inputs() {
+        use crate::fake_tblgen::FakeTblgen;
+
+        let dir = std::env::temp_dir().join("test_deduplicate_inputs");
+        std::fs::remove_dir_all(&dir).ok();
+        let common_dir = dir.join("common");
+        std::fs::create_dir_all(&common_dir).unwrap();
+
+        let log_path = dir.join("log");
+        let tblgen_path = FakeTblgen::default()
+            .log(&log_path)
+            .write(&dir.join("mlir-tblgen"));
+
+        let dialect_file = dir.join("Dup.td");
+        std::fs::write(
+            &dialect_file,
+            "def Dup_Dialect : Dialect {\n  let name = \"dup\";\n}\n",
+        )
+        .unwrap();
+
+        let mut builder = DialectBuilder::new("dup")
+            .td_file(&dialect_file)
+            .td_file(dir.join(".").join("Dup.td"))
+            .include_dir(&common_dir)
+            .include_dir(common_dir.join("."))
+            .include_dir(dir.join("missing"))
+            .include_dir(dir.join("missing"))
+            .tblgen_path(&tblgen_path);
+        builder.deduplicate_inputs();
+        assert_eq!(builder.td_files, [dialect_file]);
+        assert_eq!(
+            builder.include_dirs,
+            [common_dir.clone(), dir.join("missing")]
+        );
+
+        builder.include_dirs.pop();
+        builder
+            .generate_inc_files(Path::new("/opt/llvm"), &dir, &dir.join("inc"), None)
+            .unwrap();
+
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        let include_flag = format!("-I\t{}", common_dir.display());
+        for line in log.lines() {
+            assert_eq!(line.matches(&include_flag).count(), 1, "{}", line);
+        }
+        assert_eq!(log.matches("-gen-dialect-decls").count(), 1);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_inspect() {
+        let example_dir =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/bril_dialect/src/dialect");
+        let td_files = ["BrilDialect.td", "BrilTypes.td", "BrilOps.td"]
+            .map(|name| example_dir.join("bril").join(name));
+
+        let contents = DialectBuilder::new("bril")
+            .td_files(&td_files)
+            .include_dir(&example_dir)
+            .inspect()
+            .unwrap();
+
+        assert_eq!(
+            contents
+                .iter()
+                .map(|(path, _)| path.clone())
+                .collect::<Vec<_>>(),
+            td_files
+        );
+        let [(_, dialect), (_, types), (_, ops)] = &contents[..] else {
+            panic!("expected three TD files, got {:?}", contents);
+        };
+        assert!(dialect.has_dialect && !dialect.has_types);
+        assert!(types.has_types && !types.has_ops);
+        assert!(ops.has_ops && !ops.has_types);
+
+        assert!(matches!(
+            DialectBuilder::new("bril")
+                .td_file(example_dir.join("Missing.td"))
+                .inspect(),
+            Err(Error::FileNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_build_info() {
+        let dir = std::env::temp_dir().join("test_build_info");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        let td_file = dir.join("Bril \"Ops\".td");
+        std::fs::write(&td_file, "def Bril_AddOp : Op<Bril_Dialect, \"add\">;\n").unwrap();
+
+        let llvm = LlvmConfig {
+            version: Some("21.1.0".to_string()),
+            ..LlvmConfig::from_prefix("/opt/llvm-21")
+        };
+        let artifacts = BuildArtifacts {
+            register_file: dir.join("bril_register.rs"),
+            capi_file: dir.join("bril_capi.cpp"),
+            inc_files: vec![
+                dir.join("inc/BrilOps.h.inc"),
+                dir.join("inc/BrilOps.cpp.inc"),
+            ],
+            docs_dir: None,
+            c_headers: Vec::new(),
+            td_contents: vec![(
+                td_file.clone(),
+                tblgen::TdFileContents {
+                    has_ops: true,
+                    ..Default::default()
+                },
+            )],
+            llvm_prefix: llvm.prefix.clone(),
+        };
+        let info = DialectBuilder::new("bril")
+            .td_file(&td_file)
+            .build_info(&artifacts, &llvm)
+            .unwrap();
+
+        // Every line is a complete JSON value, key, or bracket
+        let field = |key: &str| {
+            info.lines()
+                .find_map(|line| line.trim().strip_prefix(&format!("\"{}\": ", key)))
+                .map(|value| value.trim_end_matches(','))
+        };
+        assert_eq!(field("llvm_prefix"), Some("\"/opt/llvm-21\""));
+        assert_eq!(field("llvm_version"), Some("\"21.1.0\""));
+        assert_eq!(field("tblgen"), Some("\"/opt/llvm-21/bin/mlir-tblgen\""));
+        assert_eq!(
+            field("path"),
+            Some(json_string(&td_file.display().to_string()).as_str())
+        );
+        assert_eq!(field("contents"), Some("[\"ops\"]"));
+        assert_eq!(field("hash").map(str::len), Some(18));
+        assert_eq!(field("library"), Some("\"bril_dialect\""));
+        assert_eq!(field("docs_dir"), Some("null"));
+        assert_eq!(field("c_headers"), Some("[]"));
+        assert!(info.contains(&format!(
+            "\"inc_files\": [\n    {},\n    {}\n  ],",
+            json_string(&dir.join("inc/BrilOps.h.inc").display().to_string()),
+            json_string(&dir.join("inc/BrilOps.cpp.inc").display().to_string()),
+        )));
+        // Brackets and braces balance outside of strings
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut escaped = false;
+        for c in info.chars() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if in_string => escaped = true,
+                '"' => in_string = !in_string,
+                '{' | '[' if !in_string => depth += 1,
+                '}' | ']' if !in_string => depth -= 1,
+                _ => {}
+            }
+            assert!(depth >= 0);
+        }
+        assert_eq!((depth, in_string), (0, false));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_select_registered_ops() {
+        use tblgen::{OpDef, OpDoc};
+
+        let op_def = |name: &str| OpDef {
+            name: name.to_string(),
+            operands: vec!["lhs".to_string(), "rhs".to_string()],
+            attributes: Vec::new(),
+            results: vec![String::new()],
+            regions: Vec::new(),
+            fixed_arity: true,
+        };
+        let mut generated = tblgen::GeneratedFiles {
+            operations: ["bril.add", "bril.mul", "bril.sub"]
+                .map(String::from)
+                .to_vec(),
+            op_defs: vec![op_def("bril.add"), op_def("bril.mul")],
+            op_docs: vec![OpDoc {
+                name: "bril.mul".to_string(),
+                summary: "Multiplies two integers.".to_string(),
+            }],
+            op_classes: ["Add", "Mul", "Sub"]
+                .map(|op| {
+                    (
+                        format!("bril.{}", op.to_lowercase()),
+                        format!("::mlir::bril::{}Op", op),
+                    )
+                })
+                .to_vec(),
+            ..Default::default()
+        };
+
+        let builder = DialectBuilder::new("bril").register_only(["bril.sub", "bril.add"]);
+        builder.select_registered_ops(&mut generated, builder.register_only.as_ref().unwrap());
+        assert_eq!(generated.operations, ["bril.add", "bril.sub"]);
+        assert_eq!(generated.op_defs, [op_def("bril.add")]);
+        assert!(generated.op_docs.is_empty());
+        assert_eq!(
+            builder.codegen_options(&generated).registered_ops.unwrap(),
+            ["::mlir::bril::AddOp", "::mlir::bril::SubOp"]
+        );
+        assert_eq!(
+            DialectBuilder::new("bril")
+                .codegen_options(&generated)
+                .registered_ops,
+            None
+        );
+    }
+
+    #[test]
+    fn test_from_manifest() {
+        let dir = std::env::temp_dir().join("test_from_manifest");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(dir.join("dialect")).unwrap();
+        std::fs::write(
+            dir.join("dialect/MathDialect.td"),
+            "include \"MathBase.td\"\ndef Math_AbsOp : Op<Math_Dialect, \"abs\">;\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("dialect/MathTypes.td"),
+            "include \"MathBase.td\"\ndef Math_IntType : TypeDef<Math_Dialect, \"Int\">;\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("dialect/MathBase.td"),
+            "def Math_Dialect : Dialect;\n",
+        )
+        .unwrap();
+        let manifest = dir.join("dialect.toml");
+        std::fs::write(
+            &manifest,
+            "[dialect]\nname = \"math_ext\"\ntd_files = [\"dialect/MathDialect.td\", \"dialect/MathTypes.td\"]\ncpp_namespace = \"mlir::math_ext\"\ninclude_dirs = [\"dialect\"]\n",
+        )
+        .unwrap();
+
+        let builder = DialectBuilder::from_manifest(&manifest).unwrap();
+        assert_eq!(builder.name, "math_ext");
+        assert_eq!(
+            builder.td_files,
+            [
+                dir.join("dialect/MathDialect.td"),
+                dir.join("dialect/MathTypes.td")
+            ]
+        );
+        assert_eq!(builder.cpp_namespace.as_deref(), Some("mlir::math_ext"));
+        assert_eq!(builder.include_dirs, [dir.join("dialect")]);
+        assert!(
+            builder
+                .rerun_if_changed_paths()
+                .unwrap()
+                .contains(&manifest)
+        );
+
+        let flattened = builder.write_flattened_td(dir.join("math_ext.td")).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&flattened).unwrap(),
+            "def Math_Dialect : Dialect;\ndef Math_AbsOp : Op<Math_Dialect, \"abs\">;\ndef Math_IntType : TypeDef<Math_Dialect, \"Int\">;\n"
+        );
+
+        std::fs::write(&manifest, "[dialect]\ntd_files = []\n").unwrap();
+        let Err(Error::InvalidManifest { path, message }) =
+            DialectBuilder::from_manifest(&manifest)
+        else {
+            panic!("expected an invalid manifest error");
+        };
+        assert_eq!(path, manifest);
+        assert!(message.contains("missing field `name`"), "{}", message);
+        assert!(matches!(
+            DialectBuilder::from_manifest(dir.join("missing.toml")),
+            Err(Error::FileNotFound(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_compile_cpp_cached() {
+        let dir = std::env::temp_dir().join("test_compile_cpp_cached");
+        std::fs::remove_dir_all(&dir).ok();
+        let inc_dir = dir.join("inc");
+        std::fs::create_dir_all(&inc_dir).unwrap();
+        let capi_file = dir.join("cached_capi.cpp");
+        std::fs::write(&capi_file, "// registration\n").unwrap();
+        std::fs::write(inc_dir.join("CachedOps.h.inc"), "// ops\n").unwrap();
+
+        let builder = DialectBuilder::new("cached").output_dir(&dir);
+        let llvm = LlvmConfig::from_prefix("/opt/llvm");
+        let mut build = builder.cpp_build(&capi_file, &inc_dir, &llvm).unwrap();
+        build
+            .target(&host_target())
+            .host(&host_target())
+            .opt_level(0)
+            .cargo_metadata(false);
+
+        let hash = || compile_hash(&build, &llvm, &inc_dir, []);
+        // Stands in for cc, writing the library it would assemble
+        let compiles = std::cell::Cell::new(0);
+        let compile = |_: &cc::Build| -> Result<(), Error> {
+            compiles.set(compiles.get() + 1);
+            std::fs::write(dir.join(static_lib_file("cached_dialect")), "")?;
+            Ok(())
+        };
+
+        assert!(
+            builder
+                .compile_cpp_cached(&build, "cached_dialect", hash(), &dir, compile)
+                .unwrap()
+        );
+        assert!(
+            !builder
+                .compile_cpp_cached(&build, "cached_dialect", hash(), &dir, compile)
+                .unwrap()
+        );
+        assert_eq!(compiles.get(), 1);
+
+        // A changed .inc file is compiled again
+        std::fs::write(inc_dir.join("CachedOps.h.inc"), "// more ops\n").unwrap();
+        assert!(
+            builder
+                .compile_cpp_cached(&build, "cached_dialect", hash(), &dir, compile)
+                .unwrap()
+        );
+        assert!(
+            !builder
+                .compile_cpp_cached(&build, "cached_dialect", hash(), &dir, compile)
+                .unwrap()
+        );
+
+        // So is a missing library
+        std::fs::remove_file(dir.join(static_lib_file("cached_dialect"))).unwrap();
+        assert!(
+            builder
+                .compile_cpp_cached(&build, "cached_dialect", hash(), &dir, compile)
+                .unwrap()
+        );
+
+        // A failed compile removes the stamp
+        std::fs::write(&capi_file, "// changed registration\n").unwrap();
+        let fail = |_: &cc::Build| -> Result<(), Error> {
+            Err(Error::CppCompilationFailed("test".to_string()))
+        };
+        assert!(
+            builder
+                .compile_cpp_cached(&build, "cached_dialect", hash(), &dir, fail)
+                .is_err()
+        );
+        assert!(!dir.join("cached_dialect.compile.stamp").exists());
+        assert_eq!(compiles.get(), 3);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_compile_hash_include_dirs() {
+        let dir = std::env::temp_dir().join("test_compile_hash_include_dirs");
+        std::fs::remove_dir_all(&dir).ok();
+        let include_dir = dir.join("include");
+        std::fs::create_dir_all(include_dir.join("vendor")).unwrap();
+        std::fs::write(include_dir.join("vendor/Vendor.h"), "// header\n").unwrap();
+        std::fs::write(include_dir.join("vendor/Vendor.td"), "// td\n").unwrap();
+        let capi_file = dir.join("hash_capi.cpp");
+        std::fs::write(&capi_file, "// registration\n").unwrap();
+
+        let llvm = LlvmConfig::from_prefix("/opt/llvm");
+        let mut build = DialectBuilder::new("hash")
+            .cpp_build(&capi_file, &dir.join("inc"), &llvm)
+            .unwrap();
+        build
+            .target(&host_target())
+            .host(&host_target())
+            .opt_level(0)
+            .cargo_metadata(false);
+        let hash = || compile_hash(&build, &llvm, &dir.join("inc"), [&include_dir]).unwrap();
+        let initial = hash();
+
+        // Only headers are hashed
+        std::fs::write(include_dir.join("vendor/Vendor.td"), "// more td\n").unwrap();
+        assert_eq!(hash(), initial);
+        std::fs::write(include_dir.join("vendor/Vendor.h"), "// more header\n").unwrap();
+        assert_ne!(hash(), initial);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_cpp_lib_directives() {
+        let directives = cpp_lib_directives("bril_dialect", Path::new("/out"));
+        assert_eq!(directives[0], "cargo:rustc-link-lib=static=bril_dialect");
+        assert_eq!(directives[1], "cargo:rustc-link-search=native=/out");
+        assert_eq!(
+            directives.get(2),
+            CPP_LINK_STDLIB
+                .map(|stdlib| format!("cargo:rustc-link-lib={}", stdlib))
+                .as_ref()
+        );
+        assert_eq!(directives.len(), 2 + CPP_LINK_STDLIB.iter().count());
+    }
+    #[test]
+    fn test_cpp_include_dirs_not_passed_to_tblgen() {
+        use crate::fake_tblgen::FakeTblgen;
+
+        let dir = std::env::temp_dir().join("test_cpp_include_dirs");
+        std::fs::remove_dir_all(&dir).ok();
+        let td_include_dir = dir.join("td");
+        let cpp_include_dir = dir.join("external").join("include");
+        std::fs::create_dir_all(&td_include_dir).unwrap();
+        std::fs::create_dir_all(&cpp_include_dir).unwrap();
+
+        let log_path = dir.join("log");
+        let tblgen_path = FakeTblgen::default()
+            .log(&log_path)
+            .write(&dir.join("mlir-tblgen"));
+
+        std::fs::write(
+            dir.join("ExtDialect.td"),
+            "def Ext_Dialect : Dialect {\n  let name = \"ext\";\n}\n",
+        )
+        .unwrap();
+        let td_file = dir.join("ExtOps.td");
+        std::fs::write(
+            &td_file,
+            "include \"ExtDialect.td\"\ndef Ext_AddOp : Ext_Op<\"add\">;\n",
+        )
+        .unwrap();
+
+        let builder = DialectBuilder::new("ext")
+            .td_file(&td_file)
+            .include_dir(&td_include_dir)
+            .cpp_include_dir(&cpp_include_dir)
+            .tblgen_path(&tblgen_path);
+        builder.check_inputs().unwrap();
+        builder
+            .generate_inc_files(Path::new("/opt/llvm"), &dir, &dir.join("inc"), None)
+            .unwrap();
+
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        assert!(log.contains(&td_include_dir.display().to_string()));
+        assert!(!log.contains(&cpp_include_dir.display().to_string()));
+
+        let mut build = builder
+            .cpp_build(
+                &dir.join("ext_capi.cpp"),
+                &dir,
+                &LlvmConfig::from_prefix("/opt/llvm"),
+            )
+            .unwrap();
+        build
+            .target("x86_64-unknown-linux-gnu")
+            .host("x86_64-unknown-linux-gnu")
+            .opt_level(0)
+            .out_dir(&dir)
+            .cargo_metadata(false);
+        let args = build
+            .get_compiler()
+            .args()
+            .iter()
+            .filter_map(|arg| arg.to_str().map(String::from))
+            .collect::<Vec<_>>();
+        let has_include = |path: &Path| {
+            let path = path.display().to_string();
+            args.windows(2)
+                .any(|pair| pair[0] == "-I" && pair[1] == path)
+        };
+        assert!(has_include(&cpp_include_dir));
+        assert!(has_include(&td_include_dir));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_compile_commands() {
+        let dir = std::env::temp_dir().join("test_compile_commands");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let capi_file = dir.join("bril_capi.cpp");
+        let extra_file = dir.join("BrilPasses.cpp");
+        let builder = DialectBuilder::new("bril").cpp_file(&extra_file);
+        let mut build = builder
+            .cpp_build(&capi_file, &dir, &LlvmConfig::from_prefix("/opt/llvm"))
+            .unwrap();
+        build
+            .target("x86_64-unknown-linux-gnu")
+            .host("x86_64-unknown-linux-gnu")
+            .opt_level(0)
+            .out_dir(&dir)
+            .cargo_metadata(false);
+        builder.write_compile_commands(&build, &dir).unwrap();
+
+        let json = std::fs::read_to_string(dir.join("compile_commands.json")).unwrap();
+        let entries = json
+            .trim()
+            .strip_prefix('[')
+            .and_then(|json| json.strip_suffix(']'))
+            .unwrap()
+            .split("},")
+            .collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        for (entry, file) in entries.iter().zip([&capi_file, &extra_file]) {
+            let field = |name: &str| {
+                entry
+                    .lines()
+                    .find_map(|line| line.trim().strip_prefix(&format!("\"{}\": \"", name)))
+                    .map(|value| value.trim_end_matches(',').trim_end_matches('"'))
+                    .unwrap()
+            };
+            assert_eq!(field("directory"), dir.display().to_string());
+            assert_eq!(field("file"), file.display().to_string());
+            let command = field("command");
+            assert!(command.contains(&format!(
+                "-isystem{}",
+                Path::new("/opt/llvm/include").display()
+            )));
+            assert!(command.contains(&format!("-isystem{}", dir.display())));
+            assert!(command.ends_with(&format!("-c {}", file.display())));
+        }
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+        assert_eq!(json_string("tab\tnew\n"), r#""tab\tnew\n""#);
     }
 
-    fn get_llvm_prefix(&self) -> Result<PathBuf, Error> {
-        // Try llvm-config first (most reliable when available)
-        if let Some(prefix) = Self::llvm_config("--prefix") {
-            // Also check version-specific env var in case user wants to override
-            if let Some(version) = Self::llvm_config("--version")
-                && let Some(major) = version
-                    .split('.')
-                    .next()
-                    .and_then(|v| v.parse::<u32>().ok())
-            {
-                let var = format!("MLIR_SYS_{}0_PREFIX", major);
-                if let Ok(p) = std::env::var(&var) {
-                    return Ok(PathBuf::from(p));
-                }
-            }
-            return Ok(PathBuf::from(prefix));
-        }
+    #[test]
+    fn test_enable_rtti_and_exceptions() {
+        let temp_dir = std::env::temp_dir();
+        let args = |builder: DialectBuilder| {
+            let mut build = builder
+                .cpp_build(
+                    &temp_dir.join("test_rtti_capi.cpp"),
+                    &temp_dir,
+                    &LlvmConfig::from_prefix("/opt/llvm"),
+                )
+                .unwrap();
+            build
+                .target("x86_64-unknown-linux-gnu")
+                .host("x86_64-unknown-linux-gnu")
+                .opt_level(0)
+                .out_dir(&temp_dir)
+                .cargo_metadata(false);
+            build
+                .get_compiler()
+                .args()
+                .iter()
+                .filter_map(|arg| arg.to_str().map(String::from))
+                .collect::<Vec<_>>()
+        };
 
-        // Fallback to generic env var
-        if let Ok(prefix) = std::env::var("LLVM_PREFIX") {
-            return Ok(PathBuf::from(prefix));
-        }
+        let default = args(DialectBuilder::new("test"));
+        assert!(default.iter().any(|arg| arg == "-fno-rtti"));
+        assert!(default.iter().any(|arg| arg == "-fno-exceptions"));
 
-        Err(Error::LlvmNotFound)
+        let enabled = args(
+            DialectBuilder::new("test")
+                .enable_rtti(true)
+                .enable_exceptions(true),
+        );
+        assert!(!enabled.iter().any(|arg| arg == "-fno-rtti"));
+        assert!(!enabled.iter().any(|arg| arg == "-fno-exceptions"));
     }
 
-    fn llvm_config(arg: &str) -> Option<String> {
-        let output = std::process::Command::new("llvm-config")
-            .arg(arg)
-            .output()
-            .ok()?;
-        if output.status.success() {
-            Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
-        } else {
-            None
-        }
+    #[test]
+    fn test_force_pic() {
+        let temp_dir = std::env::temp_dir();
+        let args = |builder: DialectBuilder, target: &str| {
+            let mut build = builder
+                .cpp_build(
+                    &temp_dir.join("test_pic_capi.cpp"),
+                    &temp_dir,
+                    &LlvmConfig::from_prefix("/opt/llvm"),
+                )
+                .unwrap();
+            build
+                .target(target)
+                .host("x86_64-unknown-linux-gnu")
+                .opt_level(0)
+                .out_dir(&temp_dir)
+                .cargo_metadata(false);
+            build
+                .get_compiler()
+                .args()
+                .iter()
+                .filter_map(|arg| arg.to_str().map(String::from))
+                .collect::<Vec<_>>()
+        };
+
+        // cc does not compile position-independent code for bare-metal targets
+        let target = "x86_64-unknown-none";
+        assert!(!args(DialectBuilder::new("test"), target).contains(&"-fPIC".to_string()));
+        assert!(
+            args(DialectBuilder::new("test").force_pic(true), target)
+                .contains(&"-fPIC".to_string())
+        );
+
+        let target = "x86_64-unknown-linux-gnu";
+        assert!(args(DialectBuilder::new("test"), target).contains(&"-fPIC".to_string()));
+        assert!(
+            !args(DialectBuilder::new("test").force_pic(false), target)
+                .contains(&"-fPIC".to_string())
+        );
     }
 
-    fn compile_cpp(
-        name: &str,
-        cpp_file: &Path,
-        additional_cpp_files: &[PathBuf],
-        include_dirs: &[PathBuf],
-        inc_dir: &Path,
-        llvm_prefix: &Path,
-    ) -> Result<(), Error> {
-        let llvm_include = llvm_prefix.join("include");
+    #[test]
+    fn test_opt_level_and_debug_info() {
+        let temp_dir = std::env::temp_dir();
+        let args = |builder: DialectBuilder| {
+            let mut build = builder
+                .cpp_build(
+                    &temp_dir.join("test_opt_level_capi.cpp"),
+                    &temp_dir,
+                    &LlvmConfig::from_prefix("/opt/llvm"),
+                )
+                .unwrap();
+            build
+                .target("x86_64-unknown-linux-gnu")
+                .host("x86_64-unknown-linux-gnu")
+                .out_dir(&temp_dir)
+                .cargo_metadata(false);
+            build
+                .get_compiler()
+                .args()
+                .iter()
+                .filter_map(|arg| arg.to_str().map(String::from))
+                .collect::<Vec<_>>()
+        };
 
-        let mut build = cc::Build::new();
-        build
-            .file(cpp_file)
-            .cpp(true)
-            .std("c++17")
-            .include(inc_dir)
-            .define("MLIR_CAPI_BUILDING_LIBRARY", "1")
-            .flag_if_supported("-fno-rtti")
-            .flag_if_supported("-fno-exceptions")
-            // Suppress warnings from LLVM/MLIR headers and generated code
-            .flag_if_supported(format!("-isystem{}", llvm_include.display()))
-            .flag_if_supported(format!("-isystem{}", inc_dir.display()))
-            .flag_if_supported("-Wno-unused-parameter");
+        let optimized = args(DialectBuilder::new("test").opt_level(2).debug_info(false));
+        assert!(optimized.iter().any(|arg| arg == "-O2"));
+        assert!(!optimized.iter().any(|arg| arg.starts_with("-g")));
 
-        // Add user-specified include directories
-        for dir in include_dirs {
-            build.include(dir);
-        }
+        let debug = args(DialectBuilder::new("test").opt_level(0).debug_info(true));
+        assert!(debug.iter().any(|arg| arg == "-O0"));
+        assert!(debug.iter().any(|arg| arg.starts_with("-g")));
+    }
 
-        // Add additional C++ source files
-        for file in additional_cpp_files {
-            build.file(file);
-        }
+    #[cfg(unix)]
+    #[test]
+    fn test_generate_artifacts() {
+        use crate::fake_tblgen::FakeTblgen;
 
-        #[cfg(target_os = "macos")]
-        build.cpp_link_stdlib("c++");
-        #[cfg(target_os = "linux")]
-        build.cpp_link_stdlib("stdc++");
+        let dir = std::env::temp_dir().join("test_generate_artifacts");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
 
-        build.compile(&format!("{}_dialect", name));
+        let tblgen_path = FakeTblgen::default().write(&dir.join("mlir-tblgen"));
 
-        let lib_dir = llvm_prefix.join("lib");
-        println!("cargo:rustc-link-search=native={}", lib_dir.display());
-        println!("cargo:rustc-link-lib=MLIRIR");
-        println!("cargo:rustc-link-lib=MLIRSupport");
-        println!("cargo:rustc-link-lib=MLIRCAPIIR");
+        let td_file = dir.join("ArtOps.td");
+        std::fs::write(
+            &td_file,
+            "def Art_Dialect : Dialect {}\ndef Art_AddOp : Art_Op<\"add\">;\n",
+        )
+        .unwrap();
 
-        Ok(())
+        let output_dir = dir.join("out");
+        let artifacts = DialectBuilder::new("art")
+            .td_file(&td_file)
+            .cpp_namespace("mlir::art")
+            .tblgen_path(&tblgen_path)
+            .generate(&output_dir, PathBuf::from("/opt/llvm"))
+            .unwrap();
+
+        assert_eq!(artifacts.register_file, output_dir.join("art_register.rs"));
+        assert_eq!(artifacts.capi_file, output_dir.join("art_capi.cpp"));
+        assert!(artifacts.register_file.exists());
+        assert!(artifacts.capi_file.exists());
+        assert_eq!(
+            artifacts.inc_files,
+            [
+                "ArtOps.cpp.inc",
+                "ArtOps.h.inc",
+                "ArtOpsDialect.cpp.inc",
+                "ArtOpsDialect.h.inc",
+            ]
+            .map(|name| output_dir.join("inc/art").join(name))
+        );
+        assert_eq!(artifacts.docs_dir, None);
+        assert_eq!(artifacts.llvm_prefix, Path::new("/opt/llvm"));
+
+        std::fs::remove_dir_all(&dir).ok();
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[cfg(unix)]
+    #[test]
+    fn test_scratch_dir() {
+        use crate::fake_tblgen::{FakeTblgen, read_log};
+
+        let dir = std::env::temp_dir().join("test_scratch_dir");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let log = dir.join("log");
+        let tblgen_path = FakeTblgen::default()
+            .log(&log)
+            .write(&dir.join("mlir-tblgen"));
+
+        let td_file = dir.join("ScratchOps.td");
+        std::fs::write(&td_file, "def Scratch_Dialect : Dialect {}\n").unwrap();
+
+        let builder = DialectBuilder::new("scratch")
+            .td_file(&td_file)
+            .cpp_namespace("mlir::scratch")
+            .tblgen_path(&tblgen_path)
+            .detection_mode(DetectionMode::Records);
+        let scratch_dir = dir.join("scratch");
+        let output_dir = dir.join("out");
 
+        // Intermediate files never go to the system temp dir
+        builder
+            .clone()
+            .scratch_dir(&scratch_dir)
+            .generate(&output_dir, PathBuf::from("/opt/llvm"))
+            .unwrap();
+        builder
+            .generate(&output_dir, PathBuf::from("/opt/llvm"))
+            .unwrap();
+
+        // The files records are printed for
+        let printed = read_log(&log)
+            .into_iter()
+            .filter(|args| args[0] == "--print-records")
+            .map(|args| PathBuf::from(&args[1]))
+            .collect::<Vec<_>>();
+        let stubs = printed
+            .iter()
+            .filter(|path| **path != td_file)
+            .collect::<Vec<_>>();
+        assert_eq!(stubs.len(), 2);
+        assert_eq!(stubs[0].parent(), Some(scratch_dir.as_path()));
+        assert_eq!(stubs[1].parent(), Some(output_dir.as_path()));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
     #[test]
-    fn test_namespace_subdir_mlir_prefix() {
-        let builder = DialectBuilder::new("test").cpp_namespace("mlir::bril");
+    fn test_generate_paths_with_spaces() {
+        use crate::fake_tblgen::{FakeTblgen, read_log};
+
+        let dir = std::env::temp_dir().join("test generate paths with spaces");
+        std::fs::remove_dir_all(&dir).ok();
+        let include_dir = dir.join("include dir");
+        std::fs::create_dir_all(&include_dir).unwrap();
+
+        let log = dir.join("log");
+        let tblgen_path = FakeTblgen::default()
+            .log(&log)
+            .write(&dir.join("mlir-tblgen"));
+
+        let td_file = dir.join("Space Ops.td");
+        std::fs::write(
+            &td_file,
+            "def Space_Dialect : Dialect {}\ndef Space_AddOp : Space_Op<\"add\">;\n",
+        )
+        .unwrap();
+
+        let output_dir = dir.join("out dir");
+        let artifacts = DialectBuilder::new("space")
+            .td_file(&td_file)
+            .include_dir(&include_dir)
+            .cpp_namespace("mlir::space")
+            .tblgen_path(&tblgen_path)
+            .generate(&output_dir, PathBuf::from("/opt/llvm"))
+            .unwrap();
+
+        // The include directories passed to mlir-tblgen
+        let includes = read_log(&log)
+            .into_iter()
+            .flat_map(|args| {
+                args.windows(2)
+                    .filter(|pair| pair[0] == "-I")
+                    .map(|pair| PathBuf::from(&pair[1]))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert!(includes.contains(&include_dir));
+        assert!(includes.contains(&dir));
+        assert!(
+            artifacts
+                .inc_files
+                .contains(&output_dir.join("inc/space/Space OpsDialect.h.inc"))
+        );
+        let capi = std::fs::read_to_string(&artifacts.capi_file).unwrap();
+        assert!(capi.contains("#include \"space/Space OpsDialect.h.inc\""));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_processing_order() {
+        let ops = tblgen::TdFileContents {
+            has_ops: true,
+            ..Default::default()
+        };
+        let dialect = tblgen::TdFileContents {
+            has_dialect: true,
+            has_ops: true,
+            ..Default::default()
+        };
+        let types = tblgen::TdFileContents {
+            has_types: true,
+            ..Default::default()
+        };
+        let passes = tblgen::TdFileContents {
+            has_passes: true,
+            ..Default::default()
+        };
+
         assert_eq!(
-            builder.namespace_subdir().unwrap(),
-            Some("bril".to_string())
+            processing_order(&[
+                passes.clone(),
+                ops.clone(),
+                types.clone(),
+                dialect,
+                ops,
+                types
+            ]),
+            [3, 2, 5, 1, 4, 0]
         );
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_namespace_subdir_single_level_errors() {
-        // Single-level namespace should error - must use mlir::X pattern
-        let builder = DialectBuilder::new("test").cpp_namespace("bril");
-        let err = builder.namespace_subdir().unwrap_err();
+    fn test_generate_ops_before_dialect() {
+        use crate::fake_tblgen::FakeTblgen;
+
+        let dir = std::env::temp_dir().join("test_generate_ops_before_dialect");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let tblgen_path = FakeTblgen::default().write(&dir.join("mlir-tblgen"));
+
+        let ops_file = dir.join("OrderOps.td");
+        std::fs::write(&ops_file, "def Order_AddOp : Order_Op<\"add\">;\n").unwrap();
+        let dialect_file = dir.join("OrderDialect.td");
+        std::fs::write(&dialect_file, "def Order_Dialect : Dialect {}\n").unwrap();
+
+        let output_dir = dir.join("out");
+        let artifacts = DialectBuilder::new("order")
+            .td_file(&ops_file)
+            .td_file(&dialect_file)
+            .cpp_namespace("mlir::order")
+            .tblgen_path(&tblgen_path)
+            .generate(&output_dir, PathBuf::from("/opt/llvm"))
+            .unwrap();
+
+        let inc_dir = output_dir.join("inc/order");
+        assert!(inc_dir.join("OrderDialectDialect.h.inc").exists());
+        assert!(inc_dir.join("OrderDialectDialect.cpp.inc").exists());
+        assert!(inc_dir.join("OrderOps.h.inc").exists());
+        let capi = std::fs::read_to_string(&artifacts.capi_file).unwrap();
         assert!(
-            err.to_string()
-                .contains("must use the 'mlir::namespace' pattern")
+            capi.find("order/OrderDialectDialect.h.inc").unwrap()
+                < capi.find("order/OrderOps.h.inc").unwrap()
         );
-        assert!(err.to_string().contains("Did you mean 'mlir::bril'"));
+
+        std::fs::remove_dir_all(&dir).ok();
     }
 
     #[test]
-    fn test_namespace_subdir_too_deep() {
-        let builder = DialectBuilder::new("test").cpp_namespace("mlir::foo::bar");
-        let err = builder.namespace_subdir().unwrap_err();
-        assert!(err.to_string().contains("has more than 2 levels"));
+    fn test_td_glob() {
+        let dir = std::env::temp_dir().join("test_td_glob");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(
+            dir.join("BrilOps.td"),
+            "def Bril_AddOp : Bril_Op<\"add\">;\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("BrilTypes.td"), "").unwrap();
+        std::fs::write(dir.join("Dialect.td"), "def Bril_Dialect : Dialect {}\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+        std::fs::write(dir.join("sub/BrilAttrs.td"), "").unwrap();
+
+        let pattern = dir.join("*.td");
+        let builder = DialectBuilder::new("bril").td_glob(pattern.to_str().unwrap());
+        // The dialect definition comes first, then the rest by path
+        assert_eq!(
+            builder.td_files,
+            ["Dialect.td", "BrilOps.td", "BrilTypes.td"].map(|name| dir.join(name))
+        );
+        assert!(builder.check_inputs().is_ok());
+
+        // Files already added are not added twice
+        let builder = DialectBuilder::new("bril")
+            .td_file(dir.join("BrilOps.td"))
+            .td_glob(dir.join("**/Bril?*.td").to_str().unwrap());
+        assert_eq!(
+            builder.td_files,
+            ["BrilOps.td", "BrilTypes.td", "sub/BrilAttrs.td"].map(|name| dir.join(name))
+        );
+
+        let pattern = dir.join("*.mlir");
+        let err = DialectBuilder::new("bril")
+            .td_glob(pattern.to_str().unwrap())
+            .check_inputs()
+            .unwrap_err();
+        assert!(matches!(err, Error::NoTdFilesMatched(p) if p == pattern.to_str().unwrap()));
+
+        std::fs::remove_dir_all(&dir).ok();
     }
 
     #[test]
-    fn test_namespace_subdir_none() {
+    fn test_build_missing_td_file() {
+        let td_file = std::env::temp_dir().join("test_missing_td_file/BrilOps.td");
+        let err = DialectBuilder::new("bril")
+            .td_file(&td_file)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, Error::FileNotFound(path) if path == td_file));
+    }
+
+    #[test]
+    fn test_build_include_dir_not_directory() {
+        let td_file = std::env::temp_dir().join("test_include_not_dir_ops.td");
+        std::fs::write(&td_file, "").unwrap();
+
+        let err = DialectBuilder::new("bril")
+            .td_file(&td_file)
+            .include_dir(&td_file)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidIncludeDir(path) if path == td_file));
+
+        std::fs::remove_file(&td_file).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_use_prebuilt() {
+        use crate::fake_tblgen::FakeTblgen;
+
+        let dir = std::env::temp_dir().join("test_use_prebuilt");
+        std::fs::remove_dir_all(&dir).ok();
+        let inc_dir = dir.join("vendor").join("inc");
+        std::fs::create_dir_all(&inc_dir).unwrap();
+        let capi_file = dir.join("vendor").join("bril_capi.cpp");
+        std::fs::write(&capi_file, "#include \"BrilOps.h.inc\"\n").unwrap();
+        std::fs::write(inc_dir.join("BrilOps.h.inc"), "// ops\n").unwrap();
+        let td_file = dir.join("BrilOps.td");
+        std::fs::write(
+            &td_file,
+            "def Bril_Dialect : Dialect {\n  let name = \"bril\";\n}\n\
+             def Bril_AddOp : Bril_Op<\"add\">;\n",
+        )
+        .unwrap();
+
+        // Fake mlir-tblgen that must not run
+        let log_path = dir.join("log");
+        let tblgen_path = FakeTblgen::default()
+            .log(&log_path)
+            .fail("", "", 1)
+            .write(&dir.join("mlir-tblgen"));
+
+        let output_dir = dir.join("out");
+        let builder = DialectBuilder::new("bril")
+            .td_file(&td_file)
+            .tblgen_path(&tblgen_path)
+            .use_prebuilt(&inc_dir, &capi_file);
+        builder.check_inputs().unwrap();
+        let artifacts = builder
+            .generate_prebuilt(&output_dir, &capi_file, PathBuf::from("/opt/llvm"))
+            .unwrap();
+        assert_eq!(artifacts.capi_file, capi_file);
+        assert_eq!(artifacts.inc_files, [inc_dir.join("BrilOps.h.inc")]);
+        assert!(!output_dir.join("bril_capi.cpp").exists());
+        let register = std::fs::read_to_string(&artifacts.register_file).unwrap();
+        assert!(register.contains("pub fn verify("));
+
+        let mut build = builder
+            .cpp_build(
+                &artifacts.capi_file,
+                &builder.inc_base_dir(&output_dir),
+                &LlvmConfig::from_prefix("/opt/llvm"),
+            )
+            .unwrap();
+        build
+            .target(&host_target())
+            .host(&host_target())
+            .opt_level(0)
+            .cargo_metadata(false);
+        let compile = |build: &cc::Build| -> Result<(), Error> {
+            assert!(build.get_files().any(|file| file == capi_file));
+            std::fs::write(output_dir.join(static_lib_file("bril_dialect")), "")?;
+            Ok(())
+        };
+        assert!(
+            builder
+                .compile_cpp_cached(&build, "bril_dialect", None, &output_dir, compile)
+                .unwrap()
+        );
+        assert!(!log_path.exists());
+
+        let err = DialectBuilder::new("bril")
+            .use_prebuilt(&inc_dir, dir.join("missing_capi.cpp"))
+            .check_inputs()
+            .unwrap_err();
+        assert!(matches!(err, Error::FileNotFound(path) if path == dir.join("missing_capi.cpp")));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_skip_if_no_llvm() {
+        let dir = std::env::temp_dir().join("test_skip_if_no_llvm");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        let td_file = dir.join("BrilOps.td");
+        std::fs::write(
+            &td_file,
+            "def Bril_Dialect : Dialect {\n  let name = \"bril\";\n}\n\
+             def Bril_AddOp : Bril_Op<\"add\">;\n",
+        )
+        .unwrap();
+
+        let builder = DialectBuilder::new("bril")
+            .td_file(&td_file)
+            .output_dir(dir.join("out"));
+        // No llvm-config and no environment variables
+        let llvm = DialectBuilder::find_llvm(None, &[]).map(|(llvm, _)| llvm);
+        assert!(matches!(llvm, Err(Error::LlvmNotFound)));
+
+        builder.clone().build_or_skip(llvm).unwrap();
+        let register = std::fs::read_to_string(dir.join("out/bril_register.rs")).unwrap();
+        assert!(register.contains("pub fn verify("));
+        assert!(!dir.join("out/bril_capi.cpp").exists());
+
+        // Other errors are still reported
+        let err = builder
+            .build_or_skip(Err(Error::MissingOutDir))
+            .unwrap_err();
+        assert!(matches!(err, Error::MissingOutDir));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_inc_output_dir() {
+        use crate::fake_tblgen::FakeTblgen;
+
+        let dir = std::env::temp_dir().join("test_inc_output_dir");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let tblgen_path = FakeTblgen::default().write(&dir.join("mlir-tblgen"));
+
+        std::fs::write(
+            dir.join("IdeDialect.td"),
+            "def Ide_Dialect : Dialect {\n  let name = \"ide\";\n}\n",
+        )
+        .unwrap();
+        let td_file = dir.join("IdeOps.td");
+        std::fs::write(
+            &td_file,
+            "include \"IdeDialect.td\"\ndef Ide_AddOp : Ide_Op<\"add\">;\n",
+        )
+        .unwrap();
+
+        let output_dir = dir.join("out");
+        let inc_output_dir = dir.join("generated").join("inc");
+        let artifacts = DialectBuilder::new("ide")
+            .td_file(&td_file)
+            .tblgen_path(&tblgen_path)
+            .inc_output_dir(&inc_output_dir)
+            .generate(&output_dir, PathBuf::from("/opt/llvm"))
+            .unwrap();
+
+        assert_eq!(
+            artifacts.inc_files,
+            ["IdeOps.cpp.inc", "IdeOps.h.inc"].map(|name| inc_output_dir.join(name))
+        );
+        assert!(artifacts.inc_files.iter().all(|file| file.exists()));
+        assert!(!output_dir.join("inc").exists());
+        assert!(artifacts.register_file.starts_with(&output_dir));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_cpp_postprocess() {
+        let dir = std::env::temp_dir().join("test_cpp_postprocess");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        // Never run, since there are no TD files
+        let tblgen_path = dir.join("mlir-tblgen");
+        std::fs::write(&tblgen_path, "").unwrap();
+
+        let artifacts = DialectBuilder::new("bril")
+            .tblgen_path(&tblgen_path)
+            .cpp_postprocess(|source| format!("{source}\nextern \"C\" int bril_anchor = 0;\n"))
+            .generate(&dir, PathBuf::from("/opt/llvm"))
+            .unwrap();
+
+        let source = std::fs::read_to_string(&artifacts.capi_file).unwrap();
+        assert!(source.contains("mlirGetDialectHandle__bril__"));
+        assert!(source.ends_with("extern \"C\" int bril_anchor = 0;\n"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_custom_file_names() {
+        let dir = std::env::temp_dir().join("test_custom_file_names");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        // Never run, since there are no TD files
+        let tblgen_path = dir.join("mlir-tblgen");
+        std::fs::write(&tblgen_path, "").unwrap();
+
+        let artifacts = DialectBuilder::new("bril")
+            .tblgen_path(&tblgen_path)
+            .register_file_name("bril_bindings.rs")
+            .capi_file_name("bril_registration.cpp")
+            .generate(&dir, PathBuf::from("/opt/llvm"))
+            .unwrap();
+
+        assert_eq!(artifacts.register_file, dir.join("bril_bindings.rs"));
+        assert_eq!(artifacts.capi_file, dir.join("bril_registration.cpp"));
+        assert!(artifacts.register_file.exists());
+        assert!(artifacts.capi_file.exists());
+        assert!(!dir.join("bril_register.rs").exists());
+        assert!(!dir.join("bril_capi.cpp").exists());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_rerun_if_changed_include_dirs() {
+        let include_dir = std::env::temp_dir().join("test_rerun_include_dir");
+        std::fs::remove_dir_all(&include_dir).ok();
+        std::fs::create_dir_all(include_dir.join("bril")).unwrap();
+        std::fs::write(include_dir.join("Common.td"), "").unwrap();
+        std::fs::write(include_dir.join("bril/BrilBase.td"), "").unwrap();
+        std::fs::write(include_dir.join("bril/README.md"), "").unwrap();
+
+        let td_file = include_dir.join("bril/BrilOps.td");
+        std::fs::write(&td_file, "include \"Outside.td\"\n").unwrap();
+        let outside = std::env::temp_dir().join("test_rerun_outside");
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("Outside.td"), "").unwrap();
+
+        let builder = DialectBuilder::new("bril")
+            .td_file(&td_file)
+            .cpp_file("BrilOpsImpl.cpp")
+            .include_dir(&include_dir)
+            .include_dir(&outside);
+
+        assert_eq!(
+            builder.rerun_if_changed_paths().unwrap(),
+            [
+                td_file.clone(),
+                PathBuf::from("BrilOpsImpl.cpp"),
+                include_dir.clone(),
+                include_dir.join("Common.td"),
+                include_dir.join("bril/BrilBase.td"),
+                td_file.clone(),
+                outside.clone(),
+                outside.join("Outside.td"),
+            ]
+        );
+
+        std::fs::remove_dir_all(&include_dir).ok();
+        std::fs::remove_dir_all(&outside).ok();
+    }
+
+    #[test]
+    fn test_link_directives_default() {
         let builder = DialectBuilder::new("test");
-        assert_eq!(builder.namespace_subdir().unwrap(), None);
+        assert_eq!(
+            builder.link_directives(&LlvmConfig::from_prefix("/opt/llvm")),
+            [
+                "cargo:rustc-link-search=native=/opt/llvm/lib",
+                "cargo:rustc-link-lib=MLIRIR",
+                "cargo:rustc-link-lib=MLIRSupport",
+                "cargo:rustc-link-lib=MLIRCAPIIR",
+            ]
+        );
     }
 
     #[test]
-    fn test_namespace_subdir_empty_string() {
-        let builder = DialectBuilder::new("test").cpp_namespace("");
-        assert_eq!(builder.namespace_subdir().unwrap(), None);
+    fn test_link_directives_extra_libs() {
+        let builder = DialectBuilder::new("test")
+            .link_lib("MLIRFunctionInterfaces")
+            .link_libs(["MLIRInferTypeOpInterface"]);
+        assert_eq!(
+            builder.link_directives(&LlvmConfig::from_prefix("/opt/llvm"))[4..],
+            [
+                "cargo:rustc-link-lib=MLIRFunctionInterfaces",
+                "cargo:rustc-link-lib=MLIRInferTypeOpInterface",
+            ]
+        );
     }
 
     #[test]
-    fn test_namespace_subdir_whitespace() {
-        let builder = DialectBuilder::new("test").cpp_namespace("  mlir::bril  ");
+    fn test_link_directives_system_libs() {
+        let builder = DialectBuilder::new("test")
+            .link_lib("MLIRFunctionInterfaces")
+            .system_lib("z")
+            .system_libs(["static=xml2", "framework=CoreFoundation"]);
+        builder.check_inputs().unwrap();
         assert_eq!(
-            builder.namespace_subdir().unwrap(),
-            Some("bril".to_string())
+            builder.link_directives(&LlvmConfig::from_prefix("/opt/llvm"))[4..],
+            [
+                "cargo:rustc-link-lib=MLIRFunctionInterfaces",
+                "cargo:rustc-link-lib=z",
+                "cargo:rustc-link-lib=static=xml2",
+                "cargo:rustc-link-lib=framework=CoreFoundation",
+            ]
         );
+
+        for lib in ["shared=z", "static=", ""] {
+            let err = DialectBuilder::new("test")
+                .system_lib(lib)
+                .check_inputs()
+                .unwrap_err();
+            assert!(matches!(err, Error::InvalidSystemLib(name) if name == lib));
+        }
     }
 
     #[test]
-    fn test_namespace_subdir_only_mlir() {
-        // "mlir" alone should error (single-level)
-        let builder = DialectBuilder::new("test").cpp_namespace("mlir");
-        let err = builder.namespace_subdir().unwrap_err();
+    fn test_link_directives_clear_default_libs() {
+        let builder = DialectBuilder::new("test")
+            .clear_default_libs()
+            .link_lib("MLIR");
+        assert_eq!(
+            builder.link_directives(&LlvmConfig::from_prefix("/opt/llvm")),
+            [
+                "cargo:rustc-link-search=native=/opt/llvm/lib",
+                "cargo:rustc-link-lib=MLIR",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_link_directives_auto_link_libs() {
+        let dir = std::env::temp_dir().join("test_link_directives_auto_link_libs");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::write(dir.join("lib/libMLIRIR.a"), "").unwrap();
+        std::fs::write(dir.join("lib/libMLIRCAPIIR.so"), "").unwrap();
+
+        let builder = DialectBuilder::new("test")
+            .auto_link_libs(true)
+            .link_lib("MLIRFunctionInterfaces");
+        assert_eq!(
+            builder.link_directives(&LlvmConfig::from_prefix(&dir)),
+            [
+                format!(
+                    "cargo:rustc-link-search=native={}",
+                    dir.join("lib").display()
+                ),
+                format!(
+                    "cargo:warning=The default MLIR library MLIRSupport was not found in {}, \
+                     so it is not linked",
+                    dir.join("lib").display()
+                ),
+                "cargo:rustc-link-lib=MLIRIR".to_string(),
+                "cargo:rustc-link-lib=MLIRCAPIIR".to_string(),
+                "cargo:rustc-link-lib=MLIRFunctionInterfaces".to_string(),
+            ]
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_check_link_libs() {
+        let dir = std::env::temp_dir().join("test_check_link_libs");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::write(dir.join("lib/libMLIRIR.a"), "").unwrap();
+        std::fs::write(dir.join("lib/libMLIRSupport.so"), "").unwrap();
+        let llvm = LlvmConfig::from_prefix(&dir);
+
+        let error = DialectBuilder::new("test")
+            .check_link_libs(&llvm)
+            .unwrap_err();
+        assert!(matches!(&error, Error::MissingMlirLibrary(_)));
+        let message = error.to_string();
+        assert!(message.contains("MLIRCAPIIR"));
+        assert!(message.contains(&dir.join("lib").display().to_string()));
+
         assert!(
-            err.to_string()
-                .contains("must use the 'mlir::namespace' pattern")
+            DialectBuilder::new("test")
+                .auto_link_libs(true)
+                .check_link_libs(&llvm)
+                .is_ok()
         );
+        assert!(
+            DialectBuilder::new("test")
+                .clear_default_libs()
+                .check_link_libs(&llvm)
+                .is_ok()
+        );
+
+        std::fs::write(dir.join("lib/libMLIRCAPIIR.dylib"), "").unwrap();
+        assert!(DialectBuilder::new("test").check_link_libs(&llvm).is_ok());
+
+        std::fs::remove_dir_all(&dir).ok();
     }
 
     #[test]
-    fn test_namespace_subdir_trailing_colons() {
-        let builder = DialectBuilder::new("test").cpp_namespace("mlir::bril::");
-        let err = builder.namespace_subdir().unwrap_err();
-        assert!(err.to_string().contains("invalid leading or trailing '::'"));
+    fn test_link_directives_static() {
+        let builder = DialectBuilder::new("test").link_static(true);
+        assert_eq!(
+            builder.link_directives(&LlvmConfig::from_prefix("/opt/llvm"))[..4],
+            [
+                "cargo:rustc-link-search=native=/opt/llvm/lib",
+                "cargo:rustc-link-lib=static=MLIRIR",
+                "cargo:rustc-link-lib=static=MLIRSupport",
+                "cargo:rustc-link-lib=static=MLIRCAPIIR",
+            ]
+        );
     }
 
     #[test]
-    fn test_namespace_subdir_leading_colons() {
-        let builder = DialectBuilder::new("test").cpp_namespace("::mlir::bril");
-        let err = builder.namespace_subdir().unwrap_err();
-        assert!(err.to_string().contains("invalid leading or trailing '::'"));
+    fn test_llvm_config_link_directives() {
+        assert_eq!(
+            llvm_config_link_directives("-lLLVMSupport -lLLVMDemangle\n", "static="),
+            [
+                "cargo:rustc-link-lib=static=LLVMSupport",
+                "cargo:rustc-link-lib=static=LLVMDemangle",
+            ]
+        );
+        assert_eq!(
+            llvm_config_link_directives("-lrt -L/opt/lib /usr/lib/libz3.so LLVMDemangle.lib", ""),
+            [
+                "cargo:rustc-link-lib=rt",
+                "cargo:rustc-link-search=native=/opt/lib",
+                "cargo:rustc-link-search=native=/usr/lib",
+                "cargo:rustc-link-lib=z3",
+                "cargo:rustc-link-lib=LLVMDemangle",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_snake_case() {
+        assert_eq!(to_snake_case("PtrType"), "ptr_type");
+        assert_eq!(to_snake_case("pointeeType"), "pointee_type");
+        assert_eq!(to_snake_case("I64Type"), "i64_type");
+        assert_eq!(to_snake_case("LLVMPtrType"), "llvm_ptr_type");
+        assert_eq!(to_snake_case("width"), "width");
+    }
+
+    #[test]
+    fn test_parse_major_version() {
+        assert_eq!(parse_major_version("21.1.0"), Some(21));
+        assert_eq!(parse_major_version("18.1.8git\n"), Some(18));
+        assert_eq!(parse_major_version("21"), Some(21));
+        assert_eq!(parse_major_version("unknown"), None);
+        assert_eq!(parse_major_version(""), None);
+    }
+
+    #[test]
+    fn test_find_llvm_from_mlir_sys() {
+        let vars = [
+            ("LLVM_PREFIX".to_string(), "/opt/llvm".to_string()),
+            (
+                "MLIR_SYS_180_PREFIX".to_string(),
+                "/opt/llvm-18".to_string(),
+            ),
+        ];
+
+        let (llvm, source) = DialectBuilder::find_llvm(None, &vars).unwrap();
+        assert_eq!(llvm, LlvmConfig::from_prefix("/opt/llvm-18"));
+        assert_eq!(source, "MLIR_SYS_180_PREFIX");
+
+        // The newest version wins when several are set
+        let mut vars = vars.to_vec();
+        vars.push((
+            "MLIR_SYS_210_PREFIX".to_string(),
+            "/opt/llvm-21".to_string(),
+        ));
+        let (llvm, _) = DialectBuilder::find_llvm(None, &vars).unwrap();
+        assert_eq!(llvm.prefix, PathBuf::from("/opt/llvm-21"));
+
+        // llvm-config still ranks first
+        let llvm_config =
+            LlvmConfig::parse("/usr/lib/llvm\n19.1.0\n/usr/include/llvm\n/usr/lib64\n");
+        let (llvm, source) = DialectBuilder::find_llvm(llvm_config.clone(), &vars).unwrap();
+        assert_eq!(Some(llvm), llvm_config);
+        assert_eq!(source, "llvm-config");
+
+        let (llvm, _) = DialectBuilder::find_llvm(None, &vars[..1]).unwrap();
+        assert_eq!(llvm.prefix, PathBuf::from("/opt/llvm"));
+        assert!(matches!(
+            DialectBuilder::find_llvm(None, &[]),
+            Err(Error::LlvmNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_llvm_config_parse() {
+        assert_eq!(
+            LlvmConfig::parse("/opt/llvm\n21.1.0\n/opt/llvm/include\n/opt/llvm/lib64\n"),
+            Some(LlvmConfig {
+                prefix: PathBuf::from("/opt/llvm"),
+                version: Some("21.1.0".to_string()),
+                include_dir: PathBuf::from("/opt/llvm/include"),
+                lib_dir: PathBuf::from("/opt/llvm/lib64"),
+            })
+        );
+        // Windows line endings
+        assert_eq!(
+            LlvmConfig::parse("C:\\LLVM\r\n18.1.8\r\nC:\\LLVM\\include\r\nC:\\LLVM\\lib\r\n")
+                .and_then(|llvm| llvm.version),
+            Some("18.1.8".to_string())
+        );
+        assert_eq!(LlvmConfig::parse("/opt/llvm\n21.1.0\n"), None);
+    }
+
+    #[test]
+    fn test_llvm_config_include_dirs() {
+        let dir = std::env::temp_dir().join("melior_build_test_llvm_include_dirs");
+        std::fs::remove_dir_all(&dir).ok();
+        // A distribution reporting a versioned include directory holding only
+        // the C++ headers, with the C API headers under the prefix
+        let include_dir = dir.join("include/llvm-21");
+        std::fs::create_dir_all(include_dir.join("mlir")).unwrap();
+        std::fs::create_dir_all(include_dir.join("llvm")).unwrap();
+        std::fs::create_dir_all(dir.join("include/mlir-c")).unwrap();
+
+        let llvm = LlvmConfig {
+            prefix: dir.clone(),
+            version: None,
+            include_dir: include_dir.clone(),
+            lib_dir: dir.join("lib"),
+        };
+        assert_eq!(
+            llvm.include_dirs(),
+            [
+                include_dir.clone(),
+                dir.join("include"),
+                include_dir.join("mlir"),
+                dir.join("include/mlir-c"),
+            ]
+        );
+
+        // Without llvm-config, the prefix include directory is only listed once
+        assert_eq!(
+            LlvmConfig::from_prefix(&dir).include_dirs(),
+            [dir.join("include"), dir.join("include/mlir-c")]
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_mlir_sys_prefix_major() {
+        assert_eq!(mlir_sys_prefix_major("MLIR_SYS_210_PREFIX"), Some(21));
+        assert_eq!(mlir_sys_prefix_major("MLIR_SYS_180_PREFIX"), Some(18));
+        assert_eq!(mlir_sys_prefix_major("MLIR_SYS_PREFIX"), None);
+        assert_eq!(mlir_sys_prefix_major("LLVM_SYS_210_PREFIX"), None);
     }
 }
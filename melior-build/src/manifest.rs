@@ -0,0 +1,110 @@
+//! Parsing of dialect manifests, which describe a dialect once for `build.rs`
+//! and the `melior::dialect!` macro.
+
+use serde::Deserialize;
+
+/// The `[dialect]` table of a dialect manifest.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Manifest {
+    /// The dialect name (e.g., "toy")
+    pub name: String,
+    /// The TableGen files, relative to the manifest
+    #[serde(default)]
+    pub td_files: Vec<String>,
+    /// The C++ namespace for the dialect (e.g., "mlir::toy")
+    pub cpp_namespace: Option<String>,
+    /// The TableGen include directories, relative to the manifest
+    #[serde(default)]
+    pub include_dirs: Vec<String>,
+}
+
+/// A manifest file, of which only the `[dialect]` table is read.
+#[derive(Deserialize)]
+struct ManifestFile {
+    dialect: Option<Manifest>,
+}
+
+/// Parse the `[dialect]` table of a TOML manifest.
+///
+/// Tables other than `[dialect]` are ignored.
+pub(crate) fn parse_manifest(content: &str) -> Result<Manifest, String> {
+    let file: ManifestFile = toml::from_str(content).map_err(|error| error.to_string())?;
+    let manifest = file
+        .dialect
+        .ok_or_else(|| "missing [dialect] table".to_string())?;
+    if manifest.name.is_empty() {
+        return Err("empty `name` in [dialect]".to_string());
+    }
+
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = parse_manifest(
+            r#"
+# Shared by build.rs and the dialect! invocation
+[package]
+name = "ignored"
+
+[dialect]
+name = "math_ext"  # the TableGen dialect name
+td_files = [
+    "src/dialect/MathDialect.td",
+    'src/dialect/Math#Ops.td', # literal string
+]
+cpp_namespace = "mlir::math_ext"
+include_dirs = ["src/dialect"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            manifest,
+            Manifest {
+                name: "math_ext".to_string(),
+                td_files: vec![
+                    "src/dialect/MathDialect.td".to_string(),
+                    "src/dialect/Math#Ops.td".to_string(),
+                ],
+                cpp_namespace: Some("mlir::math_ext".to_string()),
+                include_dirs: vec!["src/dialect".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_manifest_errors() {
+        for (content, message) in [
+            ("name = \"toy\"", "missing [dialect] table"),
+            ("[dialect]\nname = \"\"", "empty `name` in [dialect]"),
+            ("[dialect]\ntd_files = []", "missing field `name`"),
+            (
+                "[dialect]\nname = \"toy\"\nname = \"toy\"",
+                "duplicate key `name`",
+            ),
+            (
+                "[dialect]\nname = \"toy\"\ntd_file = \"Toy.td\"",
+                "unknown field `td_file`",
+            ),
+            ("[dialect]\nname = [\"toy\"]", "invalid type: sequence"),
+            (
+                "[dialect]\nname = \"toy\"\ntd_files = \"Toy.td\"",
+                "invalid type: string \"Toy.td\", expected a sequence",
+            ),
+            (
+                "[dialect]\nname = \"toy\"\ntd_files = [\"Toy.td\"",
+                "line 3",
+            ),
+            ("[dialect]\nname = toy", "line 2"),
+        ] {
+            let error = parse_manifest(content).unwrap_err();
+            assert!(error.contains(message), "{}: {}", content, error);
+        }
+    }
+}
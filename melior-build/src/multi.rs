@@ -0,0 +1,405 @@
+//! Building several dialects into a single library.
+
+use crate::{
+    BuildArtifacts, DialectBuilder, Error, compile_hash, cpp_gen, list_inc_files, output_subdir,
+    rust_gen,
+};
+use std::path::{Path, PathBuf};
+
+/// Builder for compiling several custom MLIR dialects into one static library.
+///
+/// Each dialect is configured with its own [`DialectBuilder`]. Their `.inc`
+/// files are generated into distinct subdirectories, registered from a single
+/// combined C++ file, and compiled and linked against MLIR once.
+///
+/// C++ compile settings (e.g., the C++ standard) are taken from the first
+/// dialect; preprocessor defines, include directories, additional C++
+/// sources, and link libraries of all dialects are combined. The output and
+/// `.inc` directories of the individual builders are ignored in favor of the
+/// output directory set here.
+#[derive(Debug, Clone)]
+pub struct MultiDialectBuilder {
+    /// The library name (e.g., "my_dialects")
+    name: String,
+    /// The dialects to build
+    dialects: Vec<DialectBuilder>,
+    /// Output directory (defaults to OUT_DIR)
+    output_dir: Option<PathBuf>,
+}
+
+impl MultiDialectBuilder {
+    /// Create a new multi-dialect builder with the given library name.
+    ///
+    /// The name is used for the static library and the generated files
+    /// (e.g., `{name}_capi.cpp` and `{name}_register.rs`).
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            dialects: Vec::new(),
+            output_dir: None,
+        }
+    }
+
+    /// Add a dialect to build.
+    pub fn dialect(mut self, dialect: DialectBuilder) -> Self {
+        self.dialects.push(dialect);
+        self
+    }
+
+    /// Add multiple dialects to build.
+    pub fn dialects(mut self, dialects: impl IntoIterator<Item = DialectBuilder>) -> Self {
+        self.dialects.extend(dialects);
+        self
+    }
+
+    /// Set the output directory for generated files and the compiled library.
+    ///
+    /// If not set, defaults to the `OUT_DIR` environment variable.
+    pub fn output_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.output_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Build the registration code for all dialects.
+    ///
+    /// The generated Rust file contains one module per dialect and should be
+    /// included in your crate:
+    /// ```rust,ignore
+    /// include!(concat!(env!("OUT_DIR"), "/{name}_register.rs"));
+    /// ```
+    ///
+    /// Like [`DialectBuilder::build`], the library is only compiled again when
+    /// its inputs changed. Fails with [`Error::UnsupportedMultiDialectOption`]
+    /// if a dialect enables [`dry_run`](DialectBuilder::dry_run),
+    /// [`use_prebuilt`](DialectBuilder::use_prebuilt), or
+    /// [`emit_build_info`](DialectBuilder::emit_build_info).
+    ///
+    /// Returns the paths of the generated files.
+    pub fn build(mut self) -> Result<BuildArtifacts, Error> {
+        self.check_dialects()?;
+        let first = &self.dialects[0];
+
+        let output_dir = match &self.output_dir {
+            Some(dir) => dir.clone(),
+            None => std::env::var("OUT_DIR")
+                .map(PathBuf::from)
+                .map_err(|_| Error::MissingOutDir)?,
+        };
+        let llvm = first.get_llvm()?;
+        for dialect in &self.dialects {
+            dialect.check_llvm_version(&llvm)?;
+            dialect.check_link_libs(&llvm)?;
+        }
+
+        let artifacts = self.generate(&output_dir, llvm.prefix.clone())?;
+
+        let inc_base = output_dir.join("inc");
+        let mut build = first.cpp_build(&artifacts.capi_file, &inc_base, &llvm)?;
+        for dialect in &self.dialects[1..] {
+            for (key, value) in &dialect.defines {
+                build.define(key, value.as_deref());
+            }
+            for dir in dialect.all_include_dirs().chain(&dialect.cpp_include_dirs) {
+                build.include(dir);
+            }
+            for file in dialect.cpp_files.iter().chain(&dialect.llvm_translation) {
+                build.file(file);
+            }
+        }
+        if self
+            .dialects
+            .iter()
+            .any(|dialect| dialect.emit_compile_commands)
+        {
+            first.write_compile_commands(&build, &output_dir)?;
+        }
+
+        let hash = compile_hash(
+            &build,
+            &llvm,
+            &inc_base,
+            self.dialects
+                .iter()
+                .flat_map(|dialect| dialect.all_include_dirs().chain(&dialect.cpp_include_dirs)),
+        );
+        first.compile_cpp_cached(&build, &self.name, hash, &output_dir, |build| {
+            build.try_compile(&self.name).map_err(|error| {
+                Error::CppCompilationFailed(format!(
+                    "the '{}' dialect library: {}",
+                    self.name, error
+                ))
+            })
+        })?;
+
+        let mut link_directives = Vec::new();
+        for directive in self
+            .dialects
+            .iter()
+            .flat_map(|dialect| dialect.link_directives(&llvm))
+        {
+            if !link_directives.contains(&directive) {
+                link_directives.push(directive);
+            }
+        }
+        for directive in link_directives {
+            println!("{}", directive);
+        }
+
+        for dialect in &self.dialects {
+            dialect.emit_rerun_if_changed()?;
+        }
+
+        Ok(artifacts)
+    }
+
+    /// Deduplicates and validates the inputs of each dialect, and rejects
+    /// options that only apply to single-dialect builds.
+    fn check_dialects(&mut self) -> Result<(), Error> {
+        if self.dialects.is_empty() {
+            return Err(Error::NoDialects);
+        }
+
+        for dialect in &mut self.dialects {
+            dialect.deduplicate_inputs();
+
+            let unsupported = [
+                (dialect.dry_run, "dry_run"),
+                (dialect.prebuilt.is_some(), "use_prebuilt"),
+                (dialect.emit_build_info, "emit_build_info"),
+            ];
+            if let Some((_, option)) = unsupported.into_iter().find(|(enabled, _)| *enabled) {
+                return Err(Error::UnsupportedMultiDialectOption {
+                    dialect: dialect.name.clone(),
+                    option,
+                });
+            }
+
+            dialect.check_inputs()?;
+            dialect.check_dialect_name()?;
+            dialect.check_cpp_namespace()?;
+        }
+
+        Ok(())
+    }
+
+    /// Generates the `.inc` files of all dialects, the combined C++
+    /// registration code, and the Rust bindings, without compiling anything.
+    fn generate(&self, output_dir: &Path, llvm_prefix: PathBuf) -> Result<BuildArtifacts, Error> {
+        std::fs::create_dir_all(output_dir)?;
+
+        let inc_base = output_dir.join("inc");
+        let mut subdirs = Vec::with_capacity(self.dialects.len());
+        let mut generated = Vec::with_capacity(self.dialects.len());
+
+        for dialect in &self.dialects {
+            // Always use a subdirectory so dialects cannot clobber each other
+            let subdir = dialect
+                .namespace_subdir()?
+                .unwrap_or_else(|| PathBuf::from(&dialect.name));
+
+            if subdirs.contains(&subdir) {
+                return Err(Error::DuplicateContent(format!(
+                    "Multiple dialects generate into the same subdirectory: {}",
+                    subdir.display()
+                )));
+            }
+
+            generated.push(dialect.generate_inc_files(
+                &llvm_prefix,
+                output_dir,
+                &inc_base,
+                Some(&subdir),
+            )?);
+            subdirs.push(subdir);
+        }
+
+        let cpp_namespaces = self
+            .dialects
+            .iter()
+            .map(DialectBuilder::resolved_cpp_namespace)
+            .collect::<Result<Vec<_>, _>>()?;
+        let options = self
+            .dialects
+            .iter()
+            .zip(&generated)
+            .map(|(dialect, generated)| dialect.codegen_options(generated))
+            .collect::<Vec<_>>();
+        let registrations = self
+            .dialects
+            .iter()
+            .zip(&cpp_namespaces)
+            .zip(generated.iter().zip(&options))
+            .zip(&subdirs)
+            .map(
+                |(((dialect, cpp_namespace), (generated, options)), subdir)| {
+                    cpp_gen::DialectRegistration {
+                        dialect_name: &dialect.name,
+                        cpp_namespace,
+                        generated,
+                        options,
+                        inc_subdir: Some(subdir),
+                    }
+                },
+            )
+            .collect::<Vec<_>>();
+
+        let cpp_file = output_dir.join(format!("{}_capi.cpp", self.name));
+        cpp_gen::generate_combined_cpp_registration(&registrations, &cpp_file)?;
+        for dialect in &self.dialects {
+            dialect.postprocess_cpp(&cpp_file)?;
+        }
+
+        let rust_file = output_dir.join(format!("{}_register.rs", self.name));
+        let dialects = self
+            .dialects
+            .iter()
+            .zip(&generated)
+            .zip(&options)
+            .map(|((dialect, generated), options)| (dialect.name.as_str(), generated, options))
+            .collect::<Vec<_>>();
+        rust_gen::generate_combined_rust_ffi(&self.name, &dialects, &rust_file)?;
+
+        let mut c_headers = Vec::new();
+        for (dialect, generated) in self.dialects.iter().zip(&generated) {
+            c_headers.extend(dialect.write_c_header(generated, output_dir)?);
+        }
+
+        let mut inc_files = Vec::new();
+        for subdir in &subdirs {
+            inc_files.extend(list_inc_files(&output_subdir(&inc_base, Some(subdir)))?);
+        }
+
+        Ok(BuildArtifacts {
+            register_file: rust_file,
+            capi_file: cpp_file,
+            inc_files,
+            docs_dir: self
+                .dialects
+                .iter()
+                .any(|dialect| dialect.generate_docs)
+                .then(|| output_dir.join("docs")),
+            c_headers,
+            td_contents: generated
+                .into_iter()
+                .flat_map(|generated| generated.td_contents)
+                .collect(),
+            llvm_prefix,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_without_dialects() {
+        let err = MultiDialectBuilder::new("empty")
+            .output_dir(std::env::temp_dir())
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, Error::NoDialects));
+    }
+
+    #[test]
+    fn test_build_rejects_unsupported_options() {
+        for (dialect, option) in [
+            (DialectBuilder::new("toy").dry_run(true), "dry_run"),
+            (
+                DialectBuilder::new("toy").use_prebuilt("inc", "toy_capi.cpp"),
+                "use_prebuilt",
+            ),
+            (
+                DialectBuilder::new("toy").emit_build_info(true),
+                "emit_build_info",
+            ),
+        ] {
+            let err = MultiDialectBuilder::new("dialects")
+                .dialect(DialectBuilder::new("bril"))
+                .dialect(dialect)
+                .output_dir(std::env::temp_dir())
+                .build()
+                .unwrap_err();
+            assert!(
+                matches!(
+                    &err,
+                    Error::UnsupportedMultiDialectOption { dialect, option: found }
+                        if dialect == "toy" && *found == option
+                ),
+                "{}",
+                err
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_generate_two_dialects() {
+        use crate::fake_tblgen::FakeTblgen;
+
+        let dir = std::env::temp_dir().join("test_multi_generate_two_dialects");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let tblgen_path = FakeTblgen::default().write(&dir.join("mlir-tblgen"));
+
+        let dialect = |name: &str, prefix: &str| {
+            let td_file = dir.join(format!("{}Ops.td", prefix));
+            std::fs::write(
+                &td_file,
+                format!(
+                    "def {prefix}_Dialect : Dialect {{\n  let name = \"{name}\";\n}}\n\
+                     def {prefix}_AddOp : {prefix}_Op<\"add\">;\n"
+                ),
+            )
+            .unwrap();
+            DialectBuilder::new(name)
+                .td_file(td_file)
+                .cpp_namespace(format!("mlir::{}", name))
+                .tblgen_path(&tblgen_path)
+        };
+
+        let output_dir = dir.join("out");
+        let artifacts = MultiDialectBuilder::new("dialects")
+            .dialect(dialect("alpha", "Alpha"))
+            .dialect(dialect("beta", "Beta"))
+            .generate(&output_dir, PathBuf::from("/opt/llvm"))
+            .unwrap();
+
+        // Each dialect generates into its own subdirectory
+        let inc_base = output_dir.join("inc");
+        for (subdir, stem) in [("alpha", "AlphaOps"), ("beta", "BetaOps")] {
+            for file in [format!("{stem}.h.inc"), format!("{stem}Dialect.h.inc")] {
+                assert!(
+                    artifacts
+                        .inc_files
+                        .contains(&inc_base.join(subdir).join(&file)),
+                    "missing {subdir}/{file}"
+                );
+            }
+        }
+
+        // One C++ file registers both dialects
+        assert_eq!(artifacts.capi_file, output_dir.join("dialects_capi.cpp"));
+        let cpp = std::fs::read_to_string(&artifacts.capi_file).unwrap();
+        for include in ["alpha/AlphaOps.h.inc", "beta/BetaOps.h.inc"] {
+            assert!(
+                cpp.contains(&format!("#include \"{include}\"")),
+                "missing include of {include}"
+            );
+        }
+        assert!(cpp.contains("mlirGetDialectHandle__alpha__"));
+        assert!(cpp.contains("mlirGetDialectHandle__beta__"));
+
+        // The Rust bindings have one module per dialect
+        assert_eq!(
+            artifacts.register_file,
+            output_dir.join("dialects_register.rs")
+        );
+        let rust = std::fs::read_to_string(&artifacts.register_file).unwrap();
+        assert!(rust.contains("pub mod alpha {"));
+        assert!(rust.contains("pub mod beta {"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,6 +1,10 @@
 //! Rust FFI code generation for dialect registration.
 
-use crate::Error;
+use crate::{
+    CodegenOptions, Error,
+    tblgen::{EnumDef, GeneratedFiles, OpDef, ParameterKind, TypeParameter},
+    to_class_name, to_snake_case,
+};
 use std::{fs, path::Path};
 
 /// Generate the Rust FFI bindings file.
@@ -8,27 +12,965 @@ use std::{fs, path::Path};
 /// This generates a Rust file that:
 /// 1. Declares the extern "C" function for getting the dialect handle
 /// 2. Provides safe wrapper functions for registration
-pub fn generate_rust_ffi(dialect_name: &str, output_path: &Path) -> Result<(), Error> {
+/// 3. Provides typed constructors for the custom types in `generated`
+/// 4. Mirrors the integer enums in `generated` as Rust enums
+/// 5. Provides typed builder functions and operation types for the ops in
+///    `generated`
+///
+/// Names, link settings, and optional helpers are taken from `options`.
+pub fn generate_rust_ffi(
+    dialect_name: &str,
+    generated: &GeneratedFiles,
+    options: &CodegenOptions,
+    output_path: &Path,
+) -> Result<(), Error> {
+    let link_name = options
+        .lib_name
+        .clone()
+        .unwrap_or_else(|| format!("{}_dialect", dialect_name));
+    let module_name = options
+        .rust_module_name
+        .clone()
+        .unwrap_or_else(|| format!("{}_registration", dialect_name));
+    let items = registration_items(dialect_name, generated, options, &link_name);
+    let exports = exported_names(dialect_name, generated, options);
+    check_unique_names(
+        dialect_name,
+        exports
+            .iter()
+            .map(String::as_str)
+            .chain(["insert_all_into_registry", "load_all"]),
+    )?;
+    let exports = exports.join(", ");
     let code = format!(
         r#"// Auto-generated by melior-build. Do not edit.
 
-mod {dialect_name}_registration {{
-    #[link(name = "{dialect_name}_dialect")]
+mod {module_name} {{
+{items}}}
+
+pub use {module_name}::{{{exports}}};
+{all_functions}"#,
+        module_name = module_name,
+        items = items,
+        exports = exports,
+        all_functions = all_dialect_functions(std::slice::from_ref(&module_name)),
+    );
+
+    fs::write(output_path, code)?;
+
+    Ok(())
+}
+
+/// Returns the names of the public items generated for a dialect, in the
+/// order they are exported.
+fn exported_names(
+    dialect_name: &str,
+    generated: &GeneratedFiles,
+    options: &CodegenOptions,
+) -> Vec<String> {
+    [
+        "DialectLoadError",
+        "OPERATIONS",
+        "dialect_handle",
+        "insert_into_registry",
+        "is_loaded",
+        "load",
+        "load_unchecked",
+        "loaded_dialect",
+        "register",
+    ]
+    .into_iter()
+    .map(String::from)
+    .chain(generated.ops_stem.as_ref().map(|_| "verify".to_string()))
+    .chain(
+        generated
+            .bytecode_stem
+            .as_ref()
+            .map(|_| "register_bytecode_interface".to_string()),
+    )
+    .chain(
+        options
+            .context_helper
+            .then(|| "context_with_dialect".to_string()),
+    )
+    .chain(
+        generated
+            .rewriters_stem
+            .as_ref()
+            .map(|_| "populate_canonicalization_patterns".to_string()),
+    )
+    .chain(
+        generated
+            .passes_stem
+            .as_ref()
+            .map(|_| "register_passes".to_string()),
+    )
+    .chain(
+        options
+            .llvm_translation
+            .then(|| "register_llvm_translations".to_string()),
+    )
+    .chain(
+        generated
+            .type_defs
+            .iter()
+            .map(|type_def| constructor_name(&type_def.class_name, "Type")),
+    )
+    .chain(
+        generated
+            .attr_defs
+            .iter()
+            .map(|attr_def| constructor_name(&attr_def.class_name, "Attribute")),
+    )
+    .chain(
+        generated
+            .enum_defs
+            .iter()
+            .map(|enum_def| enum_def.class_name.clone()),
+    )
+    .chain(
+        generated
+            .op_defs
+            .iter()
+            .filter(|op_def| op_def.fixed_arity)
+            .map(|op_def| op_builder_name(dialect_name, op_def)),
+    )
+    .chain((!generated.op_defs.is_empty()).then(|| "OperationMismatch".to_string()))
+    .chain(
+        generated
+            .op_defs
+            .iter()
+            .map(|op_def| op_type_name(dialect_name, options, op_def)),
+    )
+    .collect()
+}
+
+/// Fails if a name occurs more than once, as the generated items would not
+/// compile.
+fn check_unique_names<'a>(
+    dialect_name: &str,
+    names: impl IntoIterator<Item = &'a str>,
+) -> Result<(), Error> {
+    let mut seen = Vec::new();
+    for name in names {
+        if seen.contains(&name) {
+            return Err(Error::DuplicateRustName {
+                dialect: dialect_name.to_string(),
+                name: name.to_string(),
+            });
+        }
+        seen.push(name);
+    }
+
+    Ok(())
+}
+
+/// Generate the Rust FFI bindings file for several dialects compiled into a
+/// single library.
+///
+/// Each dialect gets its own public module named after the dialect, e.g.,
+/// `bril::register(&context)`.
+pub fn generate_combined_rust_ffi(
+    library_name: &str,
+    dialects: &[(&str, &GeneratedFiles, &CodegenOptions)],
+    output_path: &Path,
+) -> Result<(), Error> {
+    let mut code = String::from("// Auto-generated by melior-build. Do not edit.\n");
+    let module_names = dialects
+        .iter()
+        .map(|(dialect_name, _, options)| {
+            options
+                .rust_module_name
+                .clone()
+                .unwrap_or_else(|| dialect_name.to_string())
+        })
+        .collect::<Vec<_>>();
+
+    for ((dialect_name, generated, options), module_name) in dialects.iter().zip(&module_names) {
+        check_unique_names(
+            dialect_name,
+            exported_names(dialect_name, generated, options)
+                .iter()
+                .map(String::as_str),
+        )?;
+        code.push_str(&format!(
+            "\n{docs}pub mod {module_name} {{\n{items}}}\n",
+            docs = generated
+                .dialect_summary
+                .as_deref()
+                .map(|summary| doc_comment(summary, ""))
+                .unwrap_or_default(),
+            module_name = module_name,
+            items = registration_items(dialect_name, generated, options, library_name),
+        ));
+    }
+    code.push_str(&all_dialect_functions(&module_names));
+
+    fs::write(output_path, code)?;
+
+    Ok(())
+}
+
+/// Generate `insert_all_into_registry` and `load_all` functions calling the
+/// hooks of the dialects in the given modules.
+fn all_dialect_functions(modules: &[String]) -> String {
+    let calls = |function: &str, argument: &str| {
+        modules
+            .iter()
+            .map(|module| format!("    {}::{}({});\n", module, function, argument))
+            .collect::<String>()
+    };
+
+    format!(
+        r#"
+/// Insert all dialects of this file into a dialect registry.
+pub fn insert_all_into_registry(registry: &::melior::dialect::DialectRegistry) {{
+{insert_calls}}}
+
+/// Load all dialects of this file into the given context.
+pub fn load_all(context: &::melior::Context) {{
+{load_calls}}}
+"#,
+        insert_calls = calls("insert_into_registry", "registry"),
+        load_calls = calls("load_unchecked", "context"),
+    )
+}
+
+/// Generate the extern declaration and typed Rust constructor for a custom
+/// type or attribute, backed by the `mlir{Dialect}{Class}Get` C API function.
+///
+/// `value` is the kind of the constructed value, "Type" or "Attribute".
+fn constructor(
+    dialect_name: &str,
+    acronyms: &[String],
+    value: &str,
+    class_name: &str,
+    parameters: &[TypeParameter],
+) -> (String, String) {
+    let symbol = format!(
+        "mlir{}{}Get",
+        to_class_name(dialect_name, acronyms),
+        class_name
+    );
+    let mut ffi_parameters = vec!["context: mlir_sys::MlirContext".to_string()];
+    let mut rust_parameters = vec!["context: &'c ::melior::Context".to_string()];
+    let mut arguments = vec!["context.to_raw()".to_string()];
+
+    for parameter in parameters {
+        let name = to_snake_case(&parameter.name);
+        let (ffi_type, rust_type, argument) = match parameter.kind {
+            ParameterKind::Type => (
+                "mlir_sys::MlirType",
+                "::melior::ir::Type<'c>",
+                format!("::melior::ir::TypeLike::to_raw(&{name})"),
+            ),
+            ParameterKind::Attribute => (
+                "mlir_sys::MlirAttribute",
+                "::melior::ir::Attribute<'c>",
+                format!("::melior::ir::AttributeLike::to_raw(&{name})"),
+            ),
+            ParameterKind::String => (
+                "mlir_sys::MlirStringRef",
+                "&str",
+                format!("::melior::StringRef::new({name}).to_raw()"),
+            ),
+            ParameterKind::Scalar { rust_type, .. } => (rust_type, rust_type, name.clone()),
+        };
+        ffi_parameters.push(format!("{name}: {ffi_type}"));
+        rust_parameters.push(format!("{name}: {rust_type}"));
+        arguments.push(argument);
+    }
+
+    let extern_declaration = format!(
+        "        fn {symbol}({}) -> mlir_sys::Mlir{value};\n",
+        ffi_parameters.join(", ")
+    );
+    let function = format!(
+        r#"
+    /// Creates a `{class_name}` of the {dialect_name} dialect.
+    pub fn {function_name}<'c>({rust_parameters}) -> ::melior::ir::{value}<'c> {{
+        unsafe {{ ::melior::ir::{value}::from_raw({symbol}({arguments})) }}
+    }}
+"#,
+        function_name = constructor_name(class_name, value),
+        rust_parameters = rust_parameters.join(", "),
+        arguments = arguments.join(", "),
+    );
+
+    (extern_declaration, function)
+}
+
+/// Returns the name of the constructor of a custom type or attribute, e.g.,
+/// `vec_type` for `VecType`. Keywords get a suffix naming the constructed
+/// value, e.g., `struct_type` for a type class `Struct`.
+fn constructor_name(class_name: &str, value: &str) -> String {
+    let name = to_snake_case(class_name);
+    if KEYWORDS.contains(&name.as_str()) || name == "_" {
+        format!("{}_{}", name, value.to_lowercase())
+    } else {
+        name
+    }
+}
+
+/// Returns the name of the builder function of an op, e.g., `build_ptr_add`
+/// for `bril.ptr_add`.
+fn op_builder_name(dialect_name: &str, op_def: &OpDef) -> String {
+    let op_name = op_def
+        .name
+        .strip_prefix(&format!("{dialect_name}."))
+        .unwrap_or(&op_def.name);
+    format!("build_{}", to_snake_case(op_name).replace('.', "_"))
+}
+
+/// Returns the name of the Rust type of an op, as renamed in `options` or
+/// derived from its mnemonic (e.g., `PtrAddOperation` for `bril.ptr_add`).
+fn op_type_name(dialect_name: &str, options: &CodegenOptions, op_def: &OpDef) -> String {
+    match options
+        .op_renames
+        .iter()
+        .find(|(op_name, _)| *op_name == op_def.name)
+    {
+        Some((_, rust_name)) => rust_name.clone(),
+        None => {
+            let op_name = op_def
+                .name
+                .strip_prefix(&format!("{dialect_name}."))
+                .unwrap_or(&op_def.name);
+            format!(
+                "{}Operation",
+                to_class_name(&op_name.replace('.', "_"), &options.class_name_acronyms)
+            )
+        }
+    }
+}
+
+/// Strict and reserved Rust keywords, which cannot be used as plain
+/// identifiers.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Returns whether a name can be used as a plain Rust identifier.
+pub(crate) fn is_rust_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name != "_"
+        && !KEYWORDS.contains(&name)
+}
+
+/// Returns a Rust parameter name for an op argument that does not clash with
+/// the builders' own parameters or Rust keywords.
+fn op_parameter_name(name: &str) -> String {
+    let name = to_snake_case(name);
+    match name.as_str() {
+        "context" | "location" | "self" | "super" | "crate" => format!("{name}_"),
+        name if KEYWORDS.contains(&name) => format!("r#{name}"),
+        _ => name,
+    }
+}
+
+/// Generate a typed builder function for an op with a fixed number of
+/// operands and results, built with `OperationBuilder`. Other ops get a TODO
+/// comment instead.
+fn op_builder(dialect_name: &str, op_def: &OpDef) -> String {
+    if !op_def.fixed_arity {
+        return format!(
+            "\n    // TODO: `{}` has variadic or optional operands or results, regions,\n    \
+             // successors, or properties, which builders are not generated for yet.\n",
+            op_def.name
+        );
+    }
+
+    let operands = op_def
+        .operands
+        .iter()
+        .map(|name| op_parameter_name(name))
+        .collect::<Vec<_>>();
+    let attributes = op_def
+        .attributes
+        .iter()
+        .map(|name| (name, op_parameter_name(name)))
+        .collect::<Vec<_>>();
+    let results = op_def
+        .results
+        .iter()
+        .enumerate()
+        .map(|(index, name)| match name.as_str() {
+            "" if op_def.results.len() == 1 => "result_type".to_string(),
+            "" => format!("result{index}_type"),
+            name => op_parameter_name(&format!("{name}_type")),
+        })
+        .collect::<Vec<_>>();
+
+    let mut parameters = vec![
+        if attributes.is_empty() {
+            "_context: &'c ::melior::Context".to_string()
+        } else {
+            "context: &'c ::melior::Context".to_string()
+        },
+        "location: ::melior::ir::Location<'c>".to_string(),
+    ];
+    parameters.extend(
+        operands
+            .iter()
+            .map(|name| format!("{name}: ::melior::ir::Value<'c, '_>")),
+    );
+    parameters.extend(
+        attributes
+            .iter()
+            .map(|(_, name)| format!("{name}: ::melior::ir::Attribute<'c>")),
+    );
+    parameters.extend(
+        results
+            .iter()
+            .map(|name| format!("{name}: ::melior::ir::Type<'c>")),
+    );
+
+    let mut calls = String::new();
+    if !operands.is_empty() {
+        calls.push_str(&format!(
+            "\n            .add_operands(&[{}])",
+            operands.join(", ")
+        ));
+    }
+    if !attributes.is_empty() {
+        calls.push_str(&format!(
+            "\n            .add_attributes(&[{}])",
+            attributes
+                .iter()
+                .map(|(attribute, name)| format!(
+                    "(::melior::ir::Identifier::new(context, {attribute:?}), {name})"
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    if !results.is_empty() {
+        calls.push_str(&format!(
+            "\n            .add_results(&[{}])",
+            results.join(", ")
+        ));
+    }
+
+    format!(
+        r#"
+    /// Builds a `{name}` operation.
+    pub fn {function_name}<'c>(
+{parameters}    ) -> ::melior::ir::Operation<'c> {{
+        ::melior::ir::operation::OperationBuilder::new("{name}", location){calls}
+            .build()
+            .expect("valid {name} operation")
+    }}
+"#,
+        name = op_def.name,
+        function_name = op_builder_name(dialect_name, op_def),
+        parameters = parameters
+            .iter()
+            .map(|parameter| format!("        {parameter},\n"))
+            .collect::<String>(),
+    )
+}
+
+/// Generate the error returned when converting an operation to one of the
+/// dialect's operation types fails.
+fn operation_mismatch(dialect_name: &str) -> String {
+    format!(
+        r#"
+    /// The error returned when converting an operation with a different name
+    /// to an operation type of the {dialect_name} dialect.
+    #[derive(Debug)]
+    pub struct OperationMismatch<'c> {{
+        /// The full name of the expected operation
+        pub expected: &'static str,
+        /// The operation that failed to convert
+        pub operation: ::melior::ir::Operation<'c>,
+    }}
+
+    impl ::std::fmt::Display for OperationMismatch<'_> {{
+        fn fmt(&self, formatter: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {{
+            use ::melior::ir::operation::OperationLike;
+
+            write!(
+                formatter,
+                "expected a '{{}}' operation, found '{{}}'",
+                self.expected,
+                self.operation
+                    .name()
+                    .as_string_ref()
+                    .as_str()
+                    .unwrap_or("<invalid UTF-8>")
+            )
+        }}
+    }}
+
+    impl ::std::error::Error for OperationMismatch<'_> {{}}
+"#
+    )
+}
+
+/// Generate accessors for the regions of an op, and a function appending an
+/// entry block to its first region.
+fn region_accessors(op_def: &OpDef) -> String {
+    let mut accessors = op_def
+        .regions
+        .iter()
+        .enumerate()
+        .map(|(index, region)| {
+            format!(
+                r#"
+        /// Returns the `{region}` region.
+        pub fn {name}_region(&self) -> ::melior::ir::RegionRef<'c, '_> {{
+            ::melior::ir::operation::OperationLike::region(&self.operation, {index})
+                .expect("valid region index")
+        }}
+"#,
+                name = to_snake_case(region),
+            )
+        })
+        .collect::<String>();
+
+    if let Some(region) = op_def.regions.first() {
+        accessors.push_str(&format!(
+            r#"
+        /// Appends a block with arguments of the given types, located at the
+        /// operation, to the `{region}` region and returns it.
+        pub fn add_entry_block(
+            &self,
+            argument_types: &[::melior::ir::Type<'c>],
+        ) -> ::melior::ir::BlockRef<'c, '_> {{
+            let location = ::melior::ir::operation::OperationLike::location(&self.operation);
+            let arguments = argument_types
+                .iter()
+                .map(|&r#type| (r#type, location))
+                .collect::<Vec<_>>();
+
+            ::melior::ir::RegionLike::append_block(
+                &self.{name}_region(),
+                ::melior::ir::Block::new(&arguments),
+            )
+        }}
+"#,
+            name = to_snake_case(region),
+        ));
+    }
+
+    accessors
+}
+
+/// Generate the Rust type of an op, wrapping an `Operation` that
+/// `TryFrom` checked to have the op's name.
+fn op_type(dialect_name: &str, options: &CodegenOptions, op_def: &OpDef) -> String {
+    format!(
+        r#"
+    /// A `{name}` operation.
+    #[derive(Debug)]
+    pub struct {type_name}<'c> {{
+        operation: ::melior::ir::Operation<'c>,
+    }}
+
+    impl<'c> {type_name}<'c> {{
+        /// The full name of the operation.
+        pub const NAME: &'static str = "{name}";
+
+        /// Returns the generic operation.
+        pub fn as_operation(&self) -> &::melior::ir::Operation<'c> {{
+            &self.operation
+        }}
+{region_accessors}    }}
+
+    impl<'c> TryFrom<::melior::ir::Operation<'c>> for {type_name}<'c> {{
+        type Error = OperationMismatch<'c>;
+
+        fn try_from(operation: ::melior::ir::Operation<'c>) -> Result<Self, Self::Error> {{
+            use ::melior::ir::operation::OperationLike;
+
+            if operation.name().as_string_ref().as_str() == Ok(Self::NAME) {{
+                Ok(Self {{ operation }})
+            }} else {{
+                Err(OperationMismatch {{
+                    expected: Self::NAME,
+                    operation,
+                }})
+            }}
+        }}
+    }}
+
+    impl<'c> From<{type_name}<'c>> for ::melior::ir::Operation<'c> {{
+        fn from(operation: {type_name}<'c>) -> Self {{
+            operation.operation
+        }}
+    }}
+
+    impl<'c: 'a, 'a> ::melior::ir::operation::OperationLike<'c, 'a> for {type_name}<'c> {{
+        fn to_raw(&self) -> mlir_sys::MlirOperation {{
+            ::melior::ir::operation::OperationLike::to_raw(&self.operation)
+        }}
+    }}
+
+    impl ::std::fmt::Display for {type_name}<'_> {{
+        fn fmt(&self, formatter: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {{
+            ::std::fmt::Display::fmt(&self.operation, formatter)
+        }}
+    }}
+"#,
+        name = op_def.name,
+        type_name = op_type_name(dialect_name, options, op_def),
+        region_accessors = region_accessors(op_def),
+    )
+}
+
+/// Generate a `#[repr(i64)]` Rust enum mirroring an integer enum, with
+/// conversions to and from integers and integer attributes.
+fn rust_enum(dialect_name: &str, acronyms: &[String], enum_def: &EnumDef) -> String {
+    let variants = enum_def
+        .cases
+        .iter()
+        .map(|case| {
+            format!(
+                "        {} = {},\n",
+                to_class_name(&case.symbol, acronyms),
+                case.value
+            )
+        })
+        .collect::<String>();
+    let arms = enum_def
+        .cases
+        .iter()
+        .map(|case| {
+            format!(
+                "                {} => Ok(Self::{}),\n",
+                case.value,
+                to_class_name(&case.symbol, acronyms)
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        r#"
+    /// The `{name}` enum of the {dialect_name} dialect.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    #[repr(i64)]
+    pub enum {name} {{
+{variants}    }}
+
+    impl {name} {{
+        /// Creates an integer attribute holding this case.
+        pub fn attribute<'c>(
+            self,
+            context: &'c ::melior::Context,
+        ) -> ::melior::ir::attribute::IntegerAttribute<'c> {{
+            ::melior::ir::attribute::IntegerAttribute::new(
+                ::melior::ir::r#type::IntegerType::new(context, {bitwidth}).into(),
+                self as i64,
+            )
+        }}
+    }}
+
+    impl From<{name}> for i64 {{
+        fn from(value: {name}) -> Self {{
+            value as i64
+        }}
+    }}
+
+    impl TryFrom<i64> for {name} {{
+        type Error = i64;
+
+        fn try_from(value: i64) -> Result<Self, Self::Error> {{
+            match value {{
+{arms}                _ => Err(value),
+            }}
+        }}
+    }}
+
+    impl<'c> TryFrom<::melior::ir::attribute::IntegerAttribute<'c>> for {name} {{
+        type Error = i64;
+
+        fn try_from(
+            attribute: ::melior::ir::attribute::IntegerAttribute<'c>,
+        ) -> Result<Self, Self::Error> {{
+            Self::try_from(attribute.value())
+        }}
+    }}
+"#,
+        name = enum_def.class_name,
+        bitwidth = enum_def.bitwidth,
+    )
+}
+
+/// Generate the extern declarations and safe registration wrappers for a
+/// dialect, linked against the given library.
+fn registration_items(
+    dialect_name: &str,
+    generated: &GeneratedFiles,
+    options: &CodegenOptions,
+    link_name: &str,
+) -> String {
+    let acronyms = &options.class_name_acronyms;
+    let (constructor_declarations, constructor_functions): (String, String) = generated
+        .type_defs
+        .iter()
+        .map(|type_def| {
+            constructor(
+                dialect_name,
+                acronyms,
+                "Type",
+                &type_def.class_name,
+                &type_def.parameters,
+            )
+        })
+        .chain(generated.attr_defs.iter().map(|attr_def| {
+            constructor(
+                dialect_name,
+                acronyms,
+                "Attribute",
+                &attr_def.class_name,
+                &attr_def.parameters,
+            )
+        }))
+        .unzip();
+    let enums = generated
+        .enum_defs
+        .iter()
+        .map(|enum_def| rust_enum(dialect_name, &options.class_name_acronyms, enum_def))
+        .collect::<String>();
+    let (rewriter_declaration, rewriter_function) = if generated.rewriters_stem.is_some() {
+        let symbol = format!(
+            "mlir{}PopulateCanonicalizationPatterns",
+            to_class_name(dialect_name, &options.class_name_acronyms)
+        );
+        (
+            format!("        fn {symbol}(patterns: mlir_sys::MlirRewritePatternSet);\n"),
+            format!(
+                r#"
+    /// Add the rewrite patterns generated for the {dialect_name} dialect to
+    /// the given pattern set.
+    ///
+    /// # Safety
+    ///
+    /// `patterns` must be a valid rewrite pattern set.
+    pub unsafe fn populate_canonicalization_patterns(patterns: mlir_sys::MlirRewritePatternSet) {{
+        unsafe {{ {symbol}(patterns) }}
+    }}
+"#
+            ),
+        )
+    } else {
+        Default::default()
+    };
+    let (verify_declaration, verify_function) = if generated.ops_stem.is_some() {
+        let symbol = format!(
+            "mlir{}VerifyOperation",
+            to_class_name(dialect_name, &options.class_name_acronyms)
+        );
+        (
+            format!("        fn {symbol}(operation: mlir_sys::MlirOperation) -> bool;\n"),
+            format!(
+                r#"
+    /// Verify an operation and the operations nested in it, running the
+    /// custom verifiers of the {dialect_name} dialect compiled into this
+    /// library.
+    pub fn verify(operation: &::melior::ir::Operation<'_>) -> bool {{
+        unsafe {{ {symbol}(operation.to_raw()) }}
+    }}
+"#
+            ),
+        )
+    } else {
+        Default::default()
+    };
+    let (passes_declaration, passes_function) = if generated.passes_stem.is_some() {
+        let symbol = format!(
+            "mlirRegister{}Passes",
+            to_class_name(dialect_name, &options.class_name_acronyms)
+        );
+        (
+            format!("        fn {symbol}();\n"),
+            format!(
+                r#"
+    /// Register the passes of the {dialect_name} dialect globally, making them
+    /// available to pass pipelines parsed from text.
+    pub fn register_passes() {{
+        unsafe {{ {symbol}() }}
+    }}
+"#
+            ),
+        )
+    } else {
+        Default::default()
+    };
+    let (bytecode_declaration, bytecode_function) = if generated.bytecode_stem.is_some() {
+        let symbol = format!(
+            "mlir{}RegisterBytecodeInterface",
+            to_class_name(dialect_name, &options.class_name_acronyms)
+        );
+        (
+            format!("        fn {symbol}(context: mlir_sys::MlirContext);\n"),
+            format!(
+                r#"
+    /// Add the bytecode interface of the {dialect_name} dialect, loading the
+    /// dialect into the given context if needed, so that its attributes and
+    /// types can be read from and written to MLIR bytecode.
+    pub fn register_bytecode_interface(context: &::melior::Context) {{
+        unsafe {{ {symbol}(context.to_raw()) }}
+    }}
+"#
+            ),
+        )
+    } else {
+        Default::default()
+    };
+    let (translation_declaration, translation_function) = if options.llvm_translation {
+        let symbol = format!(
+            "mlir{}RegisterLLVMTranslations",
+            to_class_name(dialect_name, &options.class_name_acronyms)
+        );
+        (
+            format!("        fn {symbol}(context: mlir_sys::MlirContext);\n"),
+            format!(
+                r#"
+    /// Register the translation of the {dialect_name} dialect to LLVM IR with
+    /// the given context.
+    pub fn register_llvm_translations(context: &::melior::Context) {{
+        unsafe {{ {symbol}(context.to_raw()) }}
+    }}
+"#
+            ),
+        )
+    } else {
+        Default::default()
+    };
+    let context_function = if options.context_helper {
+        format!(
+            r#"
+    /// Create a context with all built-in dialects and the {dialect_name}
+    /// dialect loaded.
+    pub fn context_with_dialect() -> ::melior::Context {{
+        let context = ::melior::Context::new();
+        let registry = ::melior::dialect::DialectRegistry::new();
+        ::melior::utility::register_all_dialects(&registry);
+        context.append_dialect_registry(&registry);
+        context.load_all_available_dialects();
+        load_unchecked(&context);
+        context
+    }}
+"#
+        )
+    } else {
+        String::new()
+    };
+    let op_builders = generated
+        .op_defs
+        .iter()
+        .map(|op_def| op_builder(dialect_name, op_def))
+        .collect::<String>();
+    let op_types = if generated.op_defs.is_empty() {
+        String::new()
+    } else {
+        generated
+            .op_defs
+            .iter()
+            .map(|op_def| op_type(dialect_name, options, op_def))
+            .fold(operation_mismatch(dialect_name), |types, op_type| {
+                types + &op_type
+            })
+    };
+    let summary = generated
+        .dialect_summary
+        .as_deref()
+        .map(|summary| format!("    ///\n{}", doc_comment(summary, "    ")))
+        .unwrap_or_default();
+    let operations = if generated.op_docs.is_empty() {
+        String::new()
+    } else {
+        generated
+            .op_docs
+            .iter()
+            .map(|op| {
+                let summary = op.summary.split_whitespace().collect::<Vec<_>>().join(" ");
+                format!("    /// - `{}`: {}\n", op.name, summary)
+            })
+            .fold(
+                "    ///\n    /// Operations:\n    ///\n".to_string(),
+                |docs, line| docs + &line,
+            )
+    };
+
+    let (anchor_declaration, anchor_read) = if options.link_anchor {
+        let symbol = format!(
+            "mlir{}LinkAnchor",
+            to_class_name(dialect_name, &options.class_name_acronyms)
+        );
+        (
+            format!("        static {symbol}: u8;\n"),
+            format!(
+                "            // Keeps linkers from dropping the registration code\n            \
+                 ::std::ptr::read_volatile(&raw const {symbol});\n"
+            ),
+        )
+    } else {
+        Default::default()
+    };
+    let handle_link_name = options
+        .dialect_handle_symbol
+        .as_ref()
+        .map(|symbol| format!("        #[link_name = \"{symbol}\"]\n"))
+        .unwrap_or_default();
+
+    let operation_names = if generated.operations.is_empty() {
+        String::new()
+    } else {
+        generated
+            .operations
+            .iter()
+            .map(|name| format!("        {:?},\n", name))
+            .fold("\n".to_string(), |names, line| names + &line)
+            + "    "
+    };
+
+    format!(
+        r#"    #[link(name = "{link_name}")]
     unsafe extern "C" {{
-        fn mlirGetDialectHandle__{dialect_name}__() -> mlir_sys::MlirDialectHandle;
+{handle_link_name}        fn mlirGetDialectHandle__{dialect_name}__() -> mlir_sys::MlirDialectHandle;
+{anchor_declaration}{constructor_declarations}{verify_declaration}{rewriter_declaration}{passes_declaration}{bytecode_declaration}{translation_declaration}    }}
+
+    /// The full names of the operations of the {dialect_name} dialect.
+    pub const OPERATIONS: &[&str] = &[{operation_names}];
+
+    /// The error returned by `load` if loading the {dialect_name} dialect did
+    /// not register its operations.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct DialectLoadError {{
+        /// The operation that is not registered
+        pub operation: &'static str,
+    }}
+
+    impl ::std::fmt::Display for DialectLoadError {{
+        fn fmt(&self, formatter: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {{
+            write!(
+                formatter,
+                "the {dialect_name} dialect was loaded, but its operation '{{}}' is not registered",
+                self.operation
+            )
+        }}
     }}
 
+    impl ::std::error::Error for DialectLoadError {{}}
+
     /// Returns the dialect handle for the {dialect_name} dialect.
-    ///
+{summary}    ///
     /// This handle can be used to register or load the dialect into a context.
-    pub fn dialect_handle() -> ::melior::dialect::DialectHandle {{
+{operations}    pub fn dialect_handle() -> ::melior::dialect::DialectHandle {{
         unsafe {{
-            ::melior::dialect::DialectHandle::from_raw(mlirGetDialectHandle__{dialect_name}__())
+{anchor_read}            ::melior::dialect::DialectHandle::from_raw(mlirGetDialectHandle__{dialect_name}__())
         }}
     }}
 
     /// Register the {dialect_name} dialect with the given context.
-    ///
+{summary}    ///
     /// This makes the dialect available but does not load it. Use `load` if you
     /// want to load the dialect immediately.
     pub fn register(context: &::melior::Context) {{
@@ -36,28 +978,89 @@ mod {dialect_name}_registration {{
     }}
 
     /// Load the {dialect_name} dialect into the given context.
-    ///
+{summary}    ///
     /// This both registers and loads the dialect, returning the loaded dialect.
-    pub fn load(context: &::melior::Context) -> ::melior::dialect::Dialect<'_> {{
+    ///
+    /// # Errors
+    ///
+    /// Fails if the first of the dialect's `OPERATIONS` is not registered
+    /// afterwards, which usually means that the compiled C API registration
+    /// belongs to a different dialect.
+    pub fn load(
+        context: &::melior::Context,
+    ) -> Result<::melior::dialect::Dialect<'_>, DialectLoadError> {{
+        let dialect = load_unchecked(context);
+        match OPERATIONS.first() {{
+            Some(&operation) if !context.is_registered_operation(operation) => {{
+                Err(DialectLoadError {{ operation }})
+            }}
+            _ => Ok(dialect),
+        }}
+    }}
+
+    /// Load the {dialect_name} dialect into the given context without
+    /// checking that its operations are registered.
+    pub fn load_unchecked(context: &::melior::Context) -> ::melior::dialect::Dialect<'_> {{
         dialect_handle().load_dialect(context)
     }}
 
     /// Insert the {dialect_name} dialect into a dialect registry.
-    ///
+{summary}    ///
     /// This is useful when building a registry to pass to a context.
     pub fn insert_into_registry(registry: &::melior::dialect::DialectRegistry) {{
         dialect_handle().insert_dialect(registry);
     }}
-}}
 
-pub use {dialect_name}_registration::{{dialect_handle, insert_into_registry, load, register}};
-"#,
-        dialect_name = dialect_name,
-    );
+    /// Returns whether the {dialect_name} dialect is loaded in the given context.
+    ///
+    /// This checks whether any operation of the dialect is registered, so it
+    /// is always `false` for a dialect without operations.
+    pub fn is_loaded(context: &::melior::Context) -> bool {{
+        OPERATIONS
+            .iter()
+            .any(|operation| context.is_registered_operation(operation))
+    }}
 
-    fs::write(output_path, code)?;
+    /// Returns the {dialect_name} dialect if it is loaded in the given context.
+    ///
+    /// Unlike `load`, this never loads the dialect.
+    pub fn loaded_dialect(context: &::melior::Context) -> Option<::melior::dialect::Dialect<'_>> {{
+        is_loaded(context).then(|| context.get_or_load_dialect("{dialect_name}"))
+    }}
+{context_function}{verify_function}{rewriter_function}{passes_function}{bytecode_function}{translation_function}{constructor_functions}{enums}{op_builders}{op_types}"#,
+        dialect_name = dialect_name,
+        link_name = link_name,
+        handle_link_name = handle_link_name,
+        anchor_declaration = anchor_declaration,
+        anchor_read = anchor_read,
+        constructor_declarations = constructor_declarations,
+        constructor_functions = constructor_functions,
+        enums = enums,
+        summary = summary,
+        operations = operations,
+        operation_names = operation_names,
+        context_function = context_function,
+        verify_declaration = verify_declaration,
+        verify_function = verify_function,
+        rewriter_declaration = rewriter_declaration,
+        rewriter_function = rewriter_function,
+        passes_declaration = passes_declaration,
+        passes_function = passes_function,
+        bytecode_declaration = bytecode_declaration,
+        bytecode_function = bytecode_function,
+        translation_declaration = translation_declaration,
+        translation_function = translation_function,
+        op_builders = op_builders,
+        op_types = op_types,
+    )
+}
 
-    Ok(())
+/// Format text as `///` doc comment lines with the given indentation.
+fn doc_comment(text: &str, indent: &str) -> String {
+    text.trim()
+        .lines()
+        .map(|line| format!("{indent}/// {}\n", line.trim()).replace("/// \n", "///\n"))
+        .collect()
 }
 
 #[cfg(test)]
@@ -69,7 +1072,13 @@ mod tests {
         let temp_dir = std::env::temp_dir();
         let output_path = temp_dir.join("test_register.rs");
 
-        generate_rust_ffi("toy", &output_path).unwrap();
+        generate_rust_ffi(
+            "toy",
+            &GeneratedFiles::default(),
+            &CodegenOptions::default(),
+            &output_path,
+        )
+        .unwrap();
 
         let content = std::fs::read_to_string(&output_path).unwrap();
 
@@ -78,8 +1087,735 @@ mod tests {
         assert!(content.contains("pub fn register("));
         assert!(content.contains("pub fn load("));
         assert!(content.contains("pub fn insert_into_registry("));
+        assert!(content.contains("pub fn is_loaded("));
+        assert!(content.contains("    toy_registration::load_unchecked(context);\n"));
+        assert!(content.contains("    toy_registration::insert_into_registry(registry);\n"));
+        assert!(content.contains("context.get_or_load_dialect(\"toy\")"));
         assert!(content.contains("::melior::dialect::DialectHandle"));
 
         std::fs::remove_file(&output_path).ok();
     }
+
+    #[test]
+    fn test_generate_rust_ffi_rewriters() {
+        let output_path = std::env::temp_dir().join("test_rewriters_register.rs");
+
+        let generated = GeneratedFiles {
+            rewriters_stem: Some("BrilCanon".to_string()),
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &generated, &CodegenOptions::default(), &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains(
+            "fn mlirBrilPopulateCanonicalizationPatterns(patterns: mlir_sys::MlirRewritePatternSet);"
+        ));
+        assert!(content.contains("pub unsafe fn populate_canonicalization_patterns("));
+        assert!(content.contains("register, populate_canonicalization_patterns};"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_lib_name() {
+        let output_path = std::env::temp_dir().join("test_lib_name_register.rs");
+
+        let options = CodegenOptions {
+            lib_name: Some("bril_example_capi".to_string()),
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &GeneratedFiles::default(), &options, &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains("#[link(name = \"bril_example_capi\")]"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_dialect_handle_symbol() {
+        let output_path = std::env::temp_dir().join("test_handle_symbol_register.rs");
+
+        let options = CodegenOptions {
+            dialect_handle_symbol: Some("mlirGetDialectHandle__llvm__".to_string()),
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &GeneratedFiles::default(), &options, &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains(
+            "    unsafe extern \"C\" {\n        #[link_name = \"mlirGetDialectHandle__llvm__\"]\n        fn mlirGetDialectHandle__bril__()"
+        ));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_link_anchor() {
+        let output_path = std::env::temp_dir().join("test_link_anchor_register.rs");
+
+        let options = CodegenOptions {
+            link_anchor: true,
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &GeneratedFiles::default(), &options, &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains("        static mlirBrilLinkAnchor: u8;\n"));
+        assert!(content.contains("::std::ptr::read_volatile(&raw const mlirBrilLinkAnchor);"));
+
+        generate_rust_ffi(
+            "bril",
+            &GeneratedFiles::default(),
+            &CodegenOptions::default(),
+            &output_path,
+        )
+        .unwrap();
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(!content.contains("LinkAnchor"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_module_name() {
+        let output_path = std::env::temp_dir().join("test_module_name_register.rs");
+
+        let options = CodegenOptions {
+            rust_module_name: Some("bril_dialect".to_string()),
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &GeneratedFiles::default(), &options, &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains("mod bril_dialect {"));
+        assert!(
+            content.contains("pub use bril_dialect::{DialectLoadError, OPERATIONS, dialect_handle")
+        );
+        assert!(content.contains("    bril_dialect::load_unchecked(context);\n"));
+        assert!(!content.contains("bril_registration"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_is_rust_identifier() {
+        for name in ["bril", "Bril2", "_bril", "bril_registration"] {
+            assert!(is_rust_identifier(name), "{name}");
+        }
+        for name in [
+            "",
+            "_",
+            "2d",
+            "my-dialect",
+            "crate::bril",
+            "mod",
+            "self",
+            "r#type",
+        ] {
+            assert!(!is_rust_identifier(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_operations() {
+        let output_path = std::env::temp_dir().join("test_operations_register.rs");
+
+        let generated = GeneratedFiles {
+            operations: vec!["bril.add".to_string(), "bril.const".to_string()],
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &generated, &CodegenOptions::default(), &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains(
+            "pub const OPERATIONS: &[&str] = &[\n        \"bril.add\",\n        \"bril.const\",\n    ];"
+        ));
+        assert!(
+            content.contains(
+                "pub use bril_registration::{DialectLoadError, OPERATIONS, dialect_handle"
+            )
+        );
+
+        // Without operations, the constant is still defined
+        generate_rust_ffi(
+            "bril",
+            &GeneratedFiles::default(),
+            &CodegenOptions::default(),
+            &output_path,
+        )
+        .unwrap();
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains("pub const OPERATIONS: &[&str] = &[];"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_verify() {
+        let output_path = std::env::temp_dir().join("test_verify_register.rs");
+
+        let generated = GeneratedFiles {
+            ops_stem: Some("BrilOps".to_string()),
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &generated, &CodegenOptions::default(), &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(
+            content.contains(
+                "fn mlirBrilVerifyOperation(operation: mlir_sys::MlirOperation) -> bool;"
+            )
+        );
+        assert!(
+            content.contains("pub fn verify(operation: &::melior::ir::Operation<'_>) -> bool {")
+        );
+        assert!(content.contains("register, verify};"));
+
+        // Without operations, there is nothing to verify
+        generate_rust_ffi(
+            "bril",
+            &GeneratedFiles::default(),
+            &CodegenOptions::default(),
+            &output_path,
+        )
+        .unwrap();
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(!content.contains("VerifyOperation"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_context_helper() {
+        let output_path = std::env::temp_dir().join("test_context_helper_register.rs");
+
+        generate_rust_ffi(
+            "bril",
+            &GeneratedFiles::default(),
+            &CodegenOptions::default(),
+            &output_path,
+        )
+        .unwrap();
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(!content.contains("context_with_dialect"));
+        assert!(!content.contains("register_all_dialects"));
+
+        let options = CodegenOptions {
+            context_helper: true,
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &GeneratedFiles::default(), &options, &output_path).unwrap();
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains("pub fn context_with_dialect() -> ::melior::Context {"));
+        assert!(content.contains("::melior::utility::register_all_dialects(&registry);"));
+        assert!(content.contains("register, context_with_dialect};"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_bytecode() {
+        let output_path = std::env::temp_dir().join("test_bytecode_register.rs");
+
+        let generated = GeneratedFiles {
+            bytecode_stem: Some("BrilBytecode".to_string()),
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &generated, &CodegenOptions::default(), &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(
+            content
+                .contains("fn mlirBrilRegisterBytecodeInterface(context: mlir_sys::MlirContext);")
+        );
+        assert!(
+            content.contains("pub fn register_bytecode_interface(context: &::melior::Context) {")
+        );
+        assert!(content.contains("register, register_bytecode_interface};"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_passes() {
+        let output_path = std::env::temp_dir().join("test_passes_register.rs");
+
+        let generated = GeneratedFiles {
+            passes_stem: Some("BrilPasses".to_string()),
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &generated, &CodegenOptions::default(), &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains("fn mlirRegisterBrilPasses();"));
+        assert!(content.contains("pub fn register_passes() {"));
+        assert!(content.contains("register, register_passes};"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_op_builders() {
+        use crate::tblgen::OpDef;
+
+        let output_path = std::env::temp_dir().join("test_op_builders_register.rs");
+
+        let generated = GeneratedFiles {
+            op_defs: vec![
+                OpDef {
+                    name: "bril.add".to_string(),
+                    operands: vec!["lhs".to_string(), "rhs".to_string()],
+                    attributes: Vec::new(),
+                    results: vec![String::new()],
+                    regions: Vec::new(),
+                    fixed_arity: true,
+                },
+                OpDef {
+                    name: "bril.const".to_string(),
+                    operands: Vec::new(),
+                    attributes: vec!["value".to_string()],
+                    results: vec!["result".to_string()],
+                    regions: Vec::new(),
+                    fixed_arity: true,
+                },
+                OpDef {
+                    name: "bril.call".to_string(),
+                    operands: Vec::new(),
+                    attributes: vec!["callee".to_string()],
+                    results: vec![String::new()],
+                    regions: Vec::new(),
+                    fixed_arity: false,
+                },
+            ],
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &generated, &CodegenOptions::default(), &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains(
+            "    pub fn build_add<'c>(
+        _context: &'c ::melior::Context,
+        location: ::melior::ir::Location<'c>,
+        lhs: ::melior::ir::Value<'c, '_>,
+        rhs: ::melior::ir::Value<'c, '_>,
+        result_type: ::melior::ir::Type<'c>,
+    ) -> ::melior::ir::Operation<'c> {
+        ::melior::ir::operation::OperationBuilder::new(\"bril.add\", location)
+            .add_operands(&[lhs, rhs])
+            .add_results(&[result_type])
+            .build()
+            .expect(\"valid bril.add operation\")
+    }"
+        ));
+        assert!(content.contains(
+            ".add_attributes(&[(::melior::ir::Identifier::new(context, \"value\"), value)])"
+        ));
+        assert!(content.contains("// TODO: `bril.call` has variadic"));
+        assert!(!content.contains("build_call"));
+        assert!(
+            content.contains("build_add, build_const, OperationMismatch, AddOperation, ConstOperation, CallOperation};")
+        );
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_op_types() {
+        use crate::tblgen::OpDef;
+
+        let output_path = std::env::temp_dir().join("test_op_types_register.rs");
+
+        let generated = GeneratedFiles {
+            op_defs: vec![OpDef {
+                name: "bril.ptr_add".to_string(),
+                operands: vec!["ptr".to_string(), "offset".to_string()],
+                attributes: Vec::new(),
+                results: vec![String::new()],
+                regions: Vec::new(),
+                fixed_arity: true,
+            }],
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &generated, &CodegenOptions::default(), &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains("    pub struct OperationMismatch<'c> {"));
+        assert!(content.contains("    pub struct PtrAddOperation<'c> {"));
+        assert!(content.contains("        pub const NAME: &'static str = \"bril.ptr_add\";"));
+        // The conversion checks the name and hands the operation back otherwise
+        assert!(content.contains(
+            "    impl<'c> TryFrom<::melior::ir::Operation<'c>> for PtrAddOperation<'c> {
+        type Error = OperationMismatch<'c>;
+
+        fn try_from(operation: ::melior::ir::Operation<'c>) -> Result<Self, Self::Error> {
+            use ::melior::ir::operation::OperationLike;
+
+            if operation.name().as_string_ref().as_str() == Ok(Self::NAME) {
+                Ok(Self { operation })
+            } else {
+                Err(OperationMismatch {
+                    expected: Self::NAME,
+                    operation,
+                })
+            }
+        }
+    }"
+        ));
+        assert!(content.contains(
+            "impl<'c: 'a, 'a> ::melior::ir::operation::OperationLike<'c, 'a> for PtrAddOperation<'c> {"
+        ));
+        assert!(
+            content
+                .contains("impl<'c> From<PtrAddOperation<'c>> for ::melior::ir::Operation<'c> {")
+        );
+        assert!(content.contains("build_ptr_add, OperationMismatch, PtrAddOperation};"));
+        // Ops without regions get no region helpers
+        assert!(!content.contains("add_entry_block"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_op_type_name() {
+        use crate::tblgen::OpDef;
+
+        let op_def = |name: &str| OpDef {
+            name: name.to_string(),
+            operands: Vec::new(),
+            attributes: Vec::new(),
+            results: Vec::new(),
+            regions: Vec::new(),
+            fixed_arity: true,
+        };
+        let options = CodegenOptions {
+            class_name_acronyms: vec!["IR".to_string()],
+            op_renames: vec![("test.add".to_string(), "Addition".to_string())],
+            ..Default::default()
+        };
+
+        for (op_name, type_name) in [
+            ("test.type", "TypeOperation"),
+            ("test.loop", "LoopOperation"),
+            ("test.ir.cast", "IRCastOperation"),
+            ("test.add", "Addition"),
+            // Keyword mnemonics are safe with the suffix
+            ("test.self", "SelfOperation"),
+        ] {
+            let name = op_type_name("test", &options, &op_def(op_name));
+            assert_eq!(name, type_name);
+            assert!(is_rust_identifier(&name));
+        }
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_region_accessors() {
+        use crate::tblgen::OpDef;
+
+        let output_path = std::env::temp_dir().join("test_region_accessors_register.rs");
+
+        let generated = GeneratedFiles {
+            op_defs: vec![OpDef {
+                name: "test.if".to_string(),
+                operands: vec!["condition".to_string()],
+                attributes: Vec::new(),
+                results: Vec::new(),
+                regions: vec!["thenRegion".to_string(), "region1".to_string()],
+                fixed_arity: false,
+            }],
+            ..Default::default()
+        };
+        generate_rust_ffi("test", &generated, &CodegenOptions::default(), &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains(
+            "        pub fn then_region_region(&self) -> ::melior::ir::RegionRef<'c, '_> {
+            ::melior::ir::operation::OperationLike::region(&self.operation, 0)
+                .expect(\"valid region index\")
+        }"
+        ));
+        assert!(
+            content.contains("::melior::ir::operation::OperationLike::region(&self.operation, 1)")
+        );
+        // The entry block goes into the first region
+        assert!(content.contains("        pub fn add_entry_block("));
+        assert!(content.contains("                &self.then_region_region(),"));
+        assert_eq!(content.matches("pub fn add_entry_block(").count(), 1);
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_llvm_translation() {
+        let output_path = std::env::temp_dir().join("test_llvm_translation_register.rs");
+
+        let options = CodegenOptions {
+            llvm_translation: true,
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &GeneratedFiles::default(), &options, &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(
+            content
+                .contains("fn mlirBrilRegisterLLVMTranslations(context: mlir_sys::MlirContext);")
+        );
+        assert!(
+            content.contains("pub fn register_llvm_translations(context: &::melior::Context) {")
+        );
+        assert!(content.contains("register, register_llvm_translations};"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_docs() {
+        use crate::tblgen::OpDoc;
+
+        let output_path = std::env::temp_dir().join("test_docs_register.rs");
+
+        let generated = GeneratedFiles {
+            dialect_summary: Some("An MLIR dialect for the Bril language.".to_string()),
+            op_docs: vec![OpDoc {
+                name: "bril.add".to_string(),
+                summary: "Integer addition".to_string(),
+            }],
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &generated, &CodegenOptions::default(), &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert_eq!(
+            content
+                .matches("    /// An MLIR dialect for the Bril language.\n")
+                .count(),
+            4
+        );
+        assert!(content.contains("    /// - `bril.add`: Integer addition\n"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_combined_rust_ffi() {
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_combined_register.rs");
+
+        let generated = GeneratedFiles::default();
+        let options = CodegenOptions::default();
+        generate_combined_rust_ffi(
+            "my_dialects",
+            &[
+                ("bril", &generated, &options),
+                ("toy", &generated, &options),
+            ],
+            &output_path,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+
+        assert!(content.contains("pub mod bril {"));
+        assert!(content.contains("pub mod toy {"));
+        assert!(content.contains("mlirGetDialectHandle__bril__"));
+        assert!(content.contains("mlirGetDialectHandle__toy__"));
+        assert_eq!(
+            content.matches("#[link(name = \"my_dialects\")]").count(),
+            2
+        );
+        assert!(content.contains(
+            "pub fn load_all(context: &::melior::Context) {\n    \
+             bril::load_unchecked(context);\n    toy::load_unchecked(context);\n}"
+        ));
+        assert!(content.contains("    toy::insert_into_registry(registry);\n"));
+        assert_eq!(content.matches('{').count(), content.matches('}').count());
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_type_constructors() {
+        use crate::tblgen::TypeDef;
+
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_type_constructors_register.rs");
+
+        let generated = GeneratedFiles {
+            type_defs: vec![TypeDef {
+                class_name: "VecType".to_string(),
+                parameters: vec![
+                    TypeParameter {
+                        name: "elementType".to_string(),
+                        kind: ParameterKind::Type,
+                    },
+                    TypeParameter {
+                        name: "width".to_string(),
+                        kind: ParameterKind::Scalar {
+                            c_type: "unsigned",
+                            rust_type: "u32",
+                        },
+                    },
+                ],
+            }],
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &generated, &CodegenOptions::default(), &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+
+        assert!(content.contains(
+            "fn mlirBrilVecTypeGet(context: mlir_sys::MlirContext, \
+             element_type: mlir_sys::MlirType, width: u32) -> mlir_sys::MlirType;"
+        ));
+        assert!(content.contains(
+            "pub fn vec_type<'c>(context: &'c ::melior::Context, \
+             element_type: ::melior::ir::Type<'c>, width: u32) -> ::melior::ir::Type<'c>"
+        ));
+        assert!(content.contains("::melior::ir::TypeLike::to_raw(&element_type), width"));
+        assert!(content.contains("loaded_dialect, register, vec_type};"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_attr_constructors() {
+        use crate::tblgen::AttrDef;
+
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_attr_constructors_register.rs");
+
+        let generated = GeneratedFiles {
+            attr_defs: vec![AttrDef {
+                class_name: "LabelAttr".to_string(),
+                parameters: vec![TypeParameter {
+                    name: "name".to_string(),
+                    kind: ParameterKind::String,
+                }],
+            }],
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &generated, &CodegenOptions::default(), &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+
+        assert!(content.contains(
+            "fn mlirBrilLabelAttrGet(context: mlir_sys::MlirContext, \
+             name: mlir_sys::MlirStringRef) -> mlir_sys::MlirAttribute;"
+        ));
+        assert!(content.contains(
+            "pub fn label_attr<'c>(context: &'c ::melior::Context, name: &str) \
+             -> ::melior::ir::Attribute<'c>"
+        ));
+        assert!(content.contains(
+            "::melior::ir::Attribute::from_raw(mlirBrilLabelAttrGet(context.to_raw(), \
+             ::melior::StringRef::new(name).to_raw()))"
+        ));
+        assert!(content.contains("loaded_dialect, register, label_attr};"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_constructor_names() {
+        use crate::tblgen::{AttrDef, TypeDef};
+
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_constructor_names_register.rs");
+
+        // Keywords are suffixed with the kind of the constructed value
+        let generated = GeneratedFiles {
+            type_defs: vec![TypeDef {
+                class_name: "Struct".to_string(),
+                parameters: Vec::new(),
+            }],
+            attr_defs: vec![AttrDef {
+                class_name: "Ref".to_string(),
+                parameters: Vec::new(),
+            }],
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &generated, &CodegenOptions::default(), &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert!(content.contains("pub fn struct_type<'c>("));
+        assert!(content.contains("pub fn ref_attribute<'c>("));
+        assert!(content.contains("register, struct_type, ref_attribute};"));
+
+        // Constructors must not clash with the registration functions
+        let generated = GeneratedFiles {
+            type_defs: vec![TypeDef {
+                class_name: "Register".to_string(),
+                parameters: Vec::new(),
+            }],
+            ..Default::default()
+        };
+        let err = generate_rust_ffi("bril", &generated, &CodegenOptions::default(), &output_path)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DuplicateRustName { dialect, name } if dialect == "bril" && name == "register"
+        ));
+
+        // Nor with each other
+        let generated = GeneratedFiles {
+            type_defs: vec![TypeDef {
+                class_name: "Label".to_string(),
+                parameters: Vec::new(),
+            }],
+            attr_defs: vec![AttrDef {
+                class_name: "Label".to_string(),
+                parameters: Vec::new(),
+            }],
+            ..Default::default()
+        };
+        let err = generate_combined_rust_ffi(
+            "dialects",
+            &[("bril", &generated, &CodegenOptions::default())],
+            &output_path,
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::DuplicateRustName { name, .. } if name == "label"));
+
+        std::fs::remove_file(&output_path).ok();
+    }
+
+    #[test]
+    fn test_generate_rust_ffi_enums() {
+        use crate::tblgen::EnumCase;
+
+        let temp_dir = std::env::temp_dir();
+        let output_path = temp_dir.join("test_enums_register.rs");
+
+        let generated = GeneratedFiles {
+            enum_defs: vec![EnumDef {
+                class_name: "CmpPredicate".to_string(),
+                bitwidth: 64,
+                cases: vec![
+                    EnumCase {
+                        symbol: "eq".to_string(),
+                        value: 0,
+                    },
+                    EnumCase {
+                        symbol: "less_than".to_string(),
+                        value: 2,
+                    },
+                ],
+            }],
+            ..Default::default()
+        };
+        generate_rust_ffi("bril", &generated, &CodegenOptions::default(), &output_path).unwrap();
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+
+        assert!(content.contains("#[repr(i64)]\n    pub enum CmpPredicate {"));
+        assert!(content.contains("Eq = 0,"));
+        assert!(content.contains("LessThan = 2,"));
+        assert!(content.contains("2 => Ok(Self::LessThan),"));
+        assert!(content.contains("impl TryFrom<i64> for CmpPredicate"));
+        assert!(content.contains("IntegerType::new(context, 64)"));
+        assert!(content.contains("register, CmpPredicate};"));
+        assert_eq!(content.matches('{').count(), content.matches('}').count());
+
+        std::fs::remove_file(&output_path).ok();
+    }
 }
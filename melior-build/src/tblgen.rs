@@ -1,16 +1,35 @@
 //! mlir-tblgen execution wrapper.
 
-use crate::Error;
+use crate::{
+    Error,
+    error::{command_line, diagnostics},
+};
 use regex::Regex;
 use std::{
+    collections::hash_map::DefaultHasher,
+    ffi::OsString,
     fs,
+    hash::{Hash, Hasher},
     path::{Path, PathBuf},
-    process::Command,
+    process::{Command, Output},
     sync::LazyLock,
 };
 
+/// How the contents of a TableGen file are detected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DetectionMode {
+    /// Match definitions in the file text with regexes (fast, no subprocess).
+    #[default]
+    Regex,
+    /// Classify the records printed by `mlir-tblgen --print-records`.
+    ///
+    /// This sees definitions expanded from `foreach`, `multiclass`, and
+    /// `defvar`, at the cost of extra `mlir-tblgen` invocations.
+    Records,
+}
+
 /// What a TableGen file contains, detected via text analysis.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct TdFileContents {
     /// File contains a Dialect definition.
     pub has_dialect: bool,
@@ -22,22 +41,95 @@ pub struct TdFileContents {
     pub has_attrs: bool,
     /// File contains enum definitions.
     pub has_enums: bool,
+    /// File contains OpInterface definitions.
+    pub has_op_interfaces: bool,
+    /// File contains TypeInterface definitions.
+    pub has_type_interfaces: bool,
+    /// File contains AttrInterface definitions.
+    pub has_attr_interfaces: bool,
+    /// File contains DRR rewrite pattern definitions.
+    pub has_rewriters: bool,
+    /// File contains pass definitions.
+    pub has_passes: bool,
+    /// File contains bytecode encodings of attributes or types.
+    pub has_bytecode: bool,
     /// File uses FunctionOpInterface.
     pub has_function_interface: bool,
+    /// File sets `hasCustomAssemblyFormat` on an op, type, or attribute, whose
+    /// parser and printer must be implemented in C++ by hand.
+    pub needs_custom_asm: bool,
+    /// File sets `hasVerifier` or `hasRegionVerifier` on an op, whose
+    /// verifier must be implemented in C++ by hand.
+    pub needs_verifier_impl: bool,
 }
 
 impl TdFileContents {
     /// Returns true if the file contains any definitions.
     pub fn has_any(&self) -> bool {
-        self.has_dialect || self.has_ops || self.has_types || self.has_attrs || self.has_enums
+        self.has_dialect
+            || self.has_ops
+            || self.has_types
+            || self.has_attrs
+            || self.has_enums
+            || self.has_op_interfaces
+            || self.has_type_interfaces
+            || self.has_attr_interfaces
+            || self.has_rewriters
+            || self.has_passes
+            || self.has_bytecode
+    }
+
+    /// Returns the names of the detected contents (e.g., "dialect" and "ops"),
+    /// for build reports.
+    pub(crate) fn names(&self) -> Vec<&'static str> {
+        [
+            (self.has_dialect, "dialect"),
+            (self.has_ops, "ops"),
+            (self.has_types, "types"),
+            (self.has_attrs, "attrs"),
+            (self.has_enums, "enums"),
+            (self.has_op_interfaces, "op_interfaces"),
+            (self.has_type_interfaces, "type_interfaces"),
+            (self.has_attr_interfaces, "attr_interfaces"),
+            (self.has_rewriters, "rewriters"),
+            (self.has_passes, "passes"),
+            (self.has_bytecode, "bytecode"),
+            (self.has_function_interface, "function_interface"),
+            (self.needs_custom_asm, "custom_asm"),
+            (self.needs_verifier_impl, "verifier_impl"),
+        ]
+        .into_iter()
+        .filter_map(|(detected, name)| detected.then_some(name))
+        .collect()
+    }
+
+    /// Adds the definitions detected in another file, e.g., an included one.
+    pub fn merge(&mut self, other: &TdFileContents) {
+        self.has_dialect |= other.has_dialect;
+        self.has_ops |= other.has_ops;
+        self.has_types |= other.has_types;
+        self.has_attrs |= other.has_attrs;
+        self.has_enums |= other.has_enums;
+        self.has_op_interfaces |= other.has_op_interfaces;
+        self.has_type_interfaces |= other.has_type_interfaces;
+        self.has_attr_interfaces |= other.has_attr_interfaces;
+        self.has_rewriters |= other.has_rewriters;
+        self.has_passes |= other.has_passes;
+        self.has_bytecode |= other.has_bytecode;
+        self.has_function_interface |= other.has_function_interface;
+        self.needs_custom_asm |= other.needs_custom_asm;
+        self.needs_verifier_impl |= other.needs_verifier_impl;
     }
 }
 
 /// Tracks which TD file stems generated which content types.
 ///
 /// This is used to generate correct include paths in the C++ registration file,
-/// since different content types may come from different TD files with different stems.
+/// since different content types may come from different TD files with
+/// different stems. The builder options that affect the generated code are
+/// passed separately as [`CodegenOptions`](crate::CodegenOptions).
 #[derive(Debug, Clone, Default)]
+#[non_exhaustive]
 pub struct GeneratedFiles {
     /// TD file stem that generated the dialect (e.g., "BrilOps" from BrilOps.td)
     pub dialect_stem: Option<String>,
@@ -49,449 +141,4313 @@ pub struct GeneratedFiles {
     pub attrs_stem: Option<String>,
     /// TD file stem that generated the enums
     pub enums_stem: Option<String>,
+    /// TD file stem that generated the op interfaces
+    pub op_interfaces_stem: Option<String>,
+    /// TD file stem that generated the type interfaces
+    pub type_interfaces_stem: Option<String>,
+    /// TD file stem that generated the attr interfaces
+    pub attr_interfaces_stem: Option<String>,
+    /// TD file stem that generated the rewrite patterns
+    pub rewriters_stem: Option<String>,
+    /// TD file stem that generated the pass declarations
+    pub passes_stem: Option<String>,
+    /// TD file stem that generated the bytecode readers and writers
+    pub bytecode_stem: Option<String>,
     /// Whether FunctionOpInterface is used
     pub use_function_interface: bool,
+    /// The detected contents of each TD file, in the order the files were added
+    pub td_contents: Vec<(PathBuf, TdFileContents)>,
+    /// Custom types that get typed C API and Rust constructors
+    pub type_defs: Vec<TypeDef>,
+    /// Custom attributes that get typed C API and Rust constructors
+    pub attr_defs: Vec<AttrDef>,
+    /// Integer enums that get mirroring Rust enums
+    pub enum_defs: Vec<EnumDef>,
+    /// The dialect's `summary`, used for the generated Rust docs
+    pub dialect_summary: Option<String>,
+    /// The operations' summaries, used for the generated Rust docs
+    pub op_docs: Vec<OpDoc>,
+    /// The full names of the dialect's operations (e.g., "bril.add")
+    pub operations: Vec<String>,
+    /// The operations that get typed Rust builder functions
+    pub op_defs: Vec<OpDef>,
+    /// The C++ classes of the operations by full operation name (e.g.,
+    /// `("bril.add", "::mlir::bril::AddOp")`), collected when only some of
+    /// them are registered
+    pub op_classes: Vec<(String, String)>,
+}
+
+/// The summary of an operation, parsed from TableGen records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpDoc {
+    /// The full operation name (e.g., "bril.add")
+    pub name: String,
+    /// The operation's `summary`
+    pub summary: String,
+}
+
+/// The signature of an operation, parsed from TableGen records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpDef {
+    /// The full operation name (e.g., "bril.add")
+    pub name: String,
+    /// The operand names, in declaration order
+    pub operands: Vec<String>,
+    /// The attribute names, in declaration order
+    pub attributes: Vec<String>,
+    /// The result names, in declaration order (empty for unnamed results)
+    pub results: Vec<String>,
+    /// The region names, in declaration order, excluding a trailing variadic
+    /// region (`region{index}` for unnamed regions)
+    pub regions: Vec<String>,
+    /// Whether the op has a fixed number of operands and results, and no
+    /// regions, successors, or properties
+    pub fixed_arity: bool,
+}
+
+/// The operands, results, and attributes of an operation with their
+/// constraints, parsed from TableGen records for external code generators.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpSignature {
+    /// The full operation name (e.g., "bril.add")
+    pub name: String,
+    /// The operand names and type constraints (e.g., `("lhs", "I64")`), in
+    /// declaration order (names are empty for unnamed operands)
+    pub operands: Vec<(String, String)>,
+    /// The result names and type constraints, in declaration order (names
+    /// are empty for unnamed results)
+    pub results: Vec<(String, String)>,
+    /// The attribute names and constraints, in declaration order
+    pub attributes: Vec<(String, String)>,
+    /// The indices of the `Variadic` or `Optional` operands in `operands`
+    pub variadic_operands: Vec<usize>,
+    /// The indices of the `Variadic` or `Optional` results in `results`
+    pub variadic_results: Vec<usize>,
+}
+
+/// An integer enum definition (e.g., `I64EnumAttr`), parsed from TableGen
+/// records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumDef {
+    /// The C++ class name (e.g., "CmpPredicate")
+    pub class_name: String,
+    /// The bit width of the underlying integer
+    pub bitwidth: u32,
+    /// The enum cases, in declaration order
+    pub cases: Vec<EnumCase>,
+}
+
+/// A case of an integer enum definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumCase {
+    /// The C++ enumerant name (e.g., "eq")
+    pub symbol: String,
+    /// The integer value
+    pub value: i64,
+}
+
+/// A custom type definition of the dialect, parsed from TableGen records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeDef {
+    /// The C++ class name (e.g., "PtrType")
+    pub class_name: String,
+    /// The type parameters, in declaration order
+    pub parameters: Vec<TypeParameter>,
+}
+
+/// A custom attribute definition of the dialect, parsed from TableGen records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttrDef {
+    /// The C++ class name (e.g., "LabelAttr")
+    pub class_name: String,
+    /// The attribute parameters, in declaration order
+    pub parameters: Vec<TypeParameter>,
+}
+
+/// A parameter of a custom type or attribute definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeParameter {
+    /// The parameter name (e.g., "pointeeType")
+    pub name: String,
+    /// How the parameter is passed through the C API
+    pub kind: ParameterKind,
+}
+
+/// How a type or attribute parameter is passed through the C API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterKind {
+    /// An `mlir::Type`, passed as `MlirType`
+    Type,
+    /// An `mlir::Attribute`, passed as `MlirAttribute`
+    Attribute,
+    /// An `llvm::StringRef`, passed as `MlirStringRef`
+    String,
+    /// A scalar passed by value
+    Scalar {
+        /// The C type (e.g., "int64_t")
+        c_type: &'static str,
+        /// The equivalent Rust type (e.g., "i64")
+        rust_type: &'static str,
+    },
+}
+
+impl ParameterKind {
+    /// Returns the kind for a C++ parameter type, or `None` if the type
+    /// cannot be passed through the C API.
+    pub fn from_cpp_type(cpp_type: &str) -> Option<Self> {
+        const SCALARS: &[(&str, &str)] = &[
+            ("bool", "bool"),
+            ("int", "i32"),
+            ("int32_t", "i32"),
+            ("int64_t", "i64"),
+            ("unsigned", "u32"),
+            ("unsigned int", "u32"),
+            ("uint32_t", "u32"),
+            ("uint64_t", "u64"),
+        ];
+
+        let cpp_type = cpp_type.trim().trim_start_matches("::");
+        let cpp_type = cpp_type
+            .strip_prefix("mlir::")
+            .or_else(|| cpp_type.strip_prefix("std::"))
+            .unwrap_or(cpp_type);
+
+        match cpp_type {
+            "Type" => Some(Self::Type),
+            "Attribute" => Some(Self::Attribute),
+            _ => SCALARS
+                .iter()
+                .find(|(c_type, _)| *c_type == cpp_type)
+                .map(|&(c_type, rust_type)| Self::Scalar { c_type, rust_type }),
+        }
+    }
+
+    /// Returns the kind for a C++ attribute parameter type. Unlike type
+    /// parameters, these can also be strings, which are copied into the
+    /// attribute's storage by `StringRefParameter`.
+    pub fn from_attr_cpp_type(cpp_type: &str) -> Option<Self> {
+        match cpp_type.trim().trim_start_matches("::") {
+            "StringRef" | "llvm::StringRef" => Some(Self::String),
+            _ => Self::from_cpp_type(cpp_type),
+        }
+    }
 }
 
 // Static regexes for TD file content detection (compiled once)
 static DIALECT_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"def\s+\w+\s*:\s*Dialect\s*\{").unwrap());
-static OP_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"def\s+\w+\s*:\s*\w*_?Op<").unwrap());
+    LazyLock::new(|| Regex::new(r"def\s+(\w+)\s*:\s*Dialect\s*\{").unwrap());
+// Also matches the templated and anonymous names of `def`s in `foreach` and
+// `multiclass` bodies, e.g., `def Bril_ # name # Op : Bril_Op <name>`
+static OP_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"def\s+[^:;{}]*:\s*\w*_?Op\s*<").unwrap());
+static MULTICLASS_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"multiclass\s+(\w+)[^{;]*\{").unwrap());
+static DEFM_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"defm\s+[^:;]*:([^;{]*)").unwrap());
 static TYPEDEF_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"def\s+\w+\s*:\s*(\w*_?Type<|TypeDef<)").unwrap());
 static ATTRDEF_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"def\s+\w+\s*:\s*(\w*_?Attr<|AttrDef<)").unwrap());
 static ENUM_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(EnumAttr|IntEnumAttr|BitEnumAttr)").unwrap());
+static OP_INTERFACE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"def\s+\w+\s*:\s*OpInterface<").unwrap());
+static TYPE_INTERFACE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"def\s+\w+\s*:\s*TypeInterface<").unwrap());
+static ATTR_INTERFACE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"def\s+\w+\s*:\s*AttrInterface<").unwrap());
+static REWRITER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"def\s+(\w+\s*)?:\s*Pat(tern)?<").unwrap());
+static FUNCTION_INTERFACE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"def\s+\w+\s*:\s*\w*_?Op<[^{;]*\bFunctionOpInterface\b|let\s+traits\s*=\s*\[[^\]]*\bFunctionOpInterface\b",
+    )
+    .unwrap()
+});
+static CUSTOM_ASM_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"let\s+hasCustomAssemblyFormat\s*=\s*(1|true)\s*;").unwrap());
+static VERIFIER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"let\s+has(Region)?Verifier\s*=\s*(1|true)\s*;").unwrap());
+static PASS_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"def\s+\w+\s*:\s*(Pass<|PassBase<)").unwrap());
+static BYTECODE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"def\s+\w+\s*:\s*Dialect(Attributes|Types)<\s*"([^"]*)""#).unwrap()
+});
+static CPP_NAMESPACE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"let\s+cppNamespace\s*=\s*"([^"]*)"\s*;"#).unwrap());
+static DIALECT_NAME_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"let\s+name\s*=\s*"([^"]*)"\s*;"#).unwrap());
+static CODE_BLOCK_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)\[\{.*?\}\]").unwrap());
+static INCLUDE_LINE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^\s*include\s+"([^"]*)""#).unwrap());
+static INCLUDE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"include\s+"([^"]*)""#).unwrap());
+static TYPE_PARAMETER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?:"([^"]*)"|(\w+)):\$(\w+)"#).unwrap());
+static INT_ENUM_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^I(\d+)EnumAttr$").unwrap());
+static RECORD_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^def\s+(\S+)\s*\{(?:\s*//(.*))?$").unwrap());
+
+/// Read a TableGen file, normalizing CRLF line endings to `\n` so that the
+/// regexes and line-based parsing behave the same for files saved on Windows.
+fn read_td_file(path: &Path) -> Result<String, Error> {
+    Ok(fs::read_to_string(path)?.replace("\r\n", "\n"))
+}
+
+/// Strip `//` line comments and `/* ... */` block comments from TableGen
+/// source.
+///
+/// Comment markers inside string literals are left untouched. Newlines are
+/// preserved so that the structure of the remaining text is unchanged.
+fn strip_comments(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            result.push(c);
+            match c {
+                '\\' => result.extend(chars.next()),
+                '"' | '\n' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                result.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|&c| c != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = '\0';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        result.push(c);
+                    } else if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
 
 /// Detect what definitions a TableGen file contains.
 ///
 /// This uses simple regex matching to detect:
 /// - Dialect definitions: `def.*: Dialect`
-/// - Op definitions: `def SomeName : SomeClass_Op<` or `def SomeName : Op<`
+/// - Op definitions: `def SomeName : SomeClass_Op<` or `def SomeName : Op<`,
+///   also in `foreach` loops and in multiclasses instantiated with `defm` in
+///   the same file (see [`DetectionMode::Records`] for other generated ops)
 /// - Type definitions: `def SomeName : TypeDef<` or `def SomeName : SomeClass_Type<`
 /// - Attr definitions: `def SomeName : AttrDef<` or `def SomeName : SomeClass_Attr<`
 /// - Enum definitions: `EnumAttr` or `IntEnumAttr`
-/// - FunctionOpInterface usage
+/// - Op interface definitions: `def SomeName : OpInterface<`
+/// - Type interface definitions: `def SomeName : TypeInterface<`
+/// - Attr interface definitions: `def SomeName : AttrInterface<`
+/// - DRR rewrite patterns: `def SomeName : Pat<` or `def : Pattern<`
+/// - Pass definitions: `def SomeName : Pass<` or `def SomeName : PassBase<`
+/// - Bytecode encodings: `DialectAttributes<` or `DialectTypes<` definitions
+///   (the `DialectType<` encodings they list are not type definitions)
+/// - FunctionOpInterface usage: in an op's trait list or `let traits = [...]`
+/// - Hand-written assembly formats: `let hasCustomAssemblyFormat = 1`
+/// - Hand-written verifiers: `let hasVerifier = 1` (or `hasRegionVerifier`)
+///
+/// A declarative `assemblyFormat` does not count as a custom assembly format,
+/// since `mlir-tblgen` generates its parser and printer.
 ///
 /// Note: This distinguishes between `class` statements (base class definitions)
 /// and `def` statements (actual definitions). Only `def` statements count as
-/// defining ops/types/attrs.
+/// defining ops/types/attrs. Definitions that only appear inside comments are
+/// ignored.
 pub fn detect_td_contents(path: &Path) -> Result<TdFileContents, Error> {
-    let content = fs::read_to_string(path)?;
+    let content = strip_comments(&read_td_file(path)?);
 
     Ok(TdFileContents {
         has_dialect: DIALECT_RE.is_match(&content),
-        has_ops: OP_RE.is_match(&content),
-        has_types: TYPEDEF_RE.is_match(&content),
+        has_ops: defines_ops(&content),
+        // `DialectType<` is a bytecode encoding, not a type definition
+        has_types: TYPEDEF_RE
+            .captures_iter(&content)
+            .any(|captures| &captures[1] != "DialectType<"),
         has_attrs: ATTRDEF_RE.is_match(&content),
         has_enums: ENUM_RE.is_match(&content),
-        has_function_interface: content.contains("FunctionOpInterface"),
+        has_op_interfaces: OP_INTERFACE_RE.is_match(&content),
+        has_type_interfaces: TYPE_INTERFACE_RE.is_match(&content),
+        has_attr_interfaces: ATTR_INTERFACE_RE.is_match(&content),
+        has_rewriters: REWRITER_RE.is_match(&content),
+        has_passes: PASS_RE.is_match(&content),
+        has_bytecode: BYTECODE_RE.is_match(&content),
+        has_function_interface: FUNCTION_INTERFACE_RE.is_match(&content),
+        needs_custom_asm: CUSTOM_ASM_RE.is_match(&content),
+        needs_verifier_impl: VERIFIER_RE.is_match(&content),
     })
 }
 
-/// Runner for mlir-tblgen commands.
-pub struct TblgenRunner {
-    /// Path to the mlir-tblgen binary
-    tblgen_path: PathBuf,
-    /// LLVM include directory
-    llvm_include: PathBuf,
-}
-
-impl TblgenRunner {
-    /// Create a new TblgenRunner from the LLVM prefix.
-    pub fn new(llvm_prefix: &Path) -> Result<Self, Error> {
-        let tblgen_path = llvm_prefix.join("bin").join("mlir-tblgen");
+/// Returns whether TableGen source defines operations, directly, in a
+/// `foreach` loop, or in a multiclass that a `defm` instantiates.
+fn defines_ops(content: &str) -> bool {
+    let content = CODE_BLOCK_RE.replace_all(content, "");
 
-        if !tblgen_path.exists() {
-            return Err(Error::TblgenNotFound(tblgen_path));
+    // Multiclass bodies only define ops when instantiated
+    let mut top_level = String::new();
+    let mut op_multiclasses = Vec::new();
+    let mut rest = &content[..];
+    while let Some(captures) = MULTICLASS_RE.captures(rest) {
+        let header = captures.get(0).unwrap();
+        top_level.push_str(&rest[..header.start()]);
+        let body = &rest[header.end()..];
+        let end = closing_brace(body).unwrap_or(body.len());
+        if OP_RE.is_match(&body[..end]) {
+            op_multiclasses.push(captures.get(1).unwrap().as_str());
         }
+        rest = body.get(end + 1..).unwrap_or_default();
+    }
+    top_level.push_str(rest);
 
-        Ok(Self {
-            tblgen_path,
-            llvm_include: llvm_prefix.join("include"),
+    OP_RE.is_match(&top_level)
+        || DEFM_RE.captures_iter(&top_level).any(|captures| {
+            captures[1]
+                .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .any(|class| op_multiclasses.contains(&class))
         })
+}
+
+/// Returns the position of the `}` closing a block whose `{` precedes
+/// `body`.
+fn closing_brace(body: &str) -> Option<usize> {
+    let mut depth = 1;
+    for (index, c) in body.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
     }
+    None
+}
 
-    /// Generate .inc files for a TD file based on its detected contents.
-    ///
-    /// Output file names are based on the TD file stem (e.g., `BrilOps.td` produces
-    /// `BrilOpsDialect.h.inc`, `BrilOps.h.inc`, etc.), matching MLIR convention.
-    pub fn generate_for_file(
-        &self,
-        td_file: &Path,
-        include_dirs: &[PathBuf],
-        output_dir: &Path,
-        dialect_name: &str,
-        contents: &TdFileContents,
-    ) -> Result<(), Error> {
-        // Use TD file stem for output naming (MLIR convention)
-        let stem = td_file
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .ok_or_else(|| {
-                Error::Io(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    format!("Invalid TD file path: {}", td_file.display()),
-                ))
-            })?;
+/// Extract the name of the dialect defined in a TableGen file.
+///
+/// Returns the `let name = "..."` value of the first `def ... : Dialect`, or
+/// `None` if the file defines no dialect or the name is not a string literal.
+/// Code blocks (`[{ ... }]`), such as descriptions, are skipped.
+pub fn dialect_name(path: &Path) -> Result<Option<String>, Error> {
+    let content = strip_comments(&read_td_file(path)?);
+    let content = CODE_BLOCK_RE.replace_all(&content, "");
 
-        if contents.has_dialect {
-            self.run_tblgen(
-                td_file,
-                include_dirs,
-                &output_dir.join(format!("{}Dialect.h.inc", stem)),
-                "-gen-dialect-decls",
-                Some(dialect_name),
-            )?;
-            self.run_tblgen(
-                td_file,
-                include_dirs,
-                &output_dir.join(format!("{}Dialect.cpp.inc", stem)),
-                "-gen-dialect-defs",
-                Some(dialect_name),
-            )?;
-        }
+    Ok(DIALECT_RE.find(&content).and_then(|dialect| {
+        DIALECT_NAME_RE
+            .captures(&content[dialect.end()..])
+            .map(|captures| captures[1].to_string())
+    }))
+}
 
-        if contents.has_ops {
-            self.run_tblgen(
-                td_file,
-                include_dirs,
-                &output_dir.join(format!("{}.h.inc", stem)),
-                "-gen-op-decls",
-                Some(dialect_name),
-            )?;
-            self.run_tblgen(
-                td_file,
-                include_dirs,
-                &output_dir.join(format!("{}.cpp.inc", stem)),
-                "-gen-op-defs",
-                Some(dialect_name),
-            )?;
-        }
+/// Extract the names of all dialects defined in a TableGen file.
+///
+/// Each `def ... : Dialect` contributes its `let name = "..."` value, or the
+/// name of the definition itself if the dialect name is not a string literal.
+/// Code blocks (`[{ ... }]`), such as descriptions, are skipped.
+pub fn dialect_names(path: &Path) -> Result<Vec<String>, Error> {
+    let content = strip_comments(&read_td_file(path)?);
+    let content = CODE_BLOCK_RE.replace_all(&content, "");
 
-        if contents.has_types {
-            self.run_tblgen(
-                td_file,
-                include_dirs,
-                &output_dir.join(format!("{}Types.h.inc", stem)),
-                "-gen-typedef-decls",
-                Some(dialect_name),
-            )?;
-            self.run_tblgen(
-                td_file,
-                include_dirs,
-                &output_dir.join(format!("{}Types.cpp.inc", stem)),
-                "-gen-typedef-defs",
-                Some(dialect_name),
-            )?;
-        }
+    Ok(dialect_definitions(&content)
+        .into_iter()
+        .map(|(name, body)| {
+            DIALECT_NAME_RE
+                .captures(body)
+                .map_or(name, |captures| captures.get(1).unwrap().as_str())
+                .to_string()
+        })
+        .collect())
+}
 
-        if contents.has_attrs {
-            self.run_tblgen(
-                td_file,
-                include_dirs,
-                &output_dir.join(format!("{}Attrs.h.inc", stem)),
-                "-gen-attrdef-decls",
-                Some(dialect_name),
-            )?;
-            self.run_tblgen(
-                td_file,
-                include_dirs,
-                &output_dir.join(format!("{}Attrs.cpp.inc", stem)),
-                "-gen-attrdef-defs",
-                Some(dialect_name),
-            )?;
-        }
+/// Extract the dialect name that the bytecode encodings of a TableGen file are
+/// defined for, as passed to `-bytecode-dialect`.
+///
+/// Returns the argument of the first `DialectAttributes<"...">` or
+/// `DialectTypes<"...">` definition, or `None` if the file has none.
+pub fn bytecode_dialect(path: &Path) -> Result<Option<String>, Error> {
+    let content = strip_comments(&read_td_file(path)?);
 
-        if contents.has_enums {
-            self.run_tblgen(
-                td_file,
-                include_dirs,
-                &output_dir.join(format!("{}Enums.h.inc", stem)),
-                "-gen-enum-decls",
-                Some(dialect_name),
-            )?;
-            self.run_tblgen(
-                td_file,
-                include_dirs,
-                &output_dir.join(format!("{}Enums.cpp.inc", stem)),
-                "-gen-enum-defs",
-                Some(dialect_name),
-            )?;
+    Ok(BYTECODE_RE
+        .captures(&content)
+        .map(|captures| captures[2].to_string()))
+}
+
+/// Extract the `cppNamespace` of the first dialect defined in a TableGen file.
+///
+/// Returns the namespace as written (e.g., `::mlir::bril`), or `None` if the
+/// file defines no dialect or the dialect sets no namespace.
+pub fn dialect_cpp_namespace(path: &Path) -> Result<Option<String>, Error> {
+    let content = strip_comments(&read_td_file(path)?);
+    let content = CODE_BLOCK_RE.replace_all(&content, "");
+
+    Ok(dialect_definitions(&content)
+        .first()
+        .and_then(|(_, body)| CPP_NAMESPACE_RE.captures(body))
+        .map(|captures| captures[1].to_string()))
+}
+
+/// Returns the name and body of each dialect definition in TableGen source,
+/// where a body extends to the start of the next dialect definition.
+fn dialect_definitions(content: &str) -> Vec<(&str, &str)> {
+    let definitions = DIALECT_RE.captures_iter(content).collect::<Vec<_>>();
+    let ends = definitions
+        .iter()
+        .skip(1)
+        .map(|definition| definition.get(0).unwrap().start())
+        .chain([content.len()]);
+
+    definitions
+        .iter()
+        .zip(ends)
+        .map(|(definition, end)| {
+            (
+                definition.get(1).unwrap().as_str(),
+                &content[definition.get(0).unwrap().end()..end],
+            )
+        })
+        .collect()
+}
+
+/// Collect the transitive set of files included by a TableGen file.
+///
+/// `include "..."` directives are resolved against the including file's
+/// directory first and then against `include_dirs`, as `mlir-tblgen` does.
+/// Includes that cannot be resolved (e.g., `mlir/IR/OpBase.td` from the LLVM
+/// installation) are skipped, and include cycles are handled gracefully. The
+/// returned files are in discovery order and exclude `td_file` itself; a file
+/// reached under several spellings (e.g., through `..`) is returned once.
+pub fn collect_includes(td_file: &Path, include_dirs: &[PathBuf]) -> Result<Vec<PathBuf>, Error> {
+    let mut includes = Vec::new();
+    let mut visited = vec![fs::canonicalize(td_file)?];
+    let mut pending = vec![td_file.to_path_buf()];
+
+    while let Some(file) = pending.pop() {
+        let content = strip_comments(&read_td_file(&file)?);
+        let parent = file.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        for captures in INCLUDE_RE.captures_iter(&content) {
+            let Some(resolved) = std::iter::once(&parent)
+                .chain(include_dirs)
+                .map(|dir| dir.join(&captures[1]))
+                .find(|path| path.is_file())
+            else {
+                continue;
+            };
+
+            let canonical = fs::canonicalize(&resolved)?;
+            if !visited.contains(&canonical) {
+                visited.push(canonical);
+                includes.push(resolved.clone());
+                pending.push(resolved);
+            }
         }
+    }
 
-        Ok(())
+    Ok(includes)
+}
+
+/// Inline the `include "..."` directives of a TableGen file into one string.
+///
+/// Includes are resolved like in [`collect_includes`]. Resolvable includes are
+/// replaced by the content of the included file, recursively, and each file is
+/// inlined at most once; later includes of the same file are dropped.
+/// Includes that cannot be resolved (e.g., `mlir/IR/OpBase.td` from the LLVM
+/// installation) are left as-is.
+///
+/// This is useful for the `melior::dialect!` macro, whose TableGen parser works
+/// best with a single file: write the result to a temporary file and point the
+/// macro at it.
+pub fn flatten_includes(td_file: &Path, include_dirs: &[PathBuf]) -> Result<String, Error> {
+    let mut inlined = Vec::new();
+    let mut output = String::new();
+    flatten_into(td_file, include_dirs, &mut inlined, &mut output)?;
+    Ok(output)
+}
+
+/// Append the flattened content of `td_file` to `output`, unless it is in
+/// `inlined` already, so several TD files can be flattened into one without
+/// repeating shared includes.
+pub(crate) fn flatten_into(
+    td_file: &Path,
+    include_dirs: &[PathBuf],
+    inlined: &mut Vec<PathBuf>,
+    output: &mut String,
+) -> Result<(), Error> {
+    let canonical = fs::canonicalize(td_file)?;
+    if !inlined.contains(&canonical) {
+        inlined.push(canonical);
+        flatten_file(td_file, include_dirs, inlined, output)?;
     }
+    Ok(())
+}
 
-    fn run_tblgen(
-        &self,
-        td_file: &Path,
-        include_dirs: &[PathBuf],
-        output: &Path,
-        action: &str,
-        dialect: Option<&str>,
-    ) -> Result<(), Error> {
-        let mut cmd = Command::new(&self.tblgen_path);
-        cmd.arg(action).arg(td_file).arg("-o").arg(output);
-        cmd.arg("-I").arg(&self.llvm_include);
-        for include_dir in include_dirs {
-            cmd.arg("-I").arg(include_dir);
-        }
-        if let Some(dialect_name) = dialect {
-            cmd.arg(format!("--dialect={}", dialect_name));
-        }
+/// Append the content of `file` to `output`, inlining the includes of files
+/// not yet in `inlined`.
+fn flatten_file(
+    file: &Path,
+    include_dirs: &[PathBuf],
+    inlined: &mut Vec<PathBuf>,
+    output: &mut String,
+) -> Result<(), Error> {
+    let content = read_td_file(file)?;
+    let parent = file.parent().map(Path::to_path_buf).unwrap_or_default();
 
-        let output_result = cmd.output()?;
+    for line in content.lines() {
+        let resolved = INCLUDE_LINE_RE.captures(line).and_then(|captures| {
+            std::iter::once(&parent)
+                .chain(include_dirs)
+                .map(|dir| dir.join(&captures[1]))
+                .find(|path| path.is_file())
+        });
 
-        if !output_result.status.success() {
-            let stderr = String::from_utf8_lossy(&output_result.stderr);
-            return Err(Error::TblgenFailed(format!(
-                "mlir-tblgen {} failed:\n{}",
-                action, stderr
-            )));
+        match resolved {
+            Some(resolved) => {
+                let canonical = fs::canonicalize(&resolved)?;
+                if !inlined.contains(&canonical) {
+                    inlined.push(canonical);
+                    flatten_file(&resolved, include_dirs, inlined, output)?;
+                }
+            }
+            None => {
+                output.push_str(line);
+                output.push('\n');
+            }
         }
-
-        Ok(())
     }
+
+    Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::to_class_name;
-    use std::io::Write;
+/// List the operations defined in or included by a TableGen file.
+///
+/// Runs `mlir-tblgen --print-records` from the LLVM installation at
+/// `llvm_prefix`, or `llvm-tblgen` if the installation has no `mlir-tblgen`,
+/// and returns the fully-qualified names of all ops (e.g., `bril.add`),
+/// sorted, for use in tests and runtime registration checks.
+pub fn list_operations(
+    td_file: &Path,
+    include_dirs: &[PathBuf],
+    llvm_prefix: &Path,
+) -> Result<Vec<String>, Error> {
+    let runner = TblgenRunner::with_llvm_tblgen_fallback(llvm_prefix, None)?;
 
-    #[test]
-    fn test_to_class_name() {
-        assert_eq!(to_class_name("toy"), "Toy");
-        assert_eq!(to_class_name("math_ext"), "MathExt");
-        assert_eq!(to_class_name("my_custom_dialect"), "MyCustomDialect");
-        assert_eq!(to_class_name(""), "");
+    Ok(parse_op_names(
+        &parse_records(&runner.print_records(&runner, td_file, include_dirs)?),
+        &[],
+    ))
+}
+
+/// Describe the operations defined in or included by a TableGen file.
+///
+/// Runs `mlir-tblgen --print-records` from the LLVM installation at
+/// `llvm_prefix`, falling back to `llvm-tblgen` like [`list_operations`], and
+/// returns the operand, result, and attribute constraints
+/// of all ops, e.g., for generating bindings outside of melior-build.
+/// Properties and arguments with unknown constraints are skipped.
+pub fn describe_operations(
+    td_file: &Path,
+    include_dirs: &[PathBuf],
+    llvm_prefix: &Path,
+) -> Result<Vec<OpSignature>, Error> {
+    let runner = TblgenRunner::with_llvm_tblgen_fallback(llvm_prefix, None)?;
+
+    Ok(parse_op_signatures(&parse_records(&runner.print_records(
+        &runner,
+        td_file,
+        include_dirs,
+    )?)))
+}
+
+/// A record printed by `mlir-tblgen --print-records`.
+#[derive(Debug, Clone)]
+struct Record {
+    /// The record name
+    name: String,
+    /// All superclasses of the record, including indirect ones
+    superclasses: Vec<String>,
+    /// The record's field listing
+    body: String,
+}
+
+/// The records of a TableGen file, along with the records already defined by
+/// the file's own `include`s.
+///
+/// Returned by [`TblgenRunner::records`], so everything melior-build reads
+/// from a file's records comes from a single `--print-records` pass.
+#[derive(Debug, Clone)]
+pub struct TdRecords {
+    /// All records of the file, including those of its includes
+    records: Vec<Record>,
+    /// The records defined by the file's includes
+    included: Vec<Record>,
+}
+
+impl TdRecords {
+    /// Returns what definitions the file itself contains.
+    ///
+    /// Records that are already defined by the file's own `include`s are
+    /// excluded, so only definitions made by the file itself (including those
+    /// expanded from `foreach` or `multiclass`) are counted.
+    pub fn contents(&self) -> TdFileContents {
+        classify_records(&self.records, &self.included)
     }
 
-    #[test]
-    fn test_detect_dialect() {
-        let temp_dir = std::env::temp_dir();
-        let path = temp_dir.join("test_dialect.td");
-        let mut file = std::fs::File::create(&path).unwrap();
-        writeln!(
-            file,
-            r#"
-def Bril_Dialect : Dialect {{
-    let name = "bril";
-}}
-"#
+    /// Returns the custom types of the given dialect defined in or included by
+    /// the file, for generating typed constructors.
+    pub fn type_defs(&self, dialect_name: &str) -> Vec<TypeDef> {
+        parse_type_defs(&self.records, dialect_name)
+    }
+
+    /// Returns the custom attributes of the given dialect defined in or
+    /// included by the file, for generating typed constructors, and the class
+    /// names of the attributes whose parameters cannot be passed through the C
+    /// API.
+    pub fn attr_defs(&self, dialect_name: &str) -> (Vec<AttrDef>, Vec<String>) {
+        parse_attr_defs(&self.records, dialect_name)
+    }
+
+    /// Returns the integer enums defined by the file itself (not by its
+    /// includes), for generating mirroring Rust enums.
+    pub fn enum_defs(&self) -> Vec<EnumDef> {
+        parse_enum_defs(&self.records, &self.included)
+    }
+
+    /// Returns the summary of the given dialect and the summaries of the
+    /// operations defined by the file itself (not by its includes), for
+    /// documenting the generated Rust code.
+    pub fn summaries(&self, dialect_name: &str) -> (Option<String>, Vec<OpDoc>) {
+        (
+            parse_dialect_summary(&self.records, dialect_name),
+            parse_op_docs(&self.records, &self.included, dialect_name),
         )
-        .unwrap();
+    }
 
-        let contents = detect_td_contents(&path).unwrap();
-        assert!(contents.has_dialect);
-        assert!(!contents.has_ops);
-        assert!(!contents.has_types);
+    /// Returns the fully-qualified names of the operations of the given
+    /// dialect defined by the file itself (not by its includes).
+    pub fn operations(&self, dialect_name: &str) -> Vec<String> {
+        let prefix = format!("{}.", dialect_name);
 
-        std::fs::remove_file(&path).ok();
+        parse_op_names(&self.records, &self.included)
+            .into_iter()
+            .filter(|name| name.starts_with(&prefix))
+            .collect()
     }
 
-    #[test]
-    fn test_detect_ops() {
+    /// Returns the fully-qualified names and C++ classes of the operations of
+    /// the given dialect defined by the file itself (not by its includes).
+    pub fn op_classes(&self, dialect_name: &str) -> Vec<(String, String)> {
+        let prefix = format!("{}.", dialect_name);
+
+        parse_op_classes(&self.records, &self.included)
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(&prefix))
+            .collect()
+    }
+
+    /// Returns the signatures of the operations of the given dialect defined
+    /// by the file itself (not by its includes), for generating typed builder
+    /// functions.
+    pub fn op_defs(&self, dialect_name: &str) -> Vec<OpDef> {
+        let prefix = format!("{}.", dialect_name);
+
+        parse_op_defs(&self.records, &self.included)
+            .into_iter()
+            .filter(|op_def| op_def.name.starts_with(&prefix))
+            .collect()
+    }
+}
+
+/// Parse the `Defs` section of `mlir-tblgen --print-records` output.
+fn parse_records(output: &str) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut current: Option<Record> = None;
+
+    for line in output
+        .lines()
+        .skip_while(|line| !line.starts_with("------------- Defs"))
+    {
+        if let Some(record) = current.as_mut() {
+            if line == "}" {
+                records.extend(current.take());
+            } else {
+                record.body.push_str(line);
+                record.body.push('\n');
+            }
+        } else if let Some(captures) = RECORD_RE.captures(line) {
+            current = Some(Record {
+                name: captures[1].to_string(),
+                superclasses: captures
+                    .get(2)
+                    .map(|m| m.as_str().split_whitespace().map(String::from).collect())
+                    .unwrap_or_default(),
+                body: String::new(),
+            });
+        }
+    }
+
+    records
+}
+
+/// Returns the value of a field printed in a record body, e.g., `"PtrType"`
+/// for `string cppClassName = "PtrType";`.
+fn record_field<'a>(record: &'a Record, name: &str) -> Option<&'a str> {
+    record.body.lines().find_map(|line| {
+        let (declaration, value) = line.trim().split_once(" = ")?;
+        (declaration.rsplit(' ').next() == Some(name)).then(|| value.trim_end_matches(';'))
+    })
+}
+
+/// Returns the records deriving from `class` (e.g., "TypeDef") that belong
+/// to the given dialect.
+fn dialect_defs<'a>(
+    records: &'a [Record],
+    dialect_name: &str,
+    class: &'a str,
+) -> impl Iterator<Item = &'a Record> {
+    let quoted_name = format!("\"{}\"", dialect_name);
+    let dialects = records
+        .iter()
+        .filter(|record| {
+            record.superclasses.iter().any(|c| c == "Dialect")
+                && record_field(record, "name") == Some(quoted_name.as_str())
+        })
+        .map(|record| record.name.as_str())
+        .collect::<Vec<_>>();
+
+    records.iter().filter(move |record| {
+        record.superclasses.iter().any(|c| c == class)
+            && record_field(record, "dialect").is_some_and(|d| dialects.contains(&d))
+    })
+}
+
+/// Parse the parameters of a type or attribute definition, resolving
+/// parameter records (e.g., `StringRefParameter`) to their `cppType`.
+///
+/// Returns `None` if a parameter cannot be passed through the C API.
+fn parse_parameters(
+    records: &[Record],
+    record: &Record,
+    kind: impl Fn(&str) -> Option<ParameterKind>,
+) -> Option<Vec<TypeParameter>> {
+    let parameters = record_field(record, "parameters").unwrap_or("(ins)");
+    let parsed = TYPE_PARAMETER_RE
+        .captures_iter(parameters)
+        .map(|captures| {
+            let cpp_type = match (captures.get(1), captures.get(2)) {
+                (Some(cpp_type), _) => cpp_type.as_str(),
+                (_, Some(parameter)) => records
+                    .iter()
+                    .find(|record| record.name == parameter.as_str())
+                    .and_then(|record| record_field(record, "cppType"))?
+                    .trim_matches('"'),
+                _ => return None,
+            };
+
+            Some(TypeParameter {
+                name: captures[3].to_string(),
+                kind: kind(cpp_type)?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    // Parameters that are not plain C++ types or parameter records are not
+    // supported
+    (parsed.len() == parameters.matches(":$").count()).then_some(parsed)
+}
+
+/// Extract the custom type definitions of the given dialect from records.
+///
+/// Only types whose parameters can all be passed through the C API are
+/// returned; others (e.g., with `ArrayRefParameter`s) are skipped.
+fn parse_type_defs(records: &[Record], dialect_name: &str) -> Vec<TypeDef> {
+    dialect_defs(records, dialect_name, "TypeDef")
+        .filter_map(|record| {
+            Some(TypeDef {
+                class_name: record_field(record, "cppClassName")?
+                    .trim_matches('"')
+                    .to_string(),
+                parameters: parse_parameters(records, record, ParameterKind::from_cpp_type)?,
+            })
+        })
+        .collect()
+}
+
+/// Extract the custom attribute definitions of the given dialect from
+/// records.
+///
+/// Returns the attributes whose parameters can all be passed through the C
+/// API, and the class names of the others.
+fn parse_attr_defs(records: &[Record], dialect_name: &str) -> (Vec<AttrDef>, Vec<String>) {
+    let mut attr_defs = Vec::new();
+    let mut skipped = Vec::new();
+
+    for record in dialect_defs(records, dialect_name, "AttrDef") {
+        let Some(class_name) = record_field(record, "cppClassName") else {
+            continue;
+        };
+        let class_name = class_name.trim_matches('"').to_string();
+        match parse_parameters(records, record, ParameterKind::from_attr_cpp_type) {
+            Some(parameters) => attr_defs.push(AttrDef {
+                class_name,
+                parameters,
+            }),
+            None => skipped.push(class_name),
+        }
+    }
+
+    (attr_defs, skipped)
+}
+
+/// Parse a string value of a record field, either quoted (`"..."`) or a code
+/// block (`[{...}]`).
+fn parse_string(value: &str) -> Option<String> {
+    if let Some(code) = value
+        .strip_prefix("[{")
+        .and_then(|value| value.strip_suffix("}]"))
+    {
+        return Some(code.to_string());
+    }
+
+    let mut chars = value.strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut result = String::new();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next()? {
+                'n' => result.push('\n'),
+                't' => result.push('\t'),
+                c => result.push(c),
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    Some(result)
+}
+
+/// Extract the summary of the given dialect from records.
+fn parse_dialect_summary(records: &[Record], dialect_name: &str) -> Option<String> {
+    records
+        .iter()
+        .filter(|record| record.superclasses.iter().any(|c| c == "Dialect"))
+        .find(|record| {
+            record_field(record, "name")
+                .and_then(parse_string)
+                .as_deref()
+                == Some(dialect_name)
+        })
+        .and_then(|record| parse_string(record_field(record, "summary")?))
+        .filter(|summary| !summary.trim().is_empty())
+}
+
+/// Extract the summaries of the operations from records, ignoring records
+/// named in `excluded` (i.e. those pulled in from includes).
+fn parse_op_docs(records: &[Record], excluded: &[Record], dialect_name: &str) -> Vec<OpDoc> {
+    records
+        .iter()
+        .filter(|record| {
+            !record.name.starts_with("anonymous_")
+                && record.superclasses.iter().any(|c| c == "Op")
+                && !excluded.iter().any(|other| other.name == record.name)
+        })
+        .filter_map(|record| {
+            let summary = parse_string(record_field(record, "summary")?)?;
+            let op_name = parse_string(record_field(record, "opName")?)?;
+            (!summary.trim().is_empty()).then(|| OpDoc {
+                name: format!("{}.{}", dialect_name, op_name),
+                summary,
+            })
+        })
+        .collect()
+}
+
+/// Extract the fully-qualified names of the operations from records, resolving
+/// each op's `opDialect` to the dialect's `name` and ignoring records named in
+/// `excluded` (i.e. those pulled in from includes).
+fn parse_op_names(records: &[Record], excluded: &[Record]) -> Vec<String> {
+    let mut names = records
+        .iter()
+        .filter(|record| {
+            !record.name.starts_with("anonymous_")
+                && record.superclasses.iter().any(|c| c == "Op")
+                && !excluded.iter().any(|other| other.name == record.name)
+        })
+        .filter_map(|record| full_op_name(records, record))
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Extract the fully-qualified names and C++ classes (e.g.,
+/// `::mlir::bril::AddOp`) of the operations from records, ignoring records
+/// named in `excluded` (i.e. those pulled in from includes).
+fn parse_op_classes(records: &[Record], excluded: &[Record]) -> Vec<(String, String)> {
+    records
+        .iter()
+        .filter(|record| {
+            !record.name.starts_with("anonymous_")
+                && record.superclasses.iter().any(|c| c == "Op")
+                && !excluded.iter().any(|other| other.name == record.name)
+        })
+        .filter_map(|record| {
+            let name = full_op_name(records, record)?;
+            // Like mlir-tblgen, drop the dialect prefix of the def name
+            let class = match record.name.split_once('_') {
+                Some((prefix, "")) => prefix,
+                Some((_, class)) => class,
+                None => &record.name,
+            };
+            let namespace = record_field(record, "cppNamespace")
+                .and_then(parse_string)
+                .unwrap_or_default();
+            Some(if namespace.is_empty() {
+                (name, class.to_string())
+            } else {
+                (name, format!("{}::{}", namespace, class))
+            })
+        })
+        .collect()
+}
+
+/// Returns the fully-qualified name of an op record, resolving its
+/// `opDialect` to the dialect's `name`.
+fn full_op_name(records: &[Record], record: &Record) -> Option<String> {
+    let op_name = parse_string(record_field(record, "opName")?)?;
+    let dialect = record_field(record, "opDialect")?;
+    let dialect = records.iter().find(|record| record.name == dialect)?;
+    let dialect_name = parse_string(record_field(dialect, "name")?)?;
+    Some(format!("{}.{}", dialect_name, op_name))
+}
+
+/// How an argument or result of an operation is passed to its builder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArgumentKind {
+    /// A single value of a type constraint
+    Operand,
+    /// An attribute
+    Attribute,
+    /// Variadic or optional values, properties, and unknown constraints
+    Unsupported,
+}
+
+/// Classify the constraint of an op argument or result (e.g., `I64` or
+/// `anonymous_12` for `Variadic<AnyType>`), looking it up in `records`.
+fn argument_kind(records: &[Record], constraint: &str) -> ArgumentKind {
+    let record = records.iter().find(|record| record.name == constraint);
+    let is = |class: &str| {
+        record.is_some_and(|record| record.superclasses.iter().any(|c| c == class))
+            || constraint.starts_with(&format!("{class}<"))
+    };
+
+    if ["Variadic", "Optional", "VariadicOfVariadic"]
+        .into_iter()
+        .any(is)
+    {
+        ArgumentKind::Unsupported
+    } else if is("Arg") || is("Res") {
+        // Decorated arguments wrap the actual constraint
+        record
+            .and_then(|record| record_field(record, "constraint"))
+            .map_or(ArgumentKind::Unsupported, |constraint| {
+                argument_kind(records, constraint)
+            })
+    } else if is("TypeConstraint") {
+        ArgumentKind::Operand
+    } else if is("Attr") {
+        ArgumentKind::Attribute
+    } else {
+        ArgumentKind::Unsupported
+    }
+}
+
+/// Returns a readable name for the constraint of an op argument or result
+/// (e.g., `Variadic<AnyType>` for an anonymous `Variadic` record) and whether
+/// it takes a variable number of values.
+fn constraint_signature(records: &[Record], constraint: &str) -> (String, bool) {
+    let record = records.iter().find(|record| record.name == constraint);
+    let is =
+        |class: &str| record.is_some_and(|record| record.superclasses.iter().any(|c| c == class));
+
+    if (is("Arg") || is("Res"))
+        && let Some(constraint) = record.and_then(|record| record_field(record, "constraint"))
+    {
+        return constraint_signature(records, constraint);
+    }
+
+    for wrapper in ["VariadicOfVariadic", "Variadic", "Optional"] {
+        if is(wrapper) {
+            let base = record
+                .and_then(|record| record_field(record, "baseType"))
+                .map_or_else(
+                    || constraint.to_string(),
+                    |base| format!("{}<{}>", wrapper, constraint_signature(records, base).0),
+                );
+            return (base, true);
+        } else if constraint.starts_with(&format!("{wrapper}<")) {
+            return (constraint.to_string(), true);
+        }
+    }
+
+    // Anonymous constraints such as `AnyTypeOf<[I32, I64]>` are only named by
+    // their summary
+    let name = record
+        .filter(|record| record.name.starts_with("anonymous_"))
+        .and_then(|record| parse_string(record_field(record, "summary")?))
+        .unwrap_or_else(|| constraint.to_string());
+    (name, false)
+}
+
+/// Split the arguments of a dag value such as `(ins I64:$lhs, I64:$rhs)` into
+/// constraints and names (empty for unnamed arguments).
+fn dag_arguments(value: &str) -> Vec<(&str, &str)> {
+    let inner = value
+        .strip_prefix('(')
+        .and_then(|value| value.strip_suffix(')'))
+        .unwrap_or_default();
+    let Some((_, arguments)) = inner.split_once(' ') else {
+        return Vec::new();
+    };
+
+    let mut result = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, c) in arguments.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                result.push(arguments[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    result.push(arguments[start..].trim());
+
+    result
+        .into_iter()
+        .filter(|argument| !argument.is_empty())
+        .map(|argument| argument.rsplit_once(":$").unwrap_or((argument, "")))
+        .collect()
+}
+
+/// Extract the operand, attribute, and result signatures of the operations
+/// from records, ignoring records named in `excluded` (i.e. those pulled in
+/// from includes).
+fn parse_op_defs(records: &[Record], excluded: &[Record]) -> Vec<OpDef> {
+    records
+        .iter()
+        .filter(|record| {
+            !record.name.starts_with("anonymous_")
+                && record.superclasses.iter().any(|c| c == "Op")
+                && !excluded.iter().any(|other| other.name == record.name)
+        })
+        .filter_map(|record| {
+            let name = full_op_name(records, record)?;
+            let mut fixed_arity = true;
+            let mut operands = Vec::new();
+            let mut attributes = Vec::new();
+            for (index, (constraint, argument)) in
+                dag_arguments(record_field(record, "arguments").unwrap_or("(ins)"))
+                    .into_iter()
+                    .enumerate()
+            {
+                let argument = if argument.is_empty() {
+                    format!("operand{index}")
+                } else {
+                    argument.to_string()
+                };
+                match argument_kind(records, constraint) {
+                    ArgumentKind::Operand => operands.push(argument),
+                    ArgumentKind::Attribute => attributes.push(argument),
+                    ArgumentKind::Unsupported => fixed_arity = false,
+                }
+            }
+
+            let mut results = Vec::new();
+            for (constraint, result) in
+                dag_arguments(record_field(record, "results").unwrap_or("(outs)"))
+            {
+                fixed_arity &= argument_kind(records, constraint) == ArgumentKind::Operand;
+                results.push(result.to_string());
+            }
+
+            let mut regions = Vec::new();
+            for (index, (constraint, region)) in
+                dag_arguments(record_field(record, "regions").unwrap_or("(region)"))
+                    .into_iter()
+                    .enumerate()
+            {
+                fixed_arity = false;
+                let is_variadic = constraint.starts_with("VariadicRegion<")
+                    || records.iter().any(|record| {
+                        record.name == constraint
+                            && record.superclasses.iter().any(|c| c == "VariadicRegion")
+                    });
+                if is_variadic {
+                    break;
+                }
+                regions.push(if region.is_empty() {
+                    format!("region{index}")
+                } else {
+                    region.to_string()
+                });
+            }
+            fixed_arity &= record_field(record, "successors")
+                .is_none_or(|value| dag_arguments(value).is_empty());
+
+            Some(OpDef {
+                name,
+                operands,
+                attributes,
+                results,
+                regions,
+                fixed_arity,
+            })
+        })
+        .collect()
+}
+
+/// Extract the operand, result, and attribute constraints of the operations
+/// from records.
+fn parse_op_signatures(records: &[Record]) -> Vec<OpSignature> {
+    records
+        .iter()
+        .filter(|record| {
+            !record.name.starts_with("anonymous_") && record.superclasses.iter().any(|c| c == "Op")
+        })
+        .filter_map(|record| {
+            let mut signature = OpSignature {
+                name: full_op_name(records, record)?,
+                operands: Vec::new(),
+                results: Vec::new(),
+                attributes: Vec::new(),
+                variadic_operands: Vec::new(),
+                variadic_results: Vec::new(),
+            };
+
+            for (constraint, argument) in
+                dag_arguments(record_field(record, "arguments").unwrap_or("(ins)"))
+            {
+                let (name, variadic) = constraint_signature(records, constraint);
+                match argument_kind(records, constraint) {
+                    ArgumentKind::Attribute => {
+                        signature.attributes.push((argument.to_string(), name));
+                    }
+                    ArgumentKind::Unsupported if !variadic => {}
+                    _ => {
+                        if variadic {
+                            signature.variadic_operands.push(signature.operands.len());
+                        }
+                        signature.operands.push((argument.to_string(), name));
+                    }
+                }
+            }
+
+            for (constraint, result) in
+                dag_arguments(record_field(record, "results").unwrap_or("(outs)"))
+            {
+                let (name, variadic) = constraint_signature(records, constraint);
+                if variadic {
+                    signature.variadic_results.push(signature.results.len());
+                }
+                signature.results.push((result.to_string(), name));
+            }
+
+            Some(signature)
+        })
+        .collect()
+}
+
+/// Extract the integer enums from records, ignoring records named in
+/// `excluded` (i.e. those pulled in from includes).
+///
+/// Bit enums are skipped, since their values are combinations of cases.
+fn parse_enum_defs(records: &[Record], excluded: &[Record]) -> Vec<EnumDef> {
+    records
+        .iter()
+        .filter(|record| !excluded.iter().any(|other| other.name == record.name))
+        .filter_map(|record| {
+            let bitwidth = record.superclasses.iter().find_map(|class| {
+                INT_ENUM_RE
+                    .captures(class)
+                    .and_then(|captures| captures[1].parse().ok())
+            })?;
+            let class_name = record_field(record, "className")?.trim_matches('"');
+            let cases = record_field(record, "enumerants")?
+                .trim_matches(|c| c == '[' || c == ']')
+                .split(", ")
+                .filter(|name| !name.is_empty())
+                .map(|name| {
+                    let case = records.iter().find(|record| record.name == name)?;
+                    Some(EnumCase {
+                        symbol: record_field(case, "symbol")?.trim_matches('"').to_string(),
+                        value: record_field(case, "value")?.parse().ok()?,
+                    })
+                })
+                .collect::<Option<Vec<_>>>()?;
+
+            Some(EnumDef {
+                class_name: class_name.to_string(),
+                bitwidth,
+                cases,
+            })
+        })
+        .collect()
+}
+
+/// Classify records into TD file contents, ignoring anonymous records and
+/// records named in `excluded` (i.e. those pulled in from includes).
+///
+/// Rewrite patterns are commonly anonymous (`def : Pat<...>`), so they are
+/// detected by comparing the number of anonymous patterns instead.
+fn classify_records(records: &[Record], excluded: &[Record]) -> TdFileContents {
+    let mut contents = TdFileContents::default();
+
+    let anonymous_patterns = |records: &[Record]| {
+        records
+            .iter()
+            .filter(|record| {
+                record.name.starts_with("anonymous_")
+                    && record.superclasses.iter().any(|c| c == "Pattern")
+            })
+            .count()
+    };
+    contents.has_rewriters = anonymous_patterns(records) > anonymous_patterns(excluded);
+
+    for record in records {
+        if record.name.starts_with("anonymous_")
+            || excluded.iter().any(|other| other.name == record.name)
+        {
+            continue;
+        }
+
+        let is = |class: &str| record.superclasses.iter().any(|c| c == class);
+
+        contents.has_dialect |= is("Dialect");
+        contents.has_ops |= is("Op");
+        contents.has_types |= is("TypeDef");
+        contents.has_attrs |= is("AttrDef");
+        contents.has_enums |= ["EnumAttrInfo", "EnumAttr", "IntEnumAttr", "BitEnumAttr"]
+            .into_iter()
+            .any(is);
+        contents.has_op_interfaces |= is("OpInterface");
+        contents.has_type_interfaces |= is("TypeInterface");
+        contents.has_attr_interfaces |= is("AttrInterface");
+        contents.has_rewriters |= is("Pattern");
+        contents.has_passes |= is("PassBase");
+        contents.has_bytecode |= is("DialectAttributes") || is("DialectTypes");
+        contents.has_function_interface |= is("Op")
+            && record_field(record, "traits")
+                .is_some_and(|traits| traits.contains("FunctionOpInterface"));
+        contents.needs_custom_asm |= ["Op", "TypeDef", "AttrDef"].into_iter().any(is)
+            && record_field(record, "hasCustomAssemblyFormat") == Some("1");
+        contents.needs_verifier_impl |= is("Op")
+            && ["hasVerifier", "hasRegionVerifier"]
+                .into_iter()
+                .any(|field| record_field(record, field) == Some("1"));
+    }
+
+    contents
+}
+
+/// Returns the stem of a TD file, which names the files generated from it.
+///
+/// Non-UTF-8 stems are converted lossily, which is fine as long as the
+/// generated files and the includes of the registration code agree.
+pub(crate) fn td_file_stem(td_file: &Path) -> Result<String, Error> {
+    td_file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .ok_or_else(|| {
+            Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid TD file path: {}", td_file.display()),
+            ))
+        })
+}
+
+/// Returns the path of the `mlir-tblgen` binary of an LLVM installation.
+pub(crate) fn default_tblgen_path(llvm_prefix: &Path) -> PathBuf {
+    llvm_prefix.join("bin").join("mlir-tblgen")
+}
+
+/// The actions `llvm-tblgen` can run in place of `mlir-tblgen`, since they
+/// only introspect records and need none of MLIR's generators.
+const LLVM_TBLGEN_ACTIONS: &[&str] = &["--print-records", "--dump-json"];
+
+/// Executes `mlir-tblgen` with a full argument vector.
+///
+/// [`TblgenRunner`] implements this by running its binary. Tests can pass a
+/// fake to [`TblgenRunner::generate_for_file_with`] or
+/// [`TblgenRunner::records_with`] that records the invocations and writes stub
+/// outputs, so no LLVM installation is needed.
+pub trait TblgenExecutor {
+    /// Run `mlir-tblgen` with the given arguments and return its output.
+    fn run(&self, args: &[OsString]) -> Result<Output, Error>;
+}
+
+impl TblgenExecutor for TblgenRunner {
+    fn run(&self, args: &[OsString]) -> Result<Output, Error> {
+        let action = args
+            .first()
+            .map(|arg| arg.to_string_lossy())
+            .unwrap_or_default();
+        Ok(Command::new(self.tblgen_path_for(&action)?)
+            .args(args)
+            .output()?)
+    }
+}
+
+/// Runner for mlir-tblgen commands.
+pub struct TblgenRunner {
+    /// Path to the mlir-tblgen binary
+    tblgen_path: PathBuf,
+    /// Path to the llvm-tblgen binary running record introspection when
+    /// mlir-tblgen is missing
+    llvm_tblgen_path: Option<PathBuf>,
+    /// LLVM include directory
+    llvm_include: PathBuf,
+    /// Extra flags appended to every mlir-tblgen invocation
+    extra_flags: Vec<String>,
+    /// TableGen preprocessor macros defined for every invocation
+    defines: Vec<String>,
+    /// Directory for generated Markdown documentation, if enabled
+    docs_dir: Option<PathBuf>,
+    /// Directory for intermediate files (defaults to the system temp dir)
+    scratch_dir: Option<PathBuf>,
+    /// Regex selecting the ops to generate C++ for
+    op_include_regex: Option<String>,
+    /// Regex selecting the ops to skip when generating C++
+    op_exclude_regex: Option<String>,
+    /// Whether to log each invocation as a `cargo:warning`
+    verbose: bool,
+    /// Whether to print the invocations instead of running them
+    dry_run: bool,
+}
+
+impl TblgenRunner {
+    /// Create a new TblgenRunner from the LLVM prefix.
+    ///
+    /// If `tblgen_path` is given, it is used verbatim instead of
+    /// `{llvm_prefix}/bin/mlir-tblgen`.
+    pub fn new(llvm_prefix: &Path, tblgen_path: Option<&Path>) -> Result<Self, Error> {
+        let tblgen_path = tblgen_path
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| default_tblgen_path(llvm_prefix));
+
+        if !tblgen_path.exists() {
+            return Err(Error::TblgenNotFound(tblgen_path));
+        }
+
+        Ok(Self {
+            tblgen_path,
+            llvm_tblgen_path: None,
+            llvm_include: llvm_prefix.join("include"),
+            extra_flags: Vec::new(),
+            defines: Vec::new(),
+            docs_dir: None,
+            scratch_dir: None,
+            op_include_regex: None,
+            op_exclude_regex: None,
+            verbose: false,
+            dry_run: false,
+        })
+    }
+
+    /// Create a TblgenRunner like [`new`](Self::new) that falls back to
+    /// `{llvm_prefix}/bin/llvm-tblgen` for record introspection (e.g.,
+    /// `--print-records`).
+    ///
+    /// This works with a minimal LLVM installation without `mlir-tblgen`, as
+    /// long as only introspection is needed. Fails with
+    /// [`Error::TblgenNotFound`] if neither binary exists; see
+    /// [`tblgen_path_for`](Self::tblgen_path_for) for the binary used by each
+    /// action.
+    pub fn with_llvm_tblgen_fallback(
+        llvm_prefix: &Path,
+        tblgen_path: Option<&Path>,
+    ) -> Result<Self, Error> {
+        let llvm_tblgen_path = llvm_prefix.join("bin").join("llvm-tblgen");
+
+        match Self::new(llvm_prefix, tblgen_path) {
+            Ok(runner) => Ok(Self {
+                llvm_tblgen_path: llvm_tblgen_path.exists().then_some(llvm_tblgen_path),
+                ..runner
+            }),
+            Err(Error::TblgenNotFound(_)) if llvm_tblgen_path.exists() => Ok(Self {
+                llvm_tblgen_path: Some(llvm_tblgen_path),
+                dry_run: false,
+                ..Self::dry_run(llvm_prefix, tblgen_path)
+            }),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Create a TblgenRunner that prints each `mlir-tblgen` invocation as a
+    /// `cargo:warning` instead of running it.
+    ///
+    /// Unlike [`new`](Self::new), the binary does not need to exist.
+    pub fn dry_run(llvm_prefix: &Path, tblgen_path: Option<&Path>) -> Self {
+        Self {
+            tblgen_path: tblgen_path
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| default_tblgen_path(llvm_prefix)),
+            llvm_tblgen_path: None,
+            llvm_include: llvm_prefix.join("include"),
+            extra_flags: Vec::new(),
+            defines: Vec::new(),
+            docs_dir: None,
+            scratch_dir: None,
+            op_include_regex: None,
+            op_exclude_regex: None,
+            verbose: false,
+            dry_run: true,
+        }
+    }
+
+    /// Append extra flags to every mlir-tblgen invocation.
+    ///
+    /// The flags are passed after all other arguments, so they can override
+    /// the defaults chosen by the runner.
+    pub fn extra_flags<S: Into<String>>(mut self, flags: impl IntoIterator<Item = S>) -> Self {
+        self.extra_flags.extend(flags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Also generate Markdown documentation into the given directory.
+    ///
+    /// Files defining a dialect get `{stem}Dialect.md` from `-gen-dialect-doc`,
+    /// and files defining ops get `{stem}.md` from `-gen-op-doc`.
+    pub fn docs_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.docs_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Write intermediate files, such as the include stubs used to detect
+    /// contents from records, into the given directory instead of the system
+    /// temp directory.
+    pub fn scratch_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.scratch_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Define TableGen preprocessor macros (`-D{name}`) for every
+    /// mlir-tblgen invocation, for `#ifdef`s in the TD files.
+    pub fn defines<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.defines.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Restrict the generated op declarations and definitions with
+    /// `mlir-tblgen`'s `--op-include-regex` and `--op-exclude-regex`.
+    pub fn op_filter(mut self, include: Option<&str>, exclude: Option<&str>) -> Self {
+        self.op_include_regex = include.map(String::from);
+        self.op_exclude_regex = exclude.map(String::from);
+        self
+    }
+
+    /// Returns the path of the mlir-tblgen binary.
+    pub fn tblgen_path(&self) -> &Path {
+        &self.tblgen_path
+    }
+
+    /// Returns the binary that runs the given action (e.g., `--print-records`
+    /// or `-gen-op-decls`).
+    ///
+    /// This is `mlir-tblgen`, unless it is missing and the runner was created
+    /// with [`with_llvm_tblgen_fallback`](Self::with_llvm_tblgen_fallback), in
+    /// which case `llvm-tblgen` runs the actions it supports. Fails with
+    /// [`Error::TblgenNotFound`] if no binary can run the action.
+    pub fn tblgen_path_for(&self, action: &str) -> Result<&Path, Error> {
+        if self.dry_run || self.tblgen_path.exists() {
+            return Ok(&self.tblgen_path);
+        }
+
+        match &self.llvm_tblgen_path {
+            Some(path) if LLVM_TBLGEN_ACTIONS.contains(&action) => Ok(path),
+            _ => Err(Error::TblgenNotFound(self.tblgen_path.clone())),
+        }
+    }
+
+    /// Log each mlir-tblgen command line as a `cargo:warning`.
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Generate .inc files for a TD file based on its detected contents.
+    ///
+    /// Output file names are based on the TD file stem (e.g., `BrilOps.td` produces
+    /// `BrilOpsDialect.h.inc`, `BrilOps.h.inc`, etc.), matching MLIR convention.
+    ///
+    /// Each generated file gets a sidecar `.stamp` file holding a hash of its
+    /// inputs; invocations whose inputs are unchanged are skipped.
+    pub fn generate_for_file(
+        &self,
+        td_file: &Path,
+        include_dirs: &[PathBuf],
+        output_dir: &Path,
+        dialect_name: &str,
+        contents: &TdFileContents,
+    ) -> Result<(), Error> {
+        self.generate_for_file_with(
+            self,
+            td_file,
+            include_dirs,
+            output_dir,
+            dialect_name,
+            contents,
+        )
+    }
+
+    /// Generate .inc files for a TD file like
+    /// [`generate_for_file`](Self::generate_for_file), running each
+    /// invocation through the given executor.
+    pub fn generate_for_file_with(
+        &self,
+        executor: &dyn TblgenExecutor,
+        td_file: &Path,
+        include_dirs: &[PathBuf],
+        output_dir: &Path,
+        dialect_name: &str,
+        contents: &TdFileContents,
+    ) -> Result<(), Error> {
+        // Use TD file stem for output naming (MLIR convention)
+        let stem = td_file_stem(td_file)?;
+
+        if contents.has_dialect {
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}Dialect.h.inc", stem)),
+                "-gen-dialect-decls",
+                Some(dialect_name),
+            )?;
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}Dialect.cpp.inc", stem)),
+                "-gen-dialect-defs",
+                Some(dialect_name),
+            )?;
+        }
+
+        if contents.has_ops {
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}.h.inc", stem)),
+                "-gen-op-decls",
+                Some(dialect_name),
+            )?;
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}.cpp.inc", stem)),
+                "-gen-op-defs",
+                Some(dialect_name),
+            )?;
+        }
+
+        if contents.has_types {
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}Types.h.inc", stem)),
+                "-gen-typedef-decls",
+                Some(dialect_name),
+            )?;
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}Types.cpp.inc", stem)),
+                "-gen-typedef-defs",
+                Some(dialect_name),
+            )?;
+        }
+
+        if contents.has_attrs {
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}Attrs.h.inc", stem)),
+                "-gen-attrdef-decls",
+                Some(dialect_name),
+            )?;
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}Attrs.cpp.inc", stem)),
+                "-gen-attrdef-defs",
+                Some(dialect_name),
+            )?;
+        }
+
+        if contents.has_enums {
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}Enums.h.inc", stem)),
+                "-gen-enum-decls",
+                Some(dialect_name),
+            )?;
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}Enums.cpp.inc", stem)),
+                "-gen-enum-defs",
+                Some(dialect_name),
+            )?;
+        }
+
+        if contents.has_op_interfaces {
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}OpInterfaces.h.inc", stem)),
+                "-gen-op-interface-decls",
+                None,
+            )?;
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}OpInterfaces.cpp.inc", stem)),
+                "-gen-op-interface-defs",
+                None,
+            )?;
+        }
+
+        if contents.has_type_interfaces {
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}TypeInterfaces.h.inc", stem)),
+                "-gen-type-interface-decls",
+                None,
+            )?;
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}TypeInterfaces.cpp.inc", stem)),
+                "-gen-type-interface-defs",
+                None,
+            )?;
+        }
+
+        if contents.has_attr_interfaces {
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}AttrInterfaces.h.inc", stem)),
+                "-gen-attr-interface-decls",
+                None,
+            )?;
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}AttrInterfaces.cpp.inc", stem)),
+                "-gen-attr-interface-defs",
+                None,
+            )?;
+        }
+
+        if contents.has_rewriters {
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}Rewrites.inc", stem)),
+                "-gen-rewriters",
+                None,
+            )?;
+        }
+
+        if contents.has_passes {
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}Passes.h.inc", stem)),
+                "-gen-pass-decls",
+                None,
+            )?;
+        }
+
+        if contents.has_bytecode {
+            // Encodings expanded from records fall back to the class name
+            let bytecode_dialect = bytecode_dialect(td_file)?
+                .unwrap_or_else(|| crate::to_class_name(dialect_name, &[]));
+            self.run_tblgen(
+                executor,
+                td_file,
+                include_dirs,
+                &output_dir.join(format!("{}Bytecode.cpp.inc", stem)),
+                "-gen-bytecode",
+                Some(&bytecode_dialect),
+            )?;
+        }
+
+        if let Some(docs_dir) = &self.docs_dir {
+            if contents.has_dialect {
+                self.run_tblgen(
+                    executor,
+                    td_file,
+                    include_dirs,
+                    &docs_dir.join(format!("{}Dialect.md", stem)),
+                    "-gen-dialect-doc",
+                    Some(dialect_name),
+                )?;
+            }
+
+            if contents.has_ops {
+                self.run_tblgen(
+                    executor,
+                    td_file,
+                    include_dirs,
+                    &docs_dir.join(format!("{}.md", stem)),
+                    "-gen-op-doc",
+                    Some(dialect_name),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Detect what definitions a TableGen file contains from the records
+    /// `mlir-tblgen` produces for it.
+    ///
+    /// Records that are already defined by the file's own `include`s are
+    /// excluded, so only definitions made by the file itself (including those
+    /// expanded from `foreach` or `multiclass`) are counted.
+    pub fn detect_td_contents_from_records(
+        &self,
+        td_file: &Path,
+        include_dirs: &[PathBuf],
+    ) -> Result<TdFileContents, Error> {
+        Ok(self.records(td_file, include_dirs)?.contents())
+    }
+
+    /// Returns the records of a TableGen file, along with the records already
+    /// defined by the file's own `include`s.
+    ///
+    /// The `--print-records` output is cached in the scratch directory next to
+    /// a `.stamp` file like the `.inc` files, so later calls for an unchanged
+    /// file do not run `mlir-tblgen` again.
+    pub fn records(&self, td_file: &Path, include_dirs: &[PathBuf]) -> Result<TdRecords, Error> {
+        self.records_with(self, td_file, include_dirs)
+    }
+
+    /// Returns the records of a TableGen file like
+    /// [`records`](Self::records), running `--print-records` through the given
+    /// executor.
+    pub fn records_with(
+        &self,
+        executor: &dyn TblgenExecutor,
+        td_file: &Path,
+        include_dirs: &[PathBuf],
+    ) -> Result<TdRecords, Error> {
+        let content = strip_comments(&read_td_file(td_file)?);
+
+        // Resolve the file's includes from its own directory, as tblgen would
+        let mut include_dirs = include_dirs.to_vec();
+        if let Some(parent) = td_file.parent() {
+            include_dirs.push(parent.to_path_buf());
+        }
+
+        let scratch_dir = self.scratch_dir.clone().unwrap_or_else(std::env::temp_dir);
+        // Files with the same stem may live in different directories
+        let mut path_hasher = DefaultHasher::new();
+        td_file.hash(&mut path_hasher);
+        let cache_stem = format!(
+            "melior_build_{}_{:08x}",
+            td_file.file_stem().unwrap_or_default().to_string_lossy(),
+            path_hasher.finish() as u32
+        );
+
+        let includes_file =
+            scratch_dir.join(format!("{}_{}_includes.td", cache_stem, std::process::id()));
+        let includes = INCLUDE_RE
+            .find_iter(&content)
+            .map(|m| m.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        fs::write(&includes_file, includes)?;
+
+        let included = self.cached_records(
+            executor,
+            &includes_file,
+            &include_dirs,
+            &scratch_dir.join(format!("{}_included.records", cache_stem)),
+        );
+        fs::remove_file(&includes_file).ok();
+
+        Ok(TdRecords {
+            records: self.cached_records(
+                executor,
+                td_file,
+                &include_dirs,
+                &scratch_dir.join(format!("{}.records", cache_stem)),
+            )?,
+            included: included?,
+        })
+    }
+
+    /// Returns the parsed `--print-records` output for a TableGen file, reading
+    /// it from `cache` if the file and its includes are unchanged.
+    fn cached_records(
+        &self,
+        executor: &dyn TblgenExecutor,
+        td_file: &Path,
+        include_dirs: &[PathBuf],
+        cache: &Path,
+    ) -> Result<Vec<Record>, Error> {
+        let hash = self.input_hash(td_file, include_dirs, "--print-records", None)?;
+        let stamp = stamp_path(cache);
+        if is_up_to_date(cache, &stamp, &hash) {
+            return Ok(parse_records(&fs::read_to_string(cache)?));
+        }
+
+        let output = self.print_records(executor, td_file, include_dirs)?;
+        fs::write(cache, &output)?;
+        fs::write(&stamp, hash)?;
+
+        Ok(parse_records(&output))
+    }
+
+    /// Build the error for a failed invocation of `cmd` on `td_file`.
+    fn failed(cmd: &Command, td_file: &Path, action: &str, output: &Output) -> Error {
+        Error::TblgenFailed {
+            file: td_file.to_path_buf(),
+            action: action.to_string(),
+            tblgen: PathBuf::from(cmd.get_program()),
+            args: command_args(cmd),
+            diagnostics: diagnostics(
+                &String::from_utf8_lossy(&output.stdout),
+                &String::from_utf8_lossy(&output.stderr),
+                output.status.code(),
+            ),
+        }
+    }
+
+    fn print_records(
+        &self,
+        executor: &dyn TblgenExecutor,
+        td_file: &Path,
+        include_dirs: &[PathBuf],
+    ) -> Result<String, Error> {
+        // A missing binary is reported by the executor
+        let tblgen_path = self
+            .tblgen_path_for("--print-records")
+            .unwrap_or(&self.tblgen_path);
+        let mut cmd = Command::new(tblgen_path);
+        cmd.arg("--print-records").arg(td_file);
+        cmd.arg("-I").arg(&self.llvm_include);
+        for include_dir in include_dirs {
+            cmd.arg("-I").arg(include_dir);
+        }
+        for name in &self.defines {
+            cmd.arg(format!("-D{}", name));
+        }
+
+        self.log_command(&cmd);
+        let args = cmd
+            .get_args()
+            .map(|arg| arg.to_os_string())
+            .collect::<Vec<_>>();
+        let output = executor.run(&args)?;
+
+        if !output.status.success() {
+            return Err(Self::failed(&cmd, td_file, "--print-records", &output));
+        }
+
+        Ok(String::from_utf8(output.stdout)?)
+    }
+
+    fn command(
+        &self,
+        td_file: &Path,
+        include_dirs: &[PathBuf],
+        output: &Path,
+        action: &str,
+        dialect: Option<&str>,
+    ) -> Command {
+        let mut cmd = Command::new(&self.tblgen_path);
+        cmd.arg(action).arg(td_file).arg("-o").arg(output);
+        cmd.arg("-I").arg(&self.llvm_include);
+        for include_dir in include_dirs {
+            cmd.arg("-I").arg(include_dir);
+        }
+        match dialect {
+            Some(dialect_name) if action == "-gen-bytecode" => {
+                cmd.arg(format!("-bytecode-dialect={}", dialect_name));
+            }
+            Some(dialect_name) => {
+                cmd.arg(format!("--dialect={}", dialect_name));
+            }
+            None => {}
+        }
+        for name in &self.defines {
+            cmd.arg(format!("-D{}", name));
+        }
+        if matches!(action, "-gen-op-decls" | "-gen-op-defs") {
+            if let Some(regex) = &self.op_include_regex {
+                cmd.arg(format!("--op-include-regex={}", regex));
+            }
+            if let Some(regex) = &self.op_exclude_regex {
+                cmd.arg(format!("--op-exclude-regex={}", regex));
+            }
+        }
+        // User flags go last so they can override the defaults above
+        cmd.args(&self.extra_flags);
+        cmd
+    }
+
+    /// Hashes everything that affects the output of a single invocation: the
+    /// content of the TD file and its transitive includes, the resolved include
+    /// set, and the command line.
+    fn input_hash(
+        &self,
+        td_file: &Path,
+        include_dirs: &[PathBuf],
+        action: &str,
+        dialect: Option<&str>,
+    ) -> Result<String, Error> {
+        let mut hasher = DefaultHasher::new();
+        fs::read(td_file)?.hash(&mut hasher);
+        for include in collect_includes(td_file, include_dirs)? {
+            fs::read(include)?.hash(&mut hasher);
+        }
+        let tblgen_path = self.tblgen_path_for(action).unwrap_or(&self.tblgen_path);
+        tblgen_path.hash(&mut hasher);
+        // Replacing the binary in place, e.g., by upgrading LLVM under the same
+        // prefix, must invalidate the outputs of the old one
+        if let Ok(metadata) = fs::metadata(tblgen_path) {
+            metadata.len().hash(&mut hasher);
+            metadata.modified().ok().hash(&mut hasher);
+        }
+        self.llvm_include.hash(&mut hasher);
+        include_dirs.hash(&mut hasher);
+        action.hash(&mut hasher);
+        dialect.hash(&mut hasher);
+        self.extra_flags.hash(&mut hasher);
+        self.defines.hash(&mut hasher);
+        self.op_include_regex.hash(&mut hasher);
+        self.op_exclude_regex.hash(&mut hasher);
+        Ok(format!("{:016x}", hasher.finish()))
+    }
+
+    fn log_command(&self, cmd: &Command) {
+        if self.verbose {
+            println!(
+                "cargo:warning=melior-build: running {}",
+                command_line(Path::new(cmd.get_program()), &command_args(cmd))
+            );
+        }
+    }
+
+    fn run_tblgen(
+        &self,
+        executor: &dyn TblgenExecutor,
+        td_file: &Path,
+        include_dirs: &[PathBuf],
+        output: &Path,
+        action: &str,
+        dialect: Option<&str>,
+    ) -> Result<(), Error> {
+        if self.dry_run {
+            let cmd = self.command(td_file, include_dirs, output, action, dialect);
+            println!(
+                "cargo:warning=melior-build: would run {}",
+                command_line(Path::new(cmd.get_program()), &command_args(&cmd))
+            );
+            return Ok(());
+        }
+
+        // Skip the invocation if the output is up to date with its inputs
+        let stamp = stamp_path(output);
+        let hash = self.input_hash(td_file, include_dirs, action, dialect)?;
+        if is_up_to_date(output, &stamp, &hash) {
+            if self.verbose {
+                println!(
+                    "cargo:warning=melior-build: {} is up to date",
+                    output.display()
+                );
+            }
+            return Ok(());
+        }
+
+        let cmd = self.command(td_file, include_dirs, output, action, dialect);
+        self.log_command(&cmd);
+        let args = cmd
+            .get_args()
+            .map(|arg| arg.to_os_string())
+            .collect::<Vec<_>>();
+        let output_result = executor.run(&args)?;
+
+        if !output_result.status.success() {
+            return Err(Self::failed(&cmd, td_file, action, &output_result));
+        }
+
+        fs::write(&stamp, hash)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the path of the `.stamp` file holding the input hash of `output`.
+fn stamp_path(output: &Path) -> PathBuf {
+    let mut stamp = output.as_os_str().to_owned();
+    stamp.push(".stamp");
+    PathBuf::from(stamp)
+}
+
+/// Whether `output` exists and was generated from inputs with the given hash.
+fn is_up_to_date(output: &Path, stamp: &Path, hash: &str) -> bool {
+    output.exists() && fs::read_to_string(stamp).is_ok_and(|existing| existing == hash)
+}
+
+fn command_args(cmd: &Command) -> Vec<String> {
+    cmd.get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::fake_tblgen::{FakeExecutor, FakeTblgen};
+    use crate::to_class_name;
+    use std::io::Write;
+
+    #[test]
+    fn test_to_class_name() {
+        assert_eq!(to_class_name("toy", &[]), "Toy");
+        assert_eq!(to_class_name("math_ext", &[]), "MathExt");
+        assert_eq!(to_class_name("my_custom_dialect", &[]), "MyCustomDialect");
+        assert_eq!(to_class_name("", &[]), "");
+    }
+
+    #[test]
+    fn test_to_class_name_acronyms() {
+        let acronyms = ["LLVM", "IR", "GPU"].map(String::from);
+        assert_eq!(to_class_name("llvm_ir", &acronyms), "LLVMIR");
+        assert_eq!(to_class_name("gpu_ext", &acronyms), "GPUExt");
+        assert_eq!(to_class_name("llvm_ir", &[]), "LlvmIr");
+    }
+
+    #[test]
+    fn test_to_class_name_digits() {
+        assert_eq!(to_class_name("v2_ops", &[]), "V2Ops");
+        assert_eq!(to_class_name("conv_2d", &[]), "Conv2d");
+        assert_eq!(to_class_name("ops_32", &[]), "Ops32");
+    }
+
+    #[test]
+    fn test_detect_dialect() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_dialect.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+def Bril_Dialect : Dialect {{
+    let name = "bril";
+}}
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_dialect);
+        assert!(!contents.has_ops);
+        assert!(!contents.has_types);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_detect_ops() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_ops.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+def Bril_AddOp : Bril_Op<"add", [Pure]> {{
+    let arguments = (ins I64:$lhs, I64:$rhs);
+    let results = (outs I64);
+}}
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(!contents.has_dialect);
+        assert!(
+            contents.has_ops,
+            "Should detect ops using custom _Op< base class"
+        );
+        assert!(!contents.has_types);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_detect_generated_ops() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_detect_generated_ops.td");
+
+        let detect = |content: &str| {
+            std::fs::write(&path, content).unwrap();
+            detect_td_contents(&path).unwrap().has_ops
+        };
+
+        assert!(detect(
+            r#"
+foreach name = ["add", "sub"] in {
+  def Bril_ # name # Op : Bril_Op <name, [Pure]> {
+    let arguments = (ins I64:$lhs, I64:$rhs);
+  }
+}
+"#
+        ));
+        assert!(detect(
+            r#"
+multiclass BinaryOp<string mnemonic> {
+  def NAME # Op : Bril_Op<mnemonic> {
+    let description = [{ Braces { in code } blocks are ignored. }];
+  }
+}
+defm Bril_Add : BinaryOp<"add">;
+"#
+        ));
+        // A multiclass that is never instantiated defines nothing
+        assert!(!detect(
+            r#"
+multiclass BinaryOp<string mnemonic> {
+  def NAME # Op : Bril_Op<mnemonic>;
+}
+def Bril_PtrType : TypeDef<Bril_Dialect, "Ptr">;
+"#
+        ));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_detect_types() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_types.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+def Bril_PtrType : Bril_Type<"Ptr", "ptr"> {{
+    let mnemonic = "ptr";
+}}
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(!contents.has_dialect);
+        assert!(!contents.has_ops);
+        assert!(
+            contents.has_types,
+            "Should detect types using custom _Type< base class"
+        );
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_detect_combined() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_combined.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+def Bril_Dialect : Dialect {{
+    let name = "bril";
+}}
+
+def Bril_PtrType : Bril_Type<"Ptr", "ptr"> {{
+    let mnemonic = "ptr";
+}}
+
+def Bril_AddOp : Bril_Op<"add", [Pure]> {{
+    let arguments = (ins I64:$lhs, I64:$rhs);
+    let results = (outs I64);
+}}
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_dialect);
+        assert!(contents.has_ops);
+        assert!(contents.has_types);
+        assert!(contents.has_any());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_detect_direct_op() {
+        // Test detection of direct Op< usage (not via custom base class)
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_direct_op.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+def MyOp : Op<MyDialect, "my_op"> {{
+    let results = (outs I64);
+}}
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_ops, "Should detect direct Op< usage");
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_detect_function_interface() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_func_interface.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+include "mlir/Interfaces/FunctionInterfaces.td"
+
+def Bril_FuncOp : Bril_Op<"func", [
+    FunctionOpInterface,
+    IsolatedFromAbove
+]> {{
+    let arguments = (ins);
+}}
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(
+            contents.has_function_interface,
+            "Should detect FunctionOpInterface usage"
+        );
+        assert!(contents.has_ops, "Should also detect ops");
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_detect_function_interface_let_traits() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_func_interface_let_traits.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+def Bril_FuncOp : Bril_Op<"func"> {{
+    let traits = [FunctionOpInterface, IsolatedFromAbove];
+}}
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_function_interface);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_function_interface_in_comment_not_detected() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_func_interface_comment.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+include "mlir/Interfaces/FunctionInterfaces.td"
+
+// TODO: make this a FunctionOpInterface
+def Bril_CallOp : Bril_Op<"call", [Pure]> {{
+    let description = "Calls an op implementing FunctionOpInterface";
+}}
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(
+            !contents.has_function_interface,
+            "Should NOT detect FunctionOpInterface outside of a trait list"
+        );
+        assert!(contents.has_ops);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_detect_no_function_interface() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_no_func_interface.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+def Bril_AddOp : Bril_Op<"add", [Pure]> {{
+    let arguments = (ins I64:$lhs, I64:$rhs);
+    let results = (outs I64);
+}}
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(
+            !contents.has_function_interface,
+            "Should NOT detect FunctionOpInterface when not present"
+        );
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_class_definitions_not_detected_as_ops() {
+        // Test that base class definitions (using `class`) are NOT detected as ops.
+        // Only actual op definitions (using `def`) should count.
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_class_not_op.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+def Bril_Dialect : Dialect {{
+    let name = "bril";
+}}
+
+// This is a base class definition, NOT an op
+class Bril_Op<string mnemonic, list<Trait> traits = []> :
+    Op<Bril_Dialect, mnemonic, traits>;
+
+// Similarly, base class for types should not count
+class Bril_Type<string name, string typeMnemonic, list<Trait> traits = []> :
+    TypeDef<Bril_Dialect, name, traits>;
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_dialect, "Should detect dialect");
+        assert!(
+            !contents.has_ops,
+            "Should NOT detect ops from class definitions"
+        );
+        assert!(
+            !contents.has_types,
+            "Should NOT detect types from class definitions"
+        );
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_runner_explicit_tblgen_path() {
+        let temp_dir = std::env::temp_dir();
+        let tblgen_path = temp_dir.join("test_explicit_mlir-tblgen");
+        std::fs::File::create(&tblgen_path).unwrap();
+
+        let runner = TblgenRunner::new(Path::new("/nonexistent/llvm"), Some(&tblgen_path)).unwrap();
+        assert_eq!(runner.tblgen_path, tblgen_path);
+        assert_eq!(runner.llvm_include, Path::new("/nonexistent/llvm/include"));
+
+        std::fs::remove_file(&tblgen_path).ok();
+    }
+
+    #[test]
+    fn test_runner_explicit_tblgen_path_missing() {
+        let tblgen_path = std::env::temp_dir().join("test_missing_mlir-tblgen");
+
+        let err = TblgenRunner::new(Path::new("/nonexistent/llvm"), Some(&tblgen_path))
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::TblgenNotFound(path) if path == tblgen_path));
+    }
+
+    #[test]
+    fn test_runner_llvm_tblgen_fallback() {
+        let dir = std::env::temp_dir().join("test_runner_llvm_tblgen_fallback");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(dir.join("bin")).unwrap();
+        let mlir_tblgen = dir.join("bin").join("mlir-tblgen");
+        let llvm_tblgen = dir.join("bin").join("llvm-tblgen");
+
+        let err = TblgenRunner::with_llvm_tblgen_fallback(&dir, None)
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::TblgenNotFound(path) if path == mlir_tblgen));
+
+        // Only llvm-tblgen: introspection works, generators do not
+        std::fs::File::create(&llvm_tblgen).unwrap();
+        assert!(TblgenRunner::new(&dir, None).is_err());
+        let runner = TblgenRunner::with_llvm_tblgen_fallback(&dir, None).unwrap();
+        assert_eq!(
+            runner.tblgen_path_for("--print-records").unwrap(),
+            llvm_tblgen
+        );
+        assert!(matches!(
+            runner.tblgen_path_for("-gen-op-decls"),
+            Err(Error::TblgenNotFound(path)) if path == mlir_tblgen
+        ));
+
+        // Both: mlir-tblgen runs everything
+        std::fs::File::create(&mlir_tblgen).unwrap();
+        let runner = TblgenRunner::with_llvm_tblgen_fallback(&dir, None).unwrap();
+        assert_eq!(
+            runner.tblgen_path_for("--print-records").unwrap(),
+            mlir_tblgen
+        );
+        assert_eq!(
+            runner.tblgen_path_for("-gen-op-decls").unwrap(),
+            mlir_tblgen
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    /// Returns a runner whose invocations all go through an executor.
+    #[cfg(unix)]
+    fn fake_runner() -> TblgenRunner {
+        TblgenRunner {
+            dry_run: false,
+            ..TblgenRunner::dry_run(Path::new("/opt/llvm"), None)
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_generate_for_file_with_fake_executor() {
+        let dir = std::env::temp_dir().join("test_fake_executor");
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let td_path = dir.join("BrilOps.td");
+        fs::write(&td_path, "").unwrap();
+
+        let contents = TdFileContents {
+            has_ops: true,
+            has_types: true,
+            ..Default::default()
+        };
+        let runner = fake_runner();
+        let executor = FakeExecutor::default();
+        runner
+            .generate_for_file_with(&executor, &td_path, &[], &dir, "bril", &contents)
+            .unwrap();
+
+        let invocations = executor.invocations.borrow();
+        let actions = invocations
+            .iter()
+            .map(|args| args[0].to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            [
+                "-gen-op-decls",
+                "-gen-op-defs",
+                "-gen-typedef-decls",
+                "-gen-typedef-defs"
+            ]
+        );
+        assert!(
+            invocations
+                .iter()
+                .all(|args| args.contains(&OsString::from("--dialect=bril")))
+        );
+        for name in [
+            "BrilOps.h.inc",
+            "BrilOps.cpp.inc",
+            "BrilOpsTypes.h.inc",
+            "BrilOpsTypes.cpp.inc",
+        ] {
+            assert!(dir.join(name).exists(), "{name}");
+        }
+        drop(invocations);
+
+        // The stamps written for the fake outputs make a second run a no-op
+        runner
+            .generate_for_file_with(&executor, &td_path, &[], &dir, "bril", &contents)
+            .unwrap();
+        assert_eq!(executor.invocations.borrow().len(), 4);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_command_include_dir_with_space() {
+        let runner = TblgenRunner::dry_run(Path::new("/opt/llvm"), None);
+        let args = command_args(&runner.command(
+            Path::new("/src/my dialect/Ops.td"),
+            &[PathBuf::from("/src/my dialect/include")],
+            Path::new("/out dir/Ops.h.inc"),
+            "-gen-op-decls",
+            None,
+        ));
+
+        assert_eq!(args[1], "/src/my dialect/Ops.td");
+        assert_eq!(args[3], "/out dir/Ops.h.inc");
+        assert_eq!(args[6..], ["-I", "/src/my dialect/include"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_td_file_stem_non_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let td_file = Path::new(std::ffi::OsStr::from_bytes(b"/src/Bril\xffOps.td"));
+        assert_eq!(td_file_stem(td_file).unwrap(), "Bril\u{fffd}Ops");
+        assert_eq!(
+            td_file_stem(Path::new("/src/BrilOps.td")).unwrap(),
+            "BrilOps"
+        );
+        assert!(td_file_stem(Path::new("/")).is_err());
+    }
+
+    #[test]
+    fn test_command_defines() {
+        let runner = TblgenRunner::dry_run(Path::new("/opt/llvm"), None)
+            .defines(["BRIL_EXPERIMENTAL", "BRIL_DEBUG"])
+            .extra_flags(["-dialect-specific"]);
+        let args = command_args(&runner.command(
+            Path::new("Ops.td"),
+            &[],
+            Path::new("Ops.cpp.inc"),
+            "-gen-op-defs",
+            Some("bril"),
+        ));
+
+        assert_eq!(
+            args[args.len() - 3..],
+            ["-DBRIL_EXPERIMENTAL", "-DBRIL_DEBUG", "-dialect-specific"]
+        );
+    }
+
+    #[test]
+    fn test_command_op_filter() {
+        let runner = TblgenRunner::dry_run(Path::new("/opt/llvm"), None)
+            .op_filter(Some(r"bril\.(add|sub)"), Some(r"bril\.sub"));
+        let args = |action| {
+            command_args(&runner.command(
+                Path::new("Ops.td"),
+                &[],
+                Path::new("Ops.cpp.inc"),
+                action,
+                Some("bril"),
+            ))
+        };
+
+        let op_defs = args("-gen-op-defs");
+        assert!(op_defs.contains(&r"--op-include-regex=bril\.(add|sub)".to_string()));
+        assert!(op_defs.contains(&r"--op-exclude-regex=bril\.sub".to_string()));
+        assert!(args("-gen-op-decls").contains(&r"--op-include-regex=bril\.(add|sub)".to_string()));
+        assert!(
+            !args("-gen-typedef-defs")
+                .iter()
+                .any(|arg| arg.starts_with("--op-"))
+        );
+    }
+
+    #[test]
+    fn test_command_extra_flags_last() {
+        let temp_dir = std::env::temp_dir();
+        let tblgen_path = temp_dir.join("test_extra_flags_mlir-tblgen");
+        std::fs::File::create(&tblgen_path).unwrap();
+
+        let runner = TblgenRunner::new(Path::new("/opt/llvm"), Some(&tblgen_path))
+            .unwrap()
+            .extra_flags(["--asmformat-error-is-fatal=false", "-dialect-specific"]);
+        let cmd = runner.command(
+            Path::new("Ops.td"),
+            &[PathBuf::from("include")],
+            Path::new("Ops.h.inc"),
+            "-gen-op-decls",
+            Some("test"),
+        );
+        let args: Vec<_> = cmd.get_args().map(|arg| arg.to_str().unwrap()).collect();
+
+        assert_eq!(
+            args,
+            [
+                "-gen-op-decls",
+                "Ops.td",
+                "-o",
+                "Ops.h.inc",
+                "-I",
+                "/opt/llvm/include",
+                "-I",
+                "include",
+                "--dialect=test",
+                "--asmformat-error-is-fatal=false",
+                "-dialect-specific",
+            ]
+        );
+
+        std::fs::remove_file(&tblgen_path).ok();
+    }
+
+    #[test]
+    fn test_detect_op_interfaces() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_op_interfaces.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+include "mlir/IR/OpBase.td"
+
+def MyInterface : OpInterface<"MyInterface"> {{
+    let cppNamespace = "::mlir::my";
+}}
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_op_interfaces, "Should detect OpInterface");
+        assert!(!contents.has_ops, "OpInterface is not an op");
+        assert!(contents.has_any());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_detect_no_op_interfaces() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_no_op_interfaces.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+def Bril_AddOp : Bril_Op<"add", [Pure]> {{
+    let arguments = (ins I64:$lhs, I64:$rhs);
+}}
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(!contents.has_op_interfaces);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_detect_type_interfaces() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_type_interfaces.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+include "mlir/IR/OpBase.td"
+
+def MyTypeInterface : TypeInterface<"MyTypeInterface"> {{
+    let cppNamespace = "::mlir::my";
+}}
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_type_interfaces, "Should detect TypeInterface");
+        assert!(!contents.has_op_interfaces);
+        assert!(!contents.has_types, "TypeInterface is not a type");
+        assert!(contents.has_any());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_generate_type_interfaces() {
+        let dir = std::env::temp_dir().join("test_tblgen_type_interfaces");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let td_path = dir.join("MyInterfaces.td");
+        std::fs::write(
+            &td_path,
+            "def MyTypeInterface : TypeInterface<\"MyTypeInterface\">;\n",
+        )
+        .unwrap();
+        let contents = detect_td_contents(&td_path).unwrap();
+
+        let executor = FakeExecutor::default();
+        fake_runner()
+            .generate_for_file_with(&executor, &td_path, &[], &dir, "my", &contents)
+            .unwrap();
+
+        assert_eq!(
+            executor.actions(),
+            ["-gen-type-interface-decls", "-gen-type-interface-defs"]
+        );
+        assert!(dir.join("MyInterfacesTypeInterfaces.h.inc").exists());
+        assert!(dir.join("MyInterfacesTypeInterfaces.cpp.inc").exists());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_detect_attr_interfaces() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_attr_interfaces.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+include "mlir/IR/OpBase.td"
+
+def MyAttrInterface : AttrInterface<"MyAttrInterface"> {{
+    let cppNamespace = "::mlir::my";
+}}
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_attr_interfaces, "Should detect AttrInterface");
+        assert!(!contents.has_type_interfaces);
+        assert!(!contents.has_attrs, "AttrInterface is not an attribute");
+        assert!(contents.has_any());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_generate_attr_interfaces() {
+        let dir = std::env::temp_dir().join("test_tblgen_attr_interfaces");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let td_path = dir.join("MyInterfaces.td");
+        std::fs::write(
+            &td_path,
+            "def MyAttrInterface : AttrInterface<\"MyAttrInterface\">;\n",
+        )
+        .unwrap();
+        let contents = detect_td_contents(&td_path).unwrap();
+
+        let executor = FakeExecutor::default();
+        fake_runner()
+            .generate_for_file_with(&executor, &td_path, &[], &dir, "my", &contents)
+            .unwrap();
+
+        assert_eq!(
+            executor.actions(),
+            ["-gen-attr-interface-decls", "-gen-attr-interface-defs"]
+        );
+        assert!(dir.join("MyInterfacesAttrInterfaces.h.inc").exists());
+        assert!(dir.join("MyInterfacesAttrInterfaces.cpp.inc").exists());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_detect_rewriters() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_rewriters.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+include "mlir/IR/PatternBase.td"
+include "BrilOps.td"
+
+def : Pat<(Bril_AddOp $lhs, (Bril_ConstOp ConstantAttr<I64Attr, "0">)), (replaceWithValue $lhs)>;
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_rewriters, "Should detect anonymous Pat");
+        assert!(!contents.has_ops, "Patterns are not ops");
+        assert!(contents.has_any());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_detect_verifier() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_verifier.td");
+        std::fs::write(
+            &path,
+            r#"
+def Bril_AddOp : Bril_Op<"add"> {
+    let arguments = (ins AnyType:$lhs, AnyType:$rhs);
+    let hasVerifier = 1;
+}
+"#,
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_ops);
+        assert!(contents.needs_verifier_impl);
+
+        std::fs::write(
+            &path,
+            r#"
+def Bril_FuncOp : Bril_Op<"func"> {
+    let regions = (region AnyRegion:$body);
+    let hasRegionVerifier = true;
+}
+"#,
+        )
+        .unwrap();
+        assert!(detect_td_contents(&path).unwrap().needs_verifier_impl);
+
+        std::fs::write(
+            &path,
+            r#"
+def Bril_AddOp : Bril_Op<"add"> {
+    let arguments = (ins AnyType:$lhs, AnyType:$rhs);
+    let hasVerifier = 0;
+}
+"#,
+        )
+        .unwrap();
+        assert!(!detect_td_contents(&path).unwrap().needs_verifier_impl);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_detect_custom_assembly_format() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_custom_asm.td");
+        std::fs::write(
+            &path,
+            r#"
+def Bril_PtrType : Bril_Type<"Ptr", "ptr"> {
+    let parameters = (ins "Type":$pointee);
+    let hasCustomAssemblyFormat = 1;
+}
+"#,
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_types);
+        assert!(contents.needs_custom_asm);
+
+        std::fs::write(
+            &path,
+            r#"
+def Bril_PtrType : Bril_Type<"Ptr", "ptr"> {
+    let parameters = (ins "Type":$pointee);
+    let assemblyFormat = "`<` $pointee `>`";
+}
+"#,
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_types);
+        assert!(
+            !contents.needs_custom_asm,
+            "Declarative assembly formats are generated by mlir-tblgen"
+        );
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_detect_crlf_line_endings() {
+        let content = "// Bril dialect\n\
+                       def Bril_Dialect : Dialect {\n\
+                       \x20 let name = \"bril\";\n\
+                       }\n\
+                       def Bril_AddOp : Bril_Op<\"add\", [Pure]> {\n\
+                       \x20 let arguments = (ins I64:$lhs, I64:$rhs);\n\
+                       }\n";
         let temp_dir = std::env::temp_dir();
-        let path = temp_dir.join("test_ops.td");
+        let lf_path = temp_dir.join("test_detect_lf.td");
+        let crlf_path = temp_dir.join("test_detect_crlf.td");
+        std::fs::write(&lf_path, content).unwrap();
+        std::fs::write(&crlf_path, content.replace('\n', "\r\n")).unwrap();
+
+        let lf = detect_td_contents(&lf_path).unwrap();
+        let crlf = detect_td_contents(&crlf_path).unwrap();
+        assert!(crlf.has_dialect);
+        assert!(crlf.has_ops);
+        assert_eq!(crlf, lf);
+        assert_eq!(dialect_name(&crlf_path).unwrap().as_deref(), Some("bril"));
+
+        std::fs::remove_file(&lf_path).ok();
+        std::fs::remove_file(&crlf_path).ok();
+    }
+
+    #[test]
+    fn test_dialect_name() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_dialect_name.td");
+        std::fs::write(
+            &path,
+            r#"
+// let name = "commented";
+def Bril_Dialect : Dialect {
+    let description = [{ Uses `let name = "described";` }];
+    let name = "bril";
+    let cppNamespace = "::mlir::bril";
+}
+"#,
+        )
+        .unwrap();
+        assert_eq!(dialect_name(&path).unwrap().as_deref(), Some("bril"));
+
+        std::fs::write(&path, "def Bril_AddOp : Bril_Op<\"add\">;\n").unwrap();
+        assert_eq!(dialect_name(&path).unwrap(), None);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_dialect_cpp_namespace() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_dialect_cpp_namespace.td");
+
+        for namespace in ["::mlir::bril", "mlir::bril"] {
+            std::fs::write(
+                &path,
+                format!(
+                    r#"
+// let cppNamespace = "::commented";
+def Bril_Dialect : Dialect {{
+    let name = "bril";
+    let description = [{{ Not `let cppNamespace = "::described";` }}];
+    let cppNamespace = "{namespace}";
+}}
+"#
+                ),
+            )
+            .unwrap();
+            assert_eq!(
+                dialect_cpp_namespace(&path).unwrap().as_deref(),
+                Some(namespace)
+            );
+        }
+
+        std::fs::write(
+            &path,
+            "def Bril_Dialect : Dialect {\n  let name = \"bril\";\n}\n",
+        )
+        .unwrap();
+        assert_eq!(dialect_cpp_namespace(&path).unwrap(), None);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_dialect_names() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_dialect_names.td");
+        std::fs::write(
+            &path,
+            r#"
+def Bril_Dialect : Dialect {
+    let name = "bril";
+}
+def Bril_AddOp : Bril_Op<"add">;
+def Other_Dialect : Dialect {
+    let summary = [{ Not `let name = "described";` }];
+}
+"#,
+        )
+        .unwrap();
+        assert_eq!(dialect_names(&path).unwrap(), ["bril", "Other_Dialect"]);
+
+        std::fs::write(&path, "def Bril_AddOp : Bril_Op<\"add\">;\n").unwrap();
+        assert!(dialect_names(&path).unwrap().is_empty());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_detect_passes() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_passes.td");
         let mut file = std::fs::File::create(&path).unwrap();
         writeln!(
             file,
             r#"
-def Bril_AddOp : Bril_Op<"add", [Pure]> {{
-    let arguments = (ins I64:$lhs, I64:$rhs);
-    let results = (outs I64);
+include "mlir/Pass/PassBase.td"
+
+def BrilDeadCodeElimination : Pass<"my-pass", "::mlir::ModuleOp"> {{
+    let summary = "Remove unused Bril instructions";
+}}
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_passes);
+        assert!(!contents.has_ops, "Passes are not ops");
+        assert!(contents.has_any());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_generate_passes() {
+        let dir = std::env::temp_dir().join("test_tblgen_passes");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let td_path = dir.join("BrilPasses.td");
+        std::fs::write(&td_path, "def BrilDce : Pass<\"my-pass\">;\n").unwrap();
+        let contents = detect_td_contents(&td_path).unwrap();
+
+        let executor = FakeExecutor::default();
+        fake_runner()
+            .generate_for_file_with(&executor, &td_path, &[], &dir, "bril", &contents)
+            .unwrap();
+
+        assert_eq!(executor.actions(), ["-gen-pass-decls"]);
+        assert!(dir.join("BrilPassesPasses.h.inc").exists());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_detect_bytecode() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_bytecode.td");
+        std::fs::write(
+            &path,
+            r#"
+include "mlir/IR/BytecodeBase.td"
+
+def PtrType : DialectType<(type Type:$pointee)>;
+
+def BrilDialectTypes : DialectTypes<"Bril"> {
+    let elems = [PtrType];
+}
+"#,
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_bytecode);
+        assert!(
+            !contents.has_types,
+            "Bytecode encodings are not type definitions"
+        );
+        assert!(contents.has_any());
+        assert_eq!(bytecode_dialect(&path).unwrap().as_deref(), Some("Bril"));
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_generate_bytecode() {
+        let dir = std::env::temp_dir().join("test_tblgen_bytecode");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let td_path = dir.join("BrilBytecode.td");
+        std::fs::write(
+            &td_path,
+            "def BrilDialectTypes : DialectTypes<\"Bril\"> {}\n",
+        )
+        .unwrap();
+        let contents = detect_td_contents(&td_path).unwrap();
+
+        let runner = fake_runner();
+        let executor = FakeExecutor::default();
+        runner
+            .generate_for_file_with(&executor, &td_path, &[], &dir, "bril", &contents)
+            .unwrap();
+
+        let invocations = executor.invocations.borrow();
+        assert_eq!(invocations.len(), 1);
+        assert_eq!(invocations[0][0], "-gen-bytecode");
+        assert!(invocations[0].contains(&OsString::from("-bytecode-dialect=Bril")));
+        assert!(!invocations[0].contains(&OsString::from("--dialect=Bril")));
+        assert!(dir.join("BrilBytecodeBytecode.cpp.inc").exists());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_classify_records_bytecode() {
+        let records = parse_records(
+            r#"------------- Defs -----------------
+def BrilDialectTypes {	// DialectTypes
+  string dialect = "Bril";
+}
+"#,
+        );
+
+        assert!(classify_records(&records, &[]).has_bytecode);
+        assert!(!classify_records(&records, &records).has_bytecode);
+    }
+
+    #[test]
+    fn test_classify_records_passes() {
+        let records = parse_records(
+            r#"------------- Defs -----------------
+def BrilDce {	// PassBase Pass
+  string argument = "my-pass";
+}
+"#,
+        );
+
+        assert!(classify_records(&records, &[]).has_passes);
+        assert!(!classify_records(&records, &records).has_passes);
+    }
+
+    #[test]
+    fn test_classify_records_anonymous_rewriters() {
+        let records = parse_records(
+            r#"------------- Defs -----------------
+def anonymous_1 {	// Pattern Pat
+}
+def anonymous_2 {	// Pattern Pat
+}
+"#,
+        );
+
+        assert!(classify_records(&records, &records[..1]).has_rewriters);
+        assert!(!classify_records(&records[..1], &records[..1]).has_rewriters);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_generate_skips_unchanged_inputs() {
+        use crate::fake_tblgen::read_log;
+
+        let dir = std::env::temp_dir().join("test_tblgen_stamp");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let log_path = dir.join("log");
+        let tblgen_path = FakeTblgen::default()
+            .log(&log_path)
+            .write(&dir.join("mlir-tblgen"));
+
+        let td_path = dir.join("StampOps.td");
+        std::fs::write(&td_path, "def Stamp_AddOp : Stamp_Op<\"add\">;\n").unwrap();
+        let contents = detect_td_contents(&td_path).unwrap();
+
+        let runner = TblgenRunner::new(Path::new("/opt/llvm"), Some(&tblgen_path)).unwrap();
+        let invocations = || read_log(&log_path).len();
+
+        runner
+            .generate_for_file(&td_path, &[], &dir, "stamp", &contents)
+            .unwrap();
+        assert_eq!(invocations(), 2, "First run should generate decls and defs");
+
+        runner
+            .generate_for_file(&td_path, &[], &dir, "stamp", &contents)
+            .unwrap();
+        assert_eq!(invocations(), 2, "Second run should be skipped");
+
+        std::fs::write(&td_path, "def Stamp_SubOp : Stamp_Op<\"sub\">;\n").unwrap();
+        runner
+            .generate_for_file(&td_path, &[], &dir, "stamp", &contents)
+            .unwrap();
+        assert_eq!(invocations(), 4, "Changed input should regenerate");
+
+        // Replace the binary in place, as an LLVM upgrade would
+        let script = std::fs::read_to_string(&tblgen_path).unwrap();
+        std::fs::write(&tblgen_path, format!("{}# upgraded\n", script)).unwrap();
+        runner
+            .generate_for_file(&td_path, &[], &dir, "stamp", &contents)
+            .unwrap();
+        assert_eq!(invocations(), 6, "Changed mlir-tblgen should regenerate");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_records_cached() {
+        let dir = std::env::temp_dir().join("test_tblgen_records_cached");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let td_path = dir.join("StampOps.td");
+        std::fs::write(&td_path, "def Stamp_AddOp : Stamp_Op<\"add\">;\n").unwrap();
+
+        // The file has no includes, so only its own records define the op
+        let executor = FakeExecutor {
+            records: "------------- Defs -----------------\n\
+                      def Stamp_AddOp {\t// Op\n  Dialect opDialect = Stamp_Dialect;\n  \
+                      string opName = \"add\";\n}\n\
+                      def Stamp_Dialect {\t// Dialect\n  string name = \"stamp\";\n}\n"
+                .to_string(),
+            ..Default::default()
+        };
+        let runner = fake_runner().scratch_dir(&dir);
+        let invocations = || executor.invocations.borrow().len();
+
+        let records = runner.records_with(&executor, &td_path, &[]).unwrap();
+        assert_eq!(records.operations("stamp"), ["stamp.add"]);
+        assert_eq!(invocations(), 2, "First call should print both record sets");
+
+        let records = runner.records_with(&executor, &td_path, &[]).unwrap();
+        assert_eq!(records.operations("stamp"), ["stamp.add"]);
+        assert_eq!(invocations(), 2, "Second call should read the cache");
+
+        std::fs::write(&td_path, "def Stamp_SubOp : Stamp_Op<\"sub\">;\n").unwrap();
+        runner.records_with(&executor, &td_path, &[]).unwrap();
+        assert_eq!(
+            invocations(),
+            3,
+            "Changed file should print its records again"
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_generate_docs() {
+        let dir = std::env::temp_dir().join("test_tblgen_docs");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let td_path = dir.join("DocOps.td");
+        std::fs::write(
+            &td_path,
+            "def Doc_Dialect : Dialect {}\ndef Doc_AddOp : Doc_Op<\"add\">;\n",
+        )
+        .unwrap();
+        let contents = detect_td_contents(&td_path).unwrap();
+
+        let docs_dir = dir.join("docs");
+        std::fs::create_dir_all(&docs_dir).unwrap();
+        let executor = FakeExecutor::default();
+        fake_runner()
+            .docs_dir(&docs_dir)
+            .generate_for_file_with(&executor, &td_path, &[], &dir, "doc", &contents)
+            .unwrap();
+
+        let actions = executor.actions();
+        assert!(actions.iter().any(|action| action == "-gen-op-doc"));
+        assert!(actions.iter().any(|action| action == "-gen-dialect-doc"));
+        assert!(docs_dir.join("DocOps.md").exists());
+        assert!(docs_dir.join("DocOpsDialect.md").exists());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_strip_comments_preserves_strings() {
+        let stripped = strip_comments(
+            "def A : Op<\"a // b\">; // trailing\n/* block\ncomment */let s = \"/* x */\";",
+        );
+        assert_eq!(stripped, "def A : Op<\"a // b\">; \n\nlet s = \"/* x */\";");
+    }
+
+    #[test]
+    fn test_ops_in_comments_not_detected() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_ops_in_comments.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+def Bril_Dialect : Dialect {{
+    let name = "bril";
+}}
+
+// Example usage:
+//   def Foo : Bril_Op<"foo">;
+
+/* Ops are declared like this:
+   def Bar : Op<Bril_Dialect, "bar"> {{}}
+   def Baz : Bril_Type<"Baz", "baz">;
+*/
+"#
+        )
+        .unwrap();
+
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_dialect);
+        assert!(!contents.has_ops, "Should NOT detect ops inside comments");
+        assert!(
+            !contents.has_types,
+            "Should NOT detect types inside comments"
+        );
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_comment_markers_in_strings_not_stripped() {
+        let temp_dir = std::env::temp_dir();
+        let path = temp_dir.join("test_comment_markers_in_strings.td");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"
+def Bril_UrlOp : Bril_Op<"url"> {{
+    let summary = "see http://example.com /* not a comment";
 }}
+def Bril_PtrType : Bril_Type<"Ptr", "ptr">;
 "#
         )
         .unwrap();
 
-        let contents = detect_td_contents(&path).unwrap();
-        assert!(!contents.has_dialect);
-        assert!(
-            contents.has_ops,
-            "Should detect ops using custom _Op< base class"
+        let contents = detect_td_contents(&path).unwrap();
+        assert!(contents.has_ops);
+        assert!(
+            contents.has_types,
+            "Comment markers inside strings should not hide later defs"
+        );
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    const PRINTED_RECORDS: &str = r#"------------- Classes -----------------
+class Op<Dialect Op:dialect, string Op:mnemonic> {
+  Dialect opDialect = Op:dialect;
+}
+------------- Defs -----------------
+def Builtin_Dialect {	// Dialect
+  string name = "builtin";
+}
+def Bril_Dialect {	// Dialect
+  string name = "bril";
+}
+def Bril_Add0Op {	// Op Bril_Op
+  list<Trait> traits = [Pure];
+  bit hasCustomAssemblyFormat = 0;
+}
+def Bril_FuncOp {	// Op Bril_Op
+  list<Trait> traits = [FunctionOpInterface, IsolatedFromAbove];
+  bit hasRegionVerifier = 1;
+}
+def Bril_PtrType {	// Constraint TypeConstraint Type DialectType TypeDef Bril_Type
+  string mnemonic = "ptr";
+  bit hasCustomAssemblyFormat = 1;
+}
+def anonymous_42 {	// Op
+}
+"#;
+
+    #[test]
+    fn test_parse_records() {
+        let records = parse_records(PRINTED_RECORDS);
+        let names: Vec<_> = records.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Builtin_Dialect",
+                "Bril_Dialect",
+                "Bril_Add0Op",
+                "Bril_FuncOp",
+                "Bril_PtrType",
+                "anonymous_42",
+            ]
+        );
+        assert_eq!(records[2].superclasses, ["Op", "Bril_Op"]);
+        assert!(records[3].body.contains("FunctionOpInterface"));
+    }
+
+    #[test]
+    fn test_classify_records() {
+        let records = parse_records(PRINTED_RECORDS);
+        let contents = classify_records(&records, &[]);
+        assert!(contents.has_dialect);
+        assert!(contents.has_ops);
+        assert!(contents.has_types);
+        assert!(contents.has_function_interface);
+        assert!(contents.needs_custom_asm);
+        assert!(contents.needs_verifier_impl);
+        assert!(!contents.has_attrs);
+        assert!(!contents.has_enums);
+    }
+
+    #[test]
+    fn test_classify_records_excludes_included() {
+        let records = parse_records(PRINTED_RECORDS);
+        // Everything but the ops comes from an include
+        let included: Vec<_> = records
+            .iter()
+            .filter(|r| !r.superclasses.iter().any(|c| c == "Op"))
+            .cloned()
+            .collect();
+
+        let contents = classify_records(&records, &included);
+        assert!(!contents.has_dialect);
+        assert!(contents.has_ops);
+        assert!(!contents.has_types);
+        assert!(!contents.needs_custom_asm);
+    }
+
+    #[test]
+    fn test_classify_records_ignores_anonymous() {
+        let records = parse_records(PRINTED_RECORDS);
+        let named: Vec<_> = records
+            .iter()
+            .filter(|r| r.name.starts_with("Bril_") && r.superclasses.iter().any(|c| c == "Op"))
+            .cloned()
+            .collect();
+
+        let contents = classify_records(&records, &named);
+        assert!(
+            !contents.has_ops,
+            "Anonymous records should not count as ops"
+        );
+    }
+
+    #[test]
+    fn test_collect_includes_transitive() {
+        let dir = std::env::temp_dir().join("test_collect_includes");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(dir.join("include/bril")).unwrap();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+
+        std::fs::write(
+            dir.join("src/BrilOps.td"),
+            r#"
+include "mlir/IR/OpBase.td"
+include "bril/BrilBase.td"
+include "BrilLocal.td"
+// include "bril/Commented.td"
+"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("src/BrilLocal.td"), "").unwrap();
+        std::fs::write(
+            dir.join("include/bril/BrilBase.td"),
+            "include \"bril/BrilCommon.td\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("include/bril/Commented.td"), "").unwrap();
+        // Cycle back to BrilBase.td
+        std::fs::write(
+            dir.join("include/bril/BrilCommon.td"),
+            "include \"bril/BrilBase.td\"\n",
+        )
+        .unwrap();
+
+        let includes =
+            collect_includes(&dir.join("src/BrilOps.td"), &[dir.join("include")]).unwrap();
+
+        assert_eq!(
+            includes,
+            [
+                dir.join("include/bril/BrilBase.td"),
+                dir.join("src/BrilLocal.td"),
+                dir.join("include/bril/BrilCommon.td"),
+            ]
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_collect_includes_parent_dir_cycle() {
+        let dir = std::env::temp_dir().join("test_collect_includes_parent_dir_cycle");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(dir.join("a")).unwrap();
+        std::fs::write(dir.join("a/A.td"), "include \"../a/B.td\"\n").unwrap();
+        std::fs::write(dir.join("a/B.td"), "include \"../a/A.td\"\n").unwrap();
+
+        // Each pass through the cycle spells the files differently, so only
+        // their canonical paths end it
+        assert_eq!(
+            collect_includes(&dir.join("a/A.td"), &[]).unwrap(),
+            [dir.join("a/../a/B.td")]
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_parse_summaries() {
+        let records = parse_records(
+            r#"------------- Defs -----------------
+def Bril_Dialect {	// Dialect
+  string name = "bril";
+  string summary = "An MLIR dialect for \"Bril\".";
+}
+def Bril_AddOp {	// Op Bril_Op
+  string opName = "add";
+  string summary = "Integer addition";
+}
+def Bril_NopOp {	// Op Bril_Op
+  string opName = "nop";
+  string summary = "";
+}
+def Bril_ConstOp {	// Op Bril_Op
+  string opName = "const";
+  string summary = [{Constant value}];
+}
+"#,
+        );
+
+        assert_eq!(
+            parse_dialect_summary(&records, "bril").as_deref(),
+            Some("An MLIR dialect for \"Bril\".")
+        );
+        assert_eq!(parse_dialect_summary(&records, "toy"), None);
+        assert_eq!(
+            parse_op_docs(&records, &records[3..], "bril"),
+            [OpDoc {
+                name: "bril.add".to_string(),
+                summary: "Integer addition".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_flatten_includes() {
+        let dir = std::env::temp_dir().join("test_flatten_includes");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(dir.join("two")).unwrap();
+
+        std::fs::write(
+            dir.join("two/TwoDialect.td"),
+            "include \"mlir/IR/OpBase.td\"\n\ndef Two_Dialect : Dialect {\n  let name = \"two\";\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("two/TwoTypes.td"),
+            "include \"TwoDialect.td\"\n\ndef Two_PtrType : TypeDef<Two_Dialect, \"Ptr\">;\n",
+        )
+        .unwrap();
+        let td_file = dir.join("TwoOps.td");
+        std::fs::write(
+            &td_file,
+            "// include \"two/TwoDialect.td\" comes first\n\
+             include \"two/TwoDialect.td\"\n\
+             include \"two/TwoTypes.td\"\n\
+             \n\
+             def Two_AddOp : Op<Two_Dialect, \"add\">;\n",
+        )
+        .unwrap();
+
+        let flattened = flatten_includes(&td_file, &[]).unwrap();
+
+        assert!(flattened.contains("def Two_Dialect : Dialect {"));
+        assert!(flattened.contains("def Two_PtrType : TypeDef<Two_Dialect, \"Ptr\">;"));
+        assert!(flattened.contains("def Two_AddOp : Op<Two_Dialect, \"add\">;"));
+        // Each file is inlined once, even if included again by a nested file
+        assert_eq!(flattened.matches("def Two_Dialect").count(), 1);
+        assert!(!flattened.contains("\ninclude \"two/"));
+        assert!(!flattened.contains("include \"TwoDialect.td\""));
+        // Definitions keep their order, and unresolvable includes stay
+        assert!(flattened.find("def Two_Dialect") < flattened.find("def Two_PtrType"));
+        assert!(flattened.contains("include \"mlir/IR/OpBase.td\""));
+        assert!(flattened.starts_with("// include \"two/TwoDialect.td\" comes first\n"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_parse_op_names() {
+        let records = parse_records(
+            r#"------------- Defs -----------------
+def Bril_AddOp {	// Op Bril_Op
+  Dialect opDialect = Bril_Dialect;
+  string opName = "add";
+}
+def Bril_Dialect {	// Dialect
+  string name = "bril";
+}
+def Func_ReturnOp {	// Op
+  Dialect opDialect = Func_Dialect;
+  string opName = "return";
+}
+def Func_Dialect {	// Dialect
+  string name = "func";
+}
+def anonymous_1 {	// Op
+  Dialect opDialect = Bril_Dialect;
+  string opName = "hidden";
+}
+"#,
         );
-        assert!(!contents.has_types);
 
-        std::fs::remove_file(&path).ok();
+        assert_eq!(parse_op_names(&records, &[]), ["bril.add", "func.return"]);
+        assert_eq!(parse_op_names(&records, &records[2..]), ["bril.add"]);
     }
 
     #[test]
-    fn test_detect_types() {
-        let temp_dir = std::env::temp_dir();
-        let path = temp_dir.join("test_types.td");
-        let mut file = std::fs::File::create(&path).unwrap();
-        writeln!(
-            file,
-            r#"
-def Bril_PtrType : Bril_Type<"Ptr", "ptr"> {{
-    let mnemonic = "ptr";
-}}
-"#
-        )
-        .unwrap();
+    fn test_parse_op_defs() {
+        let records = parse_records(
+            r#"------------- Defs -----------------
+def Bril_AddOp {	// Op Bril_Op
+  dag arguments = (ins I64:$lhs, I64:$rhs);
+  Dialect opDialect = Bril_Dialect;
+  string opName = "add";
+  dag regions = (region);
+  dag results = (outs I64);
+  dag successors = (successor);
+}
+def Bril_CallOp {	// Op Bril_Op
+  dag arguments = (ins FlatSymbolRefAttr:$callee, anonymous_3:$args);
+  Dialect opDialect = Bril_Dialect;
+  string opName = "call";
+  dag regions = (region);
+  dag results = (outs anonymous_3);
+  dag successors = (successor);
+}
+def Bril_ConstOp {	// Op Bril_Op
+  dag arguments = (ins anonymous_4:$value);
+  Dialect opDialect = Bril_Dialect;
+  string opName = "const";
+  dag regions = (region);
+  dag results = (outs I64:$result);
+  dag successors = (successor);
+}
+def Bril_Dialect {	// Dialect
+  string name = "bril";
+}
+def Bril_FuncOp {	// Op Bril_Op
+  dag arguments = (ins SymbolNameAttr:$sym_name);
+  Dialect opDialect = Bril_Dialect;
+  string opName = "func";
+  dag regions = (region AnyRegion:$body, anonymous_5:$rest);
+  dag results = (outs);
+  dag successors = (successor);
+}
+def Bril_JmpOp {	// Op Bril_Op
+  dag arguments = (ins);
+  Dialect opDialect = Bril_Dialect;
+  string opName = "jmp";
+  dag regions = (region);
+  dag results = (outs);
+  dag successors = (successor AnySuccessor:$dest);
+}
+def FlatSymbolRefAttr {	// Constraint AttrConstraint Attr
+}
+def I64 {	// Constraint TypeConstraint Type
+}
+def I64Attr {	// Constraint AttrConstraint Attr
+}
+def SymbolNameAttr {	// Constraint AttrConstraint Attr
+}
+def anonymous_3 {	// Constraint TypeConstraint Variadic
+}
+def anonymous_4 {	// Arg
+  Constraint constraint = I64Attr;
+}
+def anonymous_5 {	// Region VariadicRegion
+}
+"#,
+        );
 
-        let contents = detect_td_contents(&path).unwrap();
-        assert!(!contents.has_dialect);
-        assert!(!contents.has_ops);
-        assert!(
-            contents.has_types,
-            "Should detect types using custom _Type< base class"
+        assert_eq!(
+            parse_op_defs(&records, &[]),
+            [
+                OpDef {
+                    name: "bril.add".to_string(),
+                    operands: vec!["lhs".to_string(), "rhs".to_string()],
+                    attributes: Vec::new(),
+                    results: vec![String::new()],
+                    regions: Vec::new(),
+                    fixed_arity: true,
+                },
+                OpDef {
+                    name: "bril.call".to_string(),
+                    operands: Vec::new(),
+                    attributes: vec!["callee".to_string()],
+                    results: vec![String::new()],
+                    regions: Vec::new(),
+                    fixed_arity: false,
+                },
+                OpDef {
+                    name: "bril.const".to_string(),
+                    operands: Vec::new(),
+                    attributes: vec!["value".to_string()],
+                    results: vec!["result".to_string()],
+                    regions: Vec::new(),
+                    fixed_arity: true,
+                },
+                OpDef {
+                    name: "bril.func".to_string(),
+                    operands: Vec::new(),
+                    attributes: vec!["sym_name".to_string()],
+                    results: Vec::new(),
+                    regions: vec!["body".to_string()],
+                    fixed_arity: false,
+                },
+                OpDef {
+                    name: "bril.jmp".to_string(),
+                    operands: Vec::new(),
+                    attributes: Vec::new(),
+                    results: Vec::new(),
+                    regions: Vec::new(),
+                    fixed_arity: false,
+                },
+            ]
         );
+    }
 
-        std::fs::remove_file(&path).ok();
+    #[test]
+    fn test_parse_op_classes() {
+        let records = parse_records(
+            r#"------------- Defs -----------------
+def Bril_AddOp {	// Op Bril_Op
+  string cppNamespace = "::mlir::bril";
+  Dialect opDialect = Bril_Dialect;
+  string opName = "add";
+}
+def Bril_Dialect {	// Dialect
+  string name = "bril";
+}
+def ConstOp {	// Op
+  Dialect opDialect = Bril_Dialect;
+  string opName = "const";
+}
+def Func_ReturnOp {	// Op
+  string cppNamespace = "::mlir::func";
+  Dialect opDialect = Func_Dialect;
+  string opName = "return";
+}
+def Func_Dialect {	// Dialect
+  string name = "func";
+}
+"#,
+        );
+
+        assert_eq!(
+            parse_op_classes(&records, &records[3..]),
+            [
+                ("bril.add".to_string(), "::mlir::bril::AddOp".to_string()),
+                ("bril.const".to_string(), "ConstOp".to_string()),
+            ]
+        );
     }
 
     #[test]
-    fn test_detect_combined() {
-        let temp_dir = std::env::temp_dir();
-        let path = temp_dir.join("test_combined.td");
-        let mut file = std::fs::File::create(&path).unwrap();
-        writeln!(
-            file,
-            r#"
-def Bril_Dialect : Dialect {{
-    let name = "bril";
-}}
+    fn test_parse_op_signatures() {
+        let records = parse_records(
+            r#"------------- Defs -----------------
+def Bril_CallOp {	// Op Bril_Op
+  dag arguments = (ins FlatSymbolRefAttr:$callee, anonymous_3:$args, anonymous_5:$arg_attrs);
+  Dialect opDialect = Bril_Dialect;
+  string opName = "call";
+  dag results = (outs anonymous_4);
+}
+def Bril_Dialect {	// Dialect
+  string name = "bril";
+}
+def FlatSymbolRefAttr {	// Constraint AttrConstraint Attr
+}
+def anonymous_2 {	// Constraint TypeConstraint AnyTypeOf
+  string summary = "any Bril type";
+}
+def anonymous_3 {	// Constraint TypeConstraint Variadic
+  TypeConstraint baseType = anonymous_2;
+}
+def anonymous_4 {	// Constraint TypeConstraint Optional
+  TypeConstraint baseType = anonymous_2;
+}
+def anonymous_5 {	// Constraint AttrConstraint Attr OptionalAttr
+  string summary = "array of dictionaries";
+}
+"#,
+        );
 
-def Bril_PtrType : Bril_Type<"Ptr", "ptr"> {{
-    let mnemonic = "ptr";
-}}
+        assert_eq!(
+            parse_op_signatures(&records),
+            [OpSignature {
+                name: "bril.call".to_string(),
+                operands: vec![("args".to_string(), "Variadic<any Bril type>".to_string())],
+                results: vec![(String::new(), "Optional<any Bril type>".to_string())],
+                attributes: vec![
+                    ("callee".to_string(), "FlatSymbolRefAttr".to_string()),
+                    ("arg_attrs".to_string(), "array of dictionaries".to_string()),
+                ],
+                variadic_operands: vec![0],
+                variadic_results: vec![0],
+            }]
+        );
+    }
 
-def Bril_AddOp : Bril_Op<"add", [Pure]> {{
-    let arguments = (ins I64:$lhs, I64:$rhs);
-    let results = (outs I64);
-}}
-"#
+    #[cfg(unix)]
+    #[test]
+    fn test_describe_operations_bril() {
+        let dir = std::env::temp_dir().join("test_describe_operations_bril");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(dir.join("bin")).unwrap();
+
+        // Fake mlir-tblgen printing the records of `bril.add`
+        let records_path = dir.join("records.txt");
+        std::fs::write(
+            &records_path,
+            "------------- Classes -----------------\n------------- Defs -----------------\ndef Bril_AddOp {\t// Op Bril_Op\n  dag arguments = (ins I64:$lhs, I64:$rhs);\n  Dialect opDialect = Bril_Dialect;\n  string opName = \"add\";\n  dag results = (outs I64);\n}\ndef Bril_Dialect {\t// Dialect\n  string name = \"bril\";\n}\ndef I64 {\t// Constraint TypeConstraint Type I\n  string summary = \"64-bit signless integer\";\n}\n",
         )
         .unwrap();
+        FakeTblgen::default()
+            .records(&records_path)
+            .write(&dir.join("bin").join("mlir-tblgen"));
 
-        let contents = detect_td_contents(&path).unwrap();
-        assert!(contents.has_dialect);
-        assert!(contents.has_ops);
-        assert!(contents.has_types);
-        assert!(contents.has_any());
+        let bril_dir =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/bril_dialect/src/dialect/bril");
+        let ops = describe_operations(
+            &bril_dir.join("BrilOps.td"),
+            std::slice::from_ref(&bril_dir),
+            &dir,
+        )
+        .unwrap();
+        let add = ops.iter().find(|op| op.name == "bril.add").unwrap();
+        assert_eq!(
+            add.operands,
+            [
+                ("lhs".to_string(), "I64".to_string()),
+                ("rhs".to_string(), "I64".to_string()),
+            ]
+        );
+        assert_eq!(add.results, [(String::new(), "I64".to_string())]);
+        assert!(add.attributes.is_empty());
+        assert!(add.variadic_operands.is_empty());
 
-        std::fs::remove_file(&path).ok();
+        std::fs::remove_dir_all(&dir).ok();
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_detect_direct_op() {
-        // Test detection of direct Op< usage (not via custom base class)
-        let temp_dir = std::env::temp_dir();
-        let path = temp_dir.join("test_direct_op.td");
-        let mut file = std::fs::File::create(&path).unwrap();
-        writeln!(
-            file,
-            r#"
-def MyOp : Op<MyDialect, "my_op"> {{
-    let results = (outs I64);
-}}
-"#
+    fn test_list_operations_llvm_tblgen_only() {
+        let dir = std::env::temp_dir().join("test_list_operations_llvm_tblgen_only");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(dir.join("bin")).unwrap();
+
+        // A minimal LLVM installation whose only TableGen binary is
+        // llvm-tblgen, printing the records of `bril.add`
+        let records_path = dir.join("records.txt");
+        std::fs::write(
+            &records_path,
+            "------------- Classes -----------------\n------------- Defs -----------------\ndef Bril_AddOp {\t// Op Bril_Op\n  Dialect opDialect = Bril_Dialect;\n  string opName = \"add\";\n}\ndef Bril_Dialect {\t// Dialect\n  string name = \"bril\";\n}\n",
         )
         .unwrap();
+        FakeTblgen::default()
+            .records(&records_path)
+            .write(&dir.join("bin").join("llvm-tblgen"));
 
-        let contents = detect_td_contents(&path).unwrap();
-        assert!(contents.has_ops, "Should detect direct Op< usage");
+        let bril_dir =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/bril_dialect/src/dialect/bril");
+        let ops = list_operations(
+            &bril_dir.join("BrilOps.td"),
+            std::slice::from_ref(&bril_dir),
+            &dir,
+        )
+        .unwrap();
+        assert_eq!(ops, ["bril.add"]);
 
-        std::fs::remove_file(&path).ok();
+        std::fs::remove_dir_all(&dir).ok();
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_detect_function_interface() {
-        let temp_dir = std::env::temp_dir();
-        let path = temp_dir.join("test_func_interface.td");
-        let mut file = std::fs::File::create(&path).unwrap();
-        writeln!(
-            file,
-            r#"
-include "mlir/Interfaces/FunctionInterfaces.td"
+    fn test_list_operations_bril() {
+        let dir = std::env::temp_dir().join("test_list_operations_bril");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(dir.join("bin")).unwrap();
 
-def Bril_FuncOp : Bril_Op<"func", [
-    FunctionOpInterface,
-    IsolatedFromAbove
-]> {{
-    let arguments = (ins);
-}}
-"#
+        // Fake mlir-tblgen printing the records of the bril arithmetic ops
+        let mut records = String::from(
+            "------------- Classes -----------------\n------------- Defs -----------------\n",
+        );
+        for (def, name) in [
+            ("Add", "add"),
+            ("Div", "div"),
+            ("Mul", "mul"),
+            ("Sub", "sub"),
+        ] {
+            records.push_str(&format!(
+                "def Bril_{}Op {{\t// Op Bril_Op\n  Dialect opDialect = Bril_Dialect;\n  string opName = \"{}\";\n}}\n",
+                def, name
+            ));
+        }
+        records.push_str("def Bril_Dialect {\t// Dialect\n  string name = \"bril\";\n}\n");
+        let records_path = dir.join("records.txt");
+        std::fs::write(&records_path, records).unwrap();
+        FakeTblgen::default()
+            .records(&records_path)
+            .write(&dir.join("bin").join("mlir-tblgen"));
+
+        let bril_dir =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/bril_dialect/src/dialect/bril");
+        let ops = list_operations(
+            &bril_dir.join("BrilOps.td"),
+            std::slice::from_ref(&bril_dir),
+            &dir,
         )
         .unwrap();
+        for op in ["bril.add", "bril.sub", "bril.mul", "bril.div"] {
+            assert!(ops.iter().any(|name| name == op), "missing {}", op);
+        }
 
-        let contents = detect_td_contents(&path).unwrap();
-        assert!(
-            contents.has_function_interface,
-            "Should detect FunctionOpInterface usage"
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_parse_type_defs() {
+        let records = parse_records(
+            r#"------------- Defs -----------------
+def Bril_Dialect {	// Dialect
+  string name = "bril";
+  string cppNamespace = "::mlir::bril";
+}
+def Other_Dialect {	// Dialect
+  string name = "other";
+}
+def Bril_PtrType {	// Constraint TypeConstraint Type DialectType AttrOrTypeDef TypeDef Bril_Type
+  Dialect dialect = Bril_Dialect;
+  string cppClassName = "PtrType";
+  dag parameters = (ins "::mlir::Type":$pointeeType);
+}
+def Bril_VecType {	// Constraint TypeConstraint Type DialectType AttrOrTypeDef TypeDef Bril_Type
+  Dialect dialect = Bril_Dialect;
+  string cppClassName = "VecType";
+  dag parameters = (ins "Type":$elementType, "unsigned":$width);
+}
+def Bril_UnitType {	// Constraint TypeConstraint Type DialectType AttrOrTypeDef TypeDef Bril_Type
+  Dialect dialect = Bril_Dialect;
+  string cppClassName = "UnitType";
+  dag parameters = (ins);
+}
+def Bril_ShapeType {	// Constraint TypeConstraint Type DialectType AttrOrTypeDef TypeDef Bril_Type
+  Dialect dialect = Bril_Dialect;
+  string cppClassName = "ShapeType";
+  dag parameters = (ins anonymous_7:$shape);
+}
+def Bril_StrType {	// Constraint TypeConstraint Type DialectType AttrOrTypeDef TypeDef Bril_Type
+  Dialect dialect = Bril_Dialect;
+  string cppClassName = "StrType";
+  dag parameters = (ins "::llvm::StringRef":$value);
+}
+def Other_FooType {	// Constraint TypeConstraint Type DialectType AttrOrTypeDef TypeDef
+  Dialect dialect = Other_Dialect;
+  string cppClassName = "FooType";
+  dag parameters = (ins);
+}
+"#,
         );
-        assert!(contents.has_ops, "Should also detect ops");
 
-        std::fs::remove_file(&path).ok();
+        assert_eq!(
+            parse_type_defs(&records, "bril"),
+            [
+                TypeDef {
+                    class_name: "PtrType".to_string(),
+                    parameters: vec![TypeParameter {
+                        name: "pointeeType".to_string(),
+                        kind: ParameterKind::Type,
+                    }],
+                },
+                TypeDef {
+                    class_name: "VecType".to_string(),
+                    parameters: vec![
+                        TypeParameter {
+                            name: "elementType".to_string(),
+                            kind: ParameterKind::Type,
+                        },
+                        TypeParameter {
+                            name: "width".to_string(),
+                            kind: ParameterKind::Scalar {
+                                c_type: "unsigned",
+                                rust_type: "u32",
+                            },
+                        },
+                    ],
+                },
+                TypeDef {
+                    class_name: "UnitType".to_string(),
+                    parameters: vec![],
+                },
+            ]
+        );
     }
 
     #[test]
-    fn test_detect_no_function_interface() {
-        let temp_dir = std::env::temp_dir();
-        let path = temp_dir.join("test_no_func_interface.td");
-        let mut file = std::fs::File::create(&path).unwrap();
-        writeln!(
-            file,
-            r#"
-def Bril_AddOp : Bril_Op<"add", [Pure]> {{
-    let arguments = (ins I64:$lhs, I64:$rhs);
-    let results = (outs I64);
-}}
-"#
-        )
-        .unwrap();
+    fn test_parameter_kind_from_cpp_type() {
+        assert_eq!(
+            ParameterKind::from_cpp_type("::mlir::Type"),
+            Some(ParameterKind::Type)
+        );
+        assert_eq!(
+            ParameterKind::from_cpp_type("mlir::Attribute"),
+            Some(ParameterKind::Attribute)
+        );
+        assert_eq!(
+            ParameterKind::from_cpp_type("int64_t"),
+            Some(ParameterKind::Scalar {
+                c_type: "int64_t",
+                rust_type: "i64"
+            })
+        );
+        assert_eq!(ParameterKind::from_cpp_type("::llvm::StringRef"), None);
+        assert_eq!(
+            ParameterKind::from_attr_cpp_type("::llvm::StringRef"),
+            Some(ParameterKind::String)
+        );
+        assert_eq!(
+            ParameterKind::from_attr_cpp_type("unsigned"),
+            ParameterKind::from_cpp_type("unsigned")
+        );
+    }
 
-        let contents = detect_td_contents(&path).unwrap();
-        assert!(
-            !contents.has_function_interface,
-            "Should NOT detect FunctionOpInterface when not present"
+    #[test]
+    fn test_parse_attr_defs() {
+        let records = parse_records(
+            r#"------------- Defs -----------------
+def Bril_Dialect {	// Dialect
+  string name = "bril";
+}
+def anonymous_512 {	// AttrOrTypeParameter StringRefParameter
+  string allocator = [{$_dst = $_allocator.copyInto($_self);}];
+  string cppType = "::llvm::StringRef";
+}
+def anonymous_513 {	// AttrOrTypeParameter ArrayRefParameter
+  string cppType = "::llvm::ArrayRef<int64_t>";
+}
+def Bril_LabelAttr {	// Constraint AttrConstraint Attr DialectAttr AttrOrTypeDef AttrDef Bril_Attr
+  Dialect dialect = Bril_Dialect;
+  string cppClassName = "LabelAttr";
+  dag parameters = (ins anonymous_512:$name);
+}
+def Bril_OffsetAttr {	// Constraint AttrConstraint Attr DialectAttr AttrOrTypeDef AttrDef Bril_Attr
+  Dialect dialect = Bril_Dialect;
+  string cppClassName = "OffsetAttr";
+  dag parameters = (ins "int64_t":$offset, "::mlir::Type":$type);
+}
+def Bril_ShapeAttr {	// Constraint AttrConstraint Attr DialectAttr AttrOrTypeDef AttrDef Bril_Attr
+  Dialect dialect = Bril_Dialect;
+  string cppClassName = "ShapeAttr";
+  dag parameters = (ins anonymous_513:$shape);
+}
+def Bril_PtrType {	// Constraint TypeConstraint Type DialectType AttrOrTypeDef TypeDef Bril_Type
+  Dialect dialect = Bril_Dialect;
+  string cppClassName = "PtrType";
+  dag parameters = (ins "::mlir::Type":$pointeeType);
+}
+"#,
         );
 
-        std::fs::remove_file(&path).ok();
+        assert_eq!(
+            parse_attr_defs(&records, "bril"),
+            (
+                vec![
+                    AttrDef {
+                        class_name: "LabelAttr".to_string(),
+                        parameters: vec![TypeParameter {
+                            name: "name".to_string(),
+                            kind: ParameterKind::String,
+                        }],
+                    },
+                    AttrDef {
+                        class_name: "OffsetAttr".to_string(),
+                        parameters: vec![
+                            TypeParameter {
+                                name: "offset".to_string(),
+                                kind: ParameterKind::Scalar {
+                                    c_type: "int64_t",
+                                    rust_type: "i64",
+                                },
+                            },
+                            TypeParameter {
+                                name: "type".to_string(),
+                                kind: ParameterKind::Type,
+                            },
+                        ],
+                    },
+                ],
+                vec!["ShapeAttr".to_string()],
+            )
+        );
     }
 
     #[test]
-    fn test_class_definitions_not_detected_as_ops() {
-        // Test that base class definitions (using `class`) are NOT detected as ops.
-        // Only actual op definitions (using `def`) should count.
-        let temp_dir = std::env::temp_dir();
-        let path = temp_dir.join("test_class_not_op.td");
-        let mut file = std::fs::File::create(&path).unwrap();
-        writeln!(
+    fn test_parse_enum_defs() {
+        let records = parse_records(
+            r#"------------- Defs -----------------
+def Arith_CmpIPredicateAttr {	// EnumAttrInfo IntEnumAttr I64EnumAttr
+  string className = "CmpIPredicate";
+  list<EnumAttrCaseInfo> enumerants = [];
+}
+def Bril_CmpEq {	// EnumAttrCaseInfo IntEnumAttrCaseBase I64EnumAttrCase
+  string symbol = "eq";
+  int value = 0;
+}
+def Bril_CmpLt {	// EnumAttrCaseInfo IntEnumAttrCaseBase I64EnumAttrCase
+  string symbol = "less_than";
+  int value = 2;
+}
+def Bril_CmpPredicate {	// EnumAttrInfo IntEnumAttr I64EnumAttr
+  string className = "CmpPredicate";
+  list<EnumAttrCaseInfo> enumerants = [Bril_CmpEq, Bril_CmpLt];
+}
+def Bril_Flags {	// EnumAttrInfo BitEnumAttr I32BitEnumAttr
+  string className = "Flags";
+  list<BitEnumAttrCaseBase> enumerants = [];
+}
+"#,
+        );
+
+        assert_eq!(
+            parse_enum_defs(&records, &records[..1]),
+            [EnumDef {
+                class_name: "CmpPredicate".to_string(),
+                bitwidth: 64,
+                cases: vec![
+                    EnumCase {
+                        symbol: "eq".to_string(),
+                        value: 0,
+                    },
+                    EnumCase {
+                        symbol: "less_than".to_string(),
+                        value: 2,
+                    },
+                ],
+            }]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_tblgen_failed_reports_command() {
+        let dir = std::env::temp_dir().join("test_tblgen_failed");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // Fake mlir-tblgen that always fails
+        let tblgen_path = dir.join("mlir-tblgen");
+        FakeTblgen::default()
+            .fail(
+                "",
+                "error: bad op
+",
+                1,
+            )
+            .write(&tblgen_path);
+
+        let td_path = dir.join("Bad Ops.td");
+        std::fs::write(&td_path, "def Bad_AddOp : Bad_Op<\"add\">;\n").unwrap();
+        let contents = detect_td_contents(&td_path).unwrap();
+
+        let runner = TblgenRunner::new(Path::new("/opt/llvm"), Some(&tblgen_path)).unwrap();
+        let err = runner
+            .generate_for_file(&td_path, &[], &dir, "bad", &contents)
+            .unwrap_err();
+
+        let Error::TblgenFailed {
             file,
-            r#"
-def Bril_Dialect : Dialect {{
-    let name = "bril";
-}}
+            action,
+            tblgen,
+            args,
+            diagnostics,
+        } = &err
+        else {
+            panic!("expected TblgenFailed, got {err:?}");
+        };
+        assert_eq!(file, &td_path);
+        assert_eq!(action, "-gen-op-decls");
+        assert_eq!(tblgen, &tblgen_path);
+        assert_eq!(args[0], "-gen-op-decls");
+        assert_eq!(diagnostics, "stderr:\nerror: bad op");
 
-// This is a base class definition, NOT an op
-class Bril_Op<string mnemonic, list<Trait> traits = []> :
-    Op<Bril_Dialect, mnemonic, traits>;
+        let message = err.to_string();
+        assert!(message.contains(&format!("failed for {}", td_path.display())));
+        assert!(message.contains(&format!(
+            "{} -gen-op-decls '{}' -o",
+            tblgen_path.display(),
+            td_path.display()
+        )));
 
-// Similarly, base class for types should not count
-class Bril_Type<string name, string typeMnemonic, list<Trait> traits = []> :
-    TypeDef<Bril_Dialect, name, traits>;
-"#
-        )
-        .unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+    }
 
-        let contents = detect_td_contents(&path).unwrap();
-        assert!(contents.has_dialect, "Should detect dialect");
-        assert!(
-            !contents.has_ops,
-            "Should NOT detect ops from class definitions"
-        );
-        assert!(
-            !contents.has_types,
-            "Should NOT detect types from class definitions"
-        );
+    #[cfg(unix)]
+    #[test]
+    fn test_tblgen_failed_reports_stdout_and_status() {
+        let dir = std::env::temp_dir().join("test_tblgen_failed_stdout");
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
 
-        std::fs::remove_file(&path).ok();
+        // Broken TD file: the op class is never defined
+        let td_path = dir.join("BrokenOps.td");
+        std::fs::write(&td_path, "def Broken_AddOp : Broken_Op<\"add\">;\n").unwrap();
+        let contents = detect_td_contents(&td_path).unwrap();
+
+        // Fake mlir-tblgen reporting its diagnostic on stdout only
+        let tblgen_path = dir.join("mlir-tblgen");
+        FakeTblgen::default()
+            .fail(
+                "error: Variable not defined: 'Broken_Op'
+",
+                "",
+                1,
+            )
+            .write(&tblgen_path);
+
+        let runner = TblgenRunner::new(Path::new("/opt/llvm"), Some(&tblgen_path)).unwrap();
+        let message = runner
+            .generate_for_file(&td_path, &[], &dir, "broken", &contents)
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("stdout:\nerror: Variable not defined: 'Broken_Op'"));
+        assert!(!message.contains("stderr:"));
+
+        // Fake mlir-tblgen failing without any output
+        FakeTblgen::default().fail("", "", 3).write(&tblgen_path);
+        let message = runner
+            .generate_for_file(&td_path, &[], &dir, "broken", &contents)
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("exited with status code 3 and no output"));
+
+        std::fs::remove_dir_all(&dir).ok();
     }
 }
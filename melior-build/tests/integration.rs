@@ -15,8 +15,8 @@ fn test_dialect_builder_multiple_files() {
     use melior_build::DialectBuilder;
 
     let _builder = DialectBuilder::new("multi")
-        .td_files(&["file1.td", "file2.td", "file3.td"])
-        .include_dirs(&["/include1", "/include2"]);
+        .td_files(["file1.td", "file2.td", "file3.td"])
+        .include_dirs(["/include1", "/include2"]);
 }
 
 #[test]
@@ -26,7 +26,7 @@ fn test_dialect_builder_cpp_files() {
     let _builder = DialectBuilder::new("my_dialect")
         .td_file("dialect.td")
         .cpp_file("src/Verifiers.cpp")
-        .cpp_files(&["src/Canonicalize.cpp", "src/Builders.cpp"]);
+        .cpp_files(["src/Canonicalize.cpp", "src/Builders.cpp"]);
 }
 
 #[test]
@@ -35,19 +35,15 @@ fn test_cpp_generation() {
     let output_path = temp_dir.join("test_dialect_capi.cpp");
 
     // Simulate a TD file "OperandTestOps.td" containing dialect and ops
-    let generated = melior_build::tblgen::GeneratedFiles {
-        dialect_stem: Some("OperandTestOps".to_string()),
-        ops_stem: Some("OperandTestOps".to_string()),
-        types_stem: None,
-        attrs_stem: None,
-        enums_stem: None,
-        use_function_interface: false,
-    };
+    let mut generated = melior_build::tblgen::GeneratedFiles::default();
+    generated.dialect_stem = Some("OperandTestOps".to_string());
+    generated.ops_stem = Some("OperandTestOps".to_string());
     melior_build::cpp_gen::generate_cpp_registration(
         "operand_test",
         "mlir::operand_test",
         &generated,
-        Some("operand_test"), // Subdirectory based on namespace
+        &melior_build::CodegenOptions::default(),
+        Some(std::path::Path::new("operand_test")), // Subdirectory based on namespace
         &output_path,
     )
     .unwrap();
@@ -72,18 +68,14 @@ fn test_cpp_generation_no_subdir() {
     let output_path = temp_dir.join("test_dialect_no_subdir_capi.cpp");
 
     // Simulate a TD file "SimpleOps.td" containing dialect and ops
-    let generated = melior_build::tblgen::GeneratedFiles {
-        dialect_stem: Some("SimpleOps".to_string()),
-        ops_stem: Some("SimpleOps".to_string()),
-        types_stem: None,
-        attrs_stem: None,
-        enums_stem: None,
-        use_function_interface: false,
-    };
+    let mut generated = melior_build::tblgen::GeneratedFiles::default();
+    generated.dialect_stem = Some("SimpleOps".to_string());
+    generated.ops_stem = Some("SimpleOps".to_string());
     melior_build::cpp_gen::generate_cpp_registration(
         "simple",
         "mlir::simple",
         &generated,
+        &melior_build::CodegenOptions::default(),
         None, // No subdirectory
         &output_path,
     )
@@ -106,7 +98,13 @@ fn test_rust_ffi_generation() {
     let temp_dir = std::env::temp_dir();
     let output_path = temp_dir.join("test_register.rs");
 
-    melior_build::rust_gen::generate_rust_ffi("operand_test", &output_path).unwrap();
+    melior_build::rust_gen::generate_rust_ffi(
+        "operand_test",
+        &melior_build::tblgen::GeneratedFiles::default(),
+        &melior_build::CodegenOptions::default(),
+        &output_path,
+    )
+    .unwrap();
 
     let content = std::fs::read_to_string(&output_path).unwrap();
 
@@ -125,7 +123,13 @@ fn test_rust_ffi_syntax() {
     let temp_dir = std::env::temp_dir();
     let output_path = temp_dir.join("syntax_test.rs");
 
-    melior_build::rust_gen::generate_rust_ffi("my_dialect", &output_path).unwrap();
+    melior_build::rust_gen::generate_rust_ffi(
+        "my_dialect",
+        &melior_build::tblgen::GeneratedFiles::default(),
+        &melior_build::CodegenOptions::default(),
+        &output_path,
+    )
+    .unwrap();
 
     let content = std::fs::read_to_string(&output_path).unwrap();
 
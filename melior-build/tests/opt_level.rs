@@ -0,0 +1,92 @@
+//! Tests that `DialectBuilder::opt_level` overrides the Cargo profile level.
+//!
+//! cc reads the profile level from the `OPT_LEVEL` environment variable, so
+//! each build runs in a child process of this test with its own environment,
+//! and the planned C++ compile is read from its dry-run output.
+
+use melior_build::DialectBuilder;
+use std::{path::Path, process::Command};
+
+/// Set in the child process to the level to build with, or `profile`
+const CHILD_VAR: &str = "MELIOR_BUILD_TEST_OPT_LEVEL";
+
+/// Returns the target triple of the host running the tests, which Cargo only
+/// passes to build scripts.
+fn host_target() -> String {
+    use std::env::consts::{ARCH, OS};
+
+    match OS {
+        "linux" if cfg!(target_env = "musl") => format!("{}-unknown-linux-musl", ARCH),
+        "linux" => format!("{}-unknown-linux-gnu", ARCH),
+        "macos" => format!("{}-apple-darwin", ARCH),
+        "windows" if cfg!(target_env = "msvc") => format!("{}-pc-windows-msvc", ARCH),
+        "windows" => format!("{}-pc-windows-gnu", ARCH),
+        os => format!("{}-unknown-{}", ARCH, os),
+    }
+}
+
+/// Plans a build of a minimal dialect in `dir` and prints the planned steps.
+fn dry_run(dir: &Path, opt_level: Option<u8>) {
+    let td_file = dir.join("Test.td");
+    std::fs::write(
+        &td_file,
+        "def Test_Dialect : Dialect {\n  let name = \"test\";\n}\n",
+    )
+    .unwrap();
+
+    let mut builder = DialectBuilder::new("test")
+        .td_file(&td_file)
+        .output_dir(dir)
+        .debug_info(false)
+        .dry_run(true);
+    if let Some(level) = opt_level {
+        builder = builder.opt_level(level);
+    }
+    builder.build().unwrap();
+}
+
+/// Returns the arguments of the planned C++ compile of a child process that
+/// builds with `opt_level` under the Cargo profile level `0`.
+fn planned_compile(dir: &Path, opt_level: &str) -> Vec<String> {
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["test_opt_level_overrides_profile", "--exact", "--nocapture"])
+        .env(CHILD_VAR, opt_level)
+        .env("OUT_DIR", dir)
+        .env("OPT_LEVEL", "0")
+        .env("TARGET", host_target())
+        .env("HOST", host_target())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+
+    stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("cargo:warning=melior-build: would compile "))
+        .unwrap_or_else(|| panic!("no planned compile in {}", stdout))
+        .split_whitespace()
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn test_opt_level_overrides_profile() {
+    if let Ok(level) = std::env::var(CHILD_VAR) {
+        let dir = Path::new(&std::env::var_os("OUT_DIR").unwrap()).to_path_buf();
+        dry_run(&dir, level.parse().ok());
+        return;
+    }
+
+    let dir = std::env::temp_dir().join("test_opt_level_overrides_profile");
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let profile = planned_compile(&dir, "profile");
+    assert!(profile.iter().any(|arg| arg == "-O0"));
+
+    let overridden = planned_compile(&dir, "2");
+    assert!(overridden.iter().any(|arg| arg == "-O2"));
+    assert!(!overridden.iter().any(|arg| arg == "-O0"));
+
+    std::fs::remove_dir_all(&dir).ok();
+}